@@ -1,10 +1,13 @@
 use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use syn::parse::{Error, Parse, ParseStream, Result};
 use syn::punctuated::Punctuated;
-use syn::{braced, token, Ident, Token};
-use wasmtime_wit_bindgen::{Opts, Ownership, TrappableError};
+use syn::{braced, bracketed, parenthesized, token, Ident, Token};
+use wasmtime_wit_bindgen::{
+    Direction, ListRepresentation, ModulePathStyle, Opts, Ownership, TrappableError,
+};
 use wit_parser::{PackageId, Resolve, UnresolvedPackage, WorldId};
 
 pub struct Config {
@@ -15,15 +18,17 @@ pub struct Config {
 }
 
 pub fn expand(input: &Config) -> Result<TokenStream> {
-    if !cfg!(feature = "async") && input.opts.async_ {
+    if !cfg!(feature = "async") && (input.opts.imports_async() || input.opts.exports_async()) {
         return Err(Error::new(
             Span::call_site(),
             "cannot enable async bindings unless `async` crate feature is active",
         ));
     }
 
-    let src = input.opts.generate(&input.resolve, input.world);
-    let mut contents = src.parse::<TokenStream>().unwrap();
+    let mut contents = input
+        .opts
+        .generate_tokens(&input.resolve, input.world)
+        .map_err(|e| Error::new(Span::call_site(), format!("{e:?}")))?;
 
     // Include a dummy `include_str!` for any files we read so rustc knows that
     // we depend on the contents of those files.
@@ -72,6 +77,8 @@ impl Parse for Config {
                     }
                     Opt::Tracing(val) => opts.tracing = val,
                     Opt::Async(val) => opts.async_ = val,
+                    Opt::ImportAsync(val) => opts.import_async = val,
+                    Opt::ExportAsync(val) => opts.export_async = val,
                     Opt::TrappableErrorType(val) => opts.trappable_error_type = val,
                     Opt::Ownership(val) => opts.ownership = val,
                     Opt::Interfaces(s) => {
@@ -100,6 +107,40 @@ impl Parse for Config {
                         opts.only_interfaces = true;
                     }
                     Opt::With(val) => opts.with.extend(val),
+                    Opt::Serde(val) => opts.serde = val,
+                    Opt::AdditionalDerives(paths) => opts.additional_derives.extend(paths),
+                    Opt::NonExhaustive(val) => opts.non_exhaustive = val,
+                    Opt::EmitTypeAsserts(val) => opts.emit_type_asserts = val,
+                    Opt::Resources(val) => opts.resources.extend(val),
+                    Opt::UseExternalDeps(val) => opts.use_external_deps = val,
+                    Opt::OptionalExports(val) => opts.optional_exports = val,
+                    Opt::ModulePathStyle(val) => opts.module_path_style = val,
+                    Opt::TracingRedact(val) => opts.tracing_redact.extend(val),
+                    Opt::DeriveDefault(val) => opts.derive_default = val,
+                    Opt::NoStd(val) => opts.no_std = val,
+                    Opt::AllowedLints(paths) => opts.allowed_lints = paths,
+                    Opt::ListRepresentation(val) => opts.list_representation = val,
+                    Opt::Lifetime(val) => opts.lifetime = val,
+                    Opt::ExportLookup(val) => opts.export_lookup = val,
+                    Opt::MustUseResults(val) => opts.must_use_results = val,
+                    Opt::RawIdentifiers(val) => opts.raw_identifiers = val,
+                    Opt::TypeWith(val) => opts.type_with.extend(val),
+                    Opt::EnumStringConversions(val) => opts.enum_string_conversions = val,
+                    Opt::TracingLevel(val) => opts.tracing_level = val,
+                    Opt::ReadOnlyImports(val) => opts.read_only_imports.extend(val),
+                    Opt::DeriveEq(val) => opts.derive_eq = val,
+                    Opt::ImportVtable(val) => opts.import_vtable = val,
+                    Opt::ListFieldIters(val) => opts.list_field_iters.extend(val),
+                    Opt::EnumDiscriminantConsts(val) => opts.enum_discriminant_consts = val,
+                    Opt::Header(val) => opts.header = Some(val),
+                    Opt::Footer(val) => opts.footer = Some(val),
+                    Opt::TypedFuncGetters(val) => opts.typed_func_getters = val,
+                    Opt::TracingInstrument(val) => opts.tracing_instrument = val,
+                    Opt::FfiRecords(val) => opts.ffi_records.extend(val),
+                    Opt::AsyncInterfaces(val) => opts.async_interfaces.extend(val),
+                    Opt::BytesForU8Lists(val) => opts.bytes_for_u8_lists = val,
+                    Opt::BorrowedImportParams(val) => opts.borrowed_import_params = val,
+                    Opt::RequireResources(val) => opts.require_resources = val,
                 }
             }
         } else {
@@ -171,6 +212,44 @@ mod kw {
     syn::custom_keyword!(ownership);
     syn::custom_keyword!(interfaces);
     syn::custom_keyword!(with);
+    syn::custom_keyword!(serde);
+    syn::custom_keyword!(additional_derives);
+    syn::custom_keyword!(non_exhaustive);
+    syn::custom_keyword!(emit_type_asserts);
+    syn::custom_keyword!(resources);
+    syn::custom_keyword!(use_external_deps);
+    syn::custom_keyword!(optional_exports);
+    syn::custom_keyword!(module_path_style);
+    syn::custom_keyword!(tracing_redact);
+    syn::custom_keyword!(derive_default);
+    syn::custom_keyword!(no_std);
+    syn::custom_keyword!(allowed_lints);
+    syn::custom_keyword!(list_representation);
+    syn::custom_keyword!(lifetime);
+    syn::custom_keyword!(export_lookup);
+    syn::custom_keyword!(must_use_results);
+    syn::custom_keyword!(raw_identifiers);
+    syn::custom_keyword!(enum_string_conversions);
+    syn::custom_keyword!(type_with);
+    syn::custom_keyword!(import);
+    syn::custom_keyword!(export);
+    syn::custom_keyword!(tracing_level);
+    syn::custom_keyword!(read_only_imports);
+    syn::custom_keyword!(derive_eq);
+    syn::custom_keyword!(import_vtable);
+    syn::custom_keyword!(list_field_iters);
+    syn::custom_keyword!(import_async);
+    syn::custom_keyword!(export_async);
+    syn::custom_keyword!(enum_discriminant_consts);
+    syn::custom_keyword!(header);
+    syn::custom_keyword!(footer);
+    syn::custom_keyword!(typed_func_getters);
+    syn::custom_keyword!(tracing_instrument);
+    syn::custom_keyword!(ffi_records);
+    syn::custom_keyword!(async_interfaces);
+    syn::custom_keyword!(bytes_for_u8_lists);
+    syn::custom_keyword!(borrowed_import_params);
+    syn::custom_keyword!(require_resources);
 }
 
 enum Opt {
@@ -179,10 +258,46 @@ enum Opt {
     Inline(syn::LitStr),
     Tracing(bool),
     Async(bool),
+    ImportAsync(bool),
+    ExportAsync(bool),
     TrappableErrorType(Vec<TrappableError>),
     Ownership(Ownership),
     Interfaces(syn::LitStr),
     With(HashMap<String, String>),
+    Serde(bool),
+    AdditionalDerives(Vec<String>),
+    NonExhaustive(bool),
+    EmitTypeAsserts(bool),
+    Resources(HashMap<String, String>),
+    UseExternalDeps(bool),
+    OptionalExports(bool),
+    ModulePathStyle(ModulePathStyle),
+    TracingRedact(Vec<(String, String)>),
+    DeriveDefault(bool),
+    NoStd(bool),
+    AllowedLints(Vec<String>),
+    ListRepresentation(ListRepresentation),
+    Lifetime(String),
+    ExportLookup(bool),
+    MustUseResults(bool),
+    RawIdentifiers(bool),
+    EnumStringConversions(bool),
+    TypeWith(HashMap<String, String>),
+    TracingLevel(String),
+    DeriveEq(bool),
+    ReadOnlyImports(Vec<String>),
+    ImportVtable(bool),
+    ListFieldIters(Vec<String>),
+    EnumDiscriminantConsts(bool),
+    Header(String),
+    Footer(String),
+    TypedFuncGetters(bool),
+    TracingInstrument(bool),
+    FfiRecords(Vec<String>),
+    AsyncInterfaces(Vec<String>),
+    BytesForU8Lists(bool),
+    BorrowedImportParams(bool),
+    RequireResources(bool),
 }
 
 impl Parse for Opt {
@@ -208,6 +323,14 @@ impl Parse for Opt {
             input.parse::<Token![async]>()?;
             input.parse::<Token![:]>()?;
             Ok(Opt::Async(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::import_async) {
+            input.parse::<kw::import_async>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::ImportAsync(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::export_async) {
+            input.parse::<kw::export_async>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::ExportAsync(input.parse::<syn::LitBool>()?.value))
         } else if l.peek(kw::ownership) {
             input.parse::<kw::ownership>()?;
             input.parse::<Token![:]>()?;
@@ -266,6 +389,248 @@ impl Parse for Opt {
             let fields: Punctuated<(String, String), Token![,]> =
                 contents.parse_terminated(with_field_parse, Token![,])?;
             Ok(Opt::With(HashMap::from_iter(fields.into_iter())))
+        } else if l.peek(kw::serde) {
+            input.parse::<kw::serde>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::Serde(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::additional_derives) {
+            input.parse::<kw::additional_derives>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbracket = bracketed!(contents in input);
+            let paths: Punctuated<syn::Path, Token![,]> =
+                contents.parse_terminated(syn::Path::parse, Token![,])?;
+            Ok(Opt::AdditionalDerives(
+                paths
+                    .into_iter()
+                    .map(|path| path.to_token_stream().to_string())
+                    .collect(),
+            ))
+        } else if l.peek(kw::non_exhaustive) {
+            input.parse::<kw::non_exhaustive>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::NonExhaustive(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::emit_type_asserts) {
+            input.parse::<kw::emit_type_asserts>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::EmitTypeAsserts(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::resources) {
+            input.parse::<kw::resources>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbrace = braced!(contents in input);
+            let fields: Punctuated<(String, String), Token![,]> =
+                contents.parse_terminated(resources_field_parse, Token![,])?;
+            Ok(Opt::Resources(HashMap::from_iter(fields.into_iter())))
+        } else if l.peek(kw::use_external_deps) {
+            input.parse::<kw::use_external_deps>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::UseExternalDeps(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::optional_exports) {
+            input.parse::<kw::optional_exports>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::OptionalExports(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::module_path_style) {
+            input.parse::<kw::module_path_style>()?;
+            input.parse::<Token![:]>()?;
+            let style = input.parse::<syn::Ident>()?;
+            Ok(Opt::ModulePathStyle(match style.to_string().as_str() {
+                "Nested" => ModulePathStyle::Nested,
+                "Flat" => ModulePathStyle::Flat,
+                name => {
+                    return Err(Error::new(
+                        style.span(),
+                        format!(
+                            "unrecognized module path style: `{name}`; \
+                             expected `Nested` or `Flat`"
+                        ),
+                    ));
+                }
+            }))
+        } else if l.peek(kw::tracing_redact) {
+            input.parse::<kw::tracing_redact>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbrack = bracketed!(contents in input);
+            let fields: Punctuated<(String, String), Token![,]> =
+                contents.parse_terminated(tracing_redact_field_parse, Token![,])?;
+            Ok(Opt::TracingRedact(fields.into_iter().collect()))
+        } else if l.peek(kw::derive_default) {
+            input.parse::<kw::derive_default>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::DeriveDefault(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::no_std) {
+            input.parse::<kw::no_std>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::NoStd(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::allowed_lints) {
+            input.parse::<kw::allowed_lints>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbracket = bracketed!(contents in input);
+            let paths: Punctuated<syn::Path, Token![,]> =
+                contents.parse_terminated(syn::Path::parse, Token![,])?;
+            Ok(Opt::AllowedLints(
+                paths
+                    .into_iter()
+                    .map(|path| path.to_token_stream().to_string())
+                    .collect(),
+            ))
+        } else if l.peek(kw::list_representation) {
+            input.parse::<kw::list_representation>()?;
+            input.parse::<Token![:]>()?;
+            let repr = input.parse::<syn::Ident>()?;
+            Ok(Opt::ListRepresentation(match repr.to_string().as_str() {
+                "Vec" => ListRepresentation::Vec,
+                "BoxedSlice" => ListRepresentation::BoxedSlice,
+                name => {
+                    return Err(Error::new(
+                        repr.span(),
+                        format!(
+                            "unrecognized list representation: `{name}`; \
+                             expected `Vec` or `BoxedSlice`"
+                        ),
+                    ));
+                }
+            }))
+        } else if l.peek(kw::lifetime) {
+            input.parse::<kw::lifetime>()?;
+            input.parse::<Token![:]>()?;
+            let lifetime = input.parse::<syn::Lifetime>()?;
+            if lifetime.ident == "static" || lifetime.ident == "_" {
+                return Err(Error::new(
+                    lifetime.span(),
+                    format!("invalid lifetime `{lifetime}` for generated types"),
+                ));
+            }
+            Ok(Opt::Lifetime(lifetime.to_string()))
+        } else if l.peek(kw::export_lookup) {
+            input.parse::<kw::export_lookup>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::ExportLookup(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::must_use_results) {
+            input.parse::<kw::must_use_results>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::MustUseResults(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::raw_identifiers) {
+            input.parse::<kw::raw_identifiers>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::RawIdentifiers(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::enum_string_conversions) {
+            input.parse::<kw::enum_string_conversions>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::EnumStringConversions(
+                input.parse::<syn::LitBool>()?.value,
+            ))
+        } else if l.peek(kw::type_with) {
+            input.parse::<kw::type_with>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbrace = braced!(contents in input);
+            let fields: Punctuated<(String, String), Token![,]> =
+                contents.parse_terminated(with_field_parse, Token![,])?;
+            Ok(Opt::TypeWith(HashMap::from_iter(fields.into_iter())))
+        } else if l.peek(kw::tracing_level) {
+            input.parse::<kw::tracing_level>()?;
+            input.parse::<Token![:]>()?;
+            let level = input.parse::<Ident>()?;
+            match level.to_string().to_ascii_lowercase().as_str() {
+                "trace" | "debug" | "info" | "warn" | "error" => {}
+                _ => {
+                    return Err(Error::new(
+                        level.span(),
+                        format!(
+                            "unrecognized tracing level: `{level}`; \
+                             expected `trace`, `debug`, `info`, `warn` or `error`"
+                        ),
+                    ));
+                }
+            }
+            Ok(Opt::TracingLevel(level.to_string()))
+        } else if l.peek(kw::derive_eq) {
+            input.parse::<kw::derive_eq>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::DeriveEq(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::read_only_imports) {
+            input.parse::<kw::read_only_imports>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbrack = bracketed!(contents in input);
+            let names: Punctuated<syn::LitStr, Token![,]> =
+                contents.parse_terminated(syn::LitStr::parse, Token![,])?;
+            Ok(Opt::ReadOnlyImports(
+                names.into_iter().map(|name| name.value()).collect(),
+            ))
+        } else if l.peek(kw::import_vtable) {
+            input.parse::<kw::import_vtable>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::ImportVtable(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::list_field_iters) {
+            input.parse::<kw::list_field_iters>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbrack = bracketed!(contents in input);
+            let types: Punctuated<syn::LitStr, Token![,]> =
+                contents.parse_terminated(syn::LitStr::parse, Token![,])?;
+            Ok(Opt::ListFieldIters(
+                types.into_iter().map(|ty| ty.value()).collect(),
+            ))
+        } else if l.peek(kw::enum_discriminant_consts) {
+            input.parse::<kw::enum_discriminant_consts>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::EnumDiscriminantConsts(
+                input.parse::<syn::LitBool>()?.value,
+            ))
+        } else if l.peek(kw::header) {
+            input.parse::<kw::header>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::Header(input.parse::<syn::LitStr>()?.value()))
+        } else if l.peek(kw::footer) {
+            input.parse::<kw::footer>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::Footer(input.parse::<syn::LitStr>()?.value()))
+        } else if l.peek(kw::typed_func_getters) {
+            input.parse::<kw::typed_func_getters>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::TypedFuncGetters(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::tracing_instrument) {
+            input.parse::<kw::tracing_instrument>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::TracingInstrument(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::ffi_records) {
+            input.parse::<kw::ffi_records>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbrack = bracketed!(contents in input);
+            let types: Punctuated<syn::LitStr, Token![,]> =
+                contents.parse_terminated(syn::LitStr::parse, Token![,])?;
+            Ok(Opt::FfiRecords(
+                types.into_iter().map(|ty| ty.value()).collect(),
+            ))
+        } else if l.peek(kw::async_interfaces) {
+            input.parse::<kw::async_interfaces>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbrack = bracketed!(contents in input);
+            let interfaces: Punctuated<syn::LitStr, Token![,]> =
+                contents.parse_terminated(syn::LitStr::parse, Token![,])?;
+            Ok(Opt::AsyncInterfaces(
+                interfaces.into_iter().map(|i| i.value()).collect(),
+            ))
+        } else if l.peek(kw::bytes_for_u8_lists) {
+            input.parse::<kw::bytes_for_u8_lists>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::BytesForU8Lists(input.parse::<syn::LitBool>()?.value))
+        } else if l.peek(kw::borrowed_import_params) {
+            input.parse::<kw::borrowed_import_params>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::BorrowedImportParams(
+                input.parse::<syn::LitBool>()?.value,
+            ))
+        } else if l.peek(kw::require_resources) {
+            input.parse::<kw::require_resources>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::RequireResources(input.parse::<syn::LitBool>()?.value))
         } else {
             Err(l.error())
         }
@@ -287,16 +652,40 @@ fn trappable_error_field_parse(input: ParseStream<'_>) -> Result<TrappableError>
         }
     }
 
+    let direction = if input.peek(kw::export) {
+        input.parse::<kw::export>()?;
+        Direction::Export
+    } else {
+        if input.peek(kw::import) {
+            input.parse::<kw::import>()?;
+        }
+        Direction::Import
+    };
     let wit_package_path = input.parse::<syn::LitStr>()?.value();
     input.parse::<Token![::]>()?;
     let wit_type_name = ident_or_str(input)?;
     input.parse::<Token![:]>()?;
     let rust_type_name = input.parse::<Ident>()?.to_string();
-    Ok(TrappableError {
-        wit_package_path,
-        wit_type_name,
-        rust_type_name,
-    })
+    Ok(
+        TrappableError::new(wit_package_path, wit_type_name, rust_type_name)
+            .with_direction(direction),
+    )
+}
+
+fn resources_field_parse(input: ParseStream<'_>) -> Result<(String, String)> {
+    let resource = input.parse::<syn::LitStr>()?.value();
+    input.parse::<Token![:]>()?;
+    let path = input.parse::<syn::Path>()?;
+    Ok((resource, path.to_token_stream().to_string()))
+}
+
+fn tracing_redact_field_parse(input: ParseStream<'_>) -> Result<(String, String)> {
+    let contents;
+    let _lparen = parenthesized!(contents in input);
+    let function = contents.parse::<syn::LitStr>()?.value();
+    contents.parse::<Token![,]>()?;
+    let param = contents.parse::<syn::LitStr>()?.value();
+    Ok((function, param))
 }
 
 fn with_field_parse(input: ParseStream<'_>) -> Result<(String, String)> {
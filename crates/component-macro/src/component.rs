@@ -577,7 +577,7 @@ impl Expander for LowerExpander {
                     &self,
                     cx: &mut #internal::LowerContext<'_, T>,
                     ty: #internal::InterfaceType,
-                    dst: &mut std::mem::MaybeUninit<Self::Lower>,
+                    dst: &mut core::mem::MaybeUninit<Self::Lower>,
                 ) -> #internal::anyhow::Result<()> {
                     #extract_ty
                     #lowers
@@ -687,7 +687,7 @@ impl Expander for LowerExpander {
                     &self,
                     cx: &mut #internal::LowerContext<'_, T>,
                     ty: #internal::InterfaceType,
-                    dst: &mut std::mem::MaybeUninit<Self::Lower>,
+                    dst: &mut core::mem::MaybeUninit<Self::Lower>,
                 ) -> #internal::anyhow::Result<()> {
                     #extract_ty
                     match self {
@@ -724,27 +724,40 @@ impl Expander for ComponentTypeExpander {
         generics: &syn::Generics,
         fields: &[&syn::Field],
     ) -> Result<TokenStream> {
-        expand_record_for_component_type(
+        let component_names = fields
+            .iter()
+            .map(|syn::Field { attrs, ident, .. }| {
+                Ok(find_rename(attrs)?.unwrap_or_else(|| {
+                    let ident = ident.as_ref().unwrap();
+                    syn::LitStr::new(&ident.to_string(), ident.span())
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let component_type_impl = expand_record_for_component_type(
             name,
             generics,
             fields,
             quote!(typecheck_record),
             fields
                 .iter()
-                .map(
-                    |syn::Field {
-                         attrs, ident, ty, ..
-                     }| {
-                        let name = find_rename(attrs)?.unwrap_or_else(|| {
-                            let ident = ident.as_ref().unwrap();
-                            syn::LitStr::new(&ident.to_string(), ident.span())
-                        });
-
-                        Ok(quote!((#name, <#ty as wasmtime::component::ComponentType>::typecheck),))
-                    },
-                )
-                .collect::<Result<_>>()?,
-        )
+                .zip(&component_names)
+                .map(|(syn::Field { ty, .. }, name)| {
+                    quote!((#name, <#ty as wasmtime::component::ComponentType>::typecheck),)
+                })
+                .collect(),
+        )?;
+
+        // The component names of the fields, in order, which generated
+        // bindings check against their WIT names at compile time.
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        Ok(quote! {
+            #component_type_impl
+
+            impl #impl_generics wasmtime::component::__internal::ComponentRecord for #name #ty_generics #where_clause {
+                const FIELD_NAMES: &'static [&'static str] = &[#(#component_names),*];
+            }
+        })
     }
 
     fn expand_variant(
@@ -881,6 +894,7 @@ impl Expander for ComponentTypeExpander {
 
 #[derive(Debug)]
 struct Flag {
+    docs: Vec<syn::Attribute>,
     rename: Option<String>,
     name: String,
 }
@@ -890,22 +904,28 @@ impl Parse for Flag {
         let attributes = syn::Attribute::parse_outer(input)?;
 
         let rename = find_rename(&attributes)?.map(|literal| literal.value());
+        let docs = attributes
+            .into_iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .collect();
 
         input.parse::<Token![const]>()?;
         let name = input.parse::<syn::Ident>()?.to_string();
 
-        Ok(Self { rename, name })
+        Ok(Self { docs, rename, name })
     }
 }
 
 #[derive(Debug)]
 pub struct Flags {
+    attrs: Vec<syn::Attribute>,
     name: String,
     flags: Vec<Flag>,
 }
 
 impl Parse for Flags {
     fn parse(input: ParseStream) -> Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
         let name = input.parse::<syn::Ident>()?.to_string();
 
         let content;
@@ -916,7 +936,7 @@ impl Parse for Flags {
             .into_iter()
             .collect();
 
-        Ok(Self { name, flags })
+        Ok(Self { attrs, name, flags })
     }
 }
 
@@ -925,6 +945,7 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
 
     let ty;
     let eq;
+    let hash;
 
     let count = flags.flags.len();
 
@@ -932,27 +953,40 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
         FlagsSize::Size0 => {
             ty = quote!(());
             eq = quote!(true);
+            hash = quote!(core::hash::Hash::hash(&(), state););
         }
         FlagsSize::Size1 => {
             ty = quote!(u8);
 
-            eq = if count == 8 {
-                quote!(self.__inner0.eq(&rhs.__inner0))
+            (eq, hash) = if count == 8 {
+                (
+                    quote!(self.__inner0.eq(&rhs.__inner0)),
+                    quote!(core::hash::Hash::hash(&self.__inner0, state);),
+                )
             } else {
                 let mask = !(0xFF_u8 << count);
 
-                quote!((self.__inner0 & #mask).eq(&(rhs.__inner0 & #mask)))
+                (
+                    quote!((self.__inner0 & #mask).eq(&(rhs.__inner0 & #mask))),
+                    quote!(core::hash::Hash::hash(&(self.__inner0 & #mask), state);),
+                )
             };
         }
         FlagsSize::Size2 => {
             ty = quote!(u16);
 
-            eq = if count == 16 {
-                quote!(self.__inner0.eq(&rhs.__inner0))
+            (eq, hash) = if count == 16 {
+                (
+                    quote!(self.__inner0.eq(&rhs.__inner0)),
+                    quote!(core::hash::Hash::hash(&self.__inner0, state);),
+                )
             } else {
                 let mask = !(0xFFFF_u16 << count);
 
-                quote!((self.__inner0 & #mask).eq(&(rhs.__inner0 & #mask)))
+                (
+                    quote!((self.__inner0 & #mask).eq(&(rhs.__inner0 & #mask))),
+                    quote!(core::hash::Hash::hash(&(self.__inner0 & #mask), state);),
+                )
             };
         }
         FlagsSize::Size4Plus(n) => {
@@ -966,14 +1000,28 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
                 })
                 .collect::<TokenStream>();
 
+            let hashes = (0..(n - 1))
+                .map(|index| {
+                    let field = format_ident!("__inner{}", index);
+
+                    quote!(core::hash::Hash::hash(&self.#field, state);)
+                })
+                .collect::<TokenStream>();
+
             let field = format_ident!("__inner{}", n - 1);
 
-            eq = if count % 32 == 0 {
-                quote!(#comparisons self.#field.eq(&rhs.#field))
+            (eq, hash) = if count % 32 == 0 {
+                (
+                    quote!(#comparisons self.#field.eq(&rhs.#field)),
+                    quote!(#hashes core::hash::Hash::hash(&self.#field, state);),
+                )
             } else {
                 let mask = !(0xFFFF_FFFF_u32 << (count % 32));
 
-                quote!(#comparisons (self.#field & #mask).eq(&(rhs.#field & #mask)))
+                (
+                    quote!(#comparisons (self.#field & #mask).eq(&(rhs.#field & #mask))),
+                    quote!(#hashes core::hash::Hash::hash(&(self.#field & #mask), state);),
+                )
             }
         }
     }
@@ -1057,7 +1105,7 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
     let mut rust_names = TokenStream::new();
     let mut component_names = TokenStream::new();
 
-    for (index, Flag { name, rename }) in flags.flags.iter().enumerate() {
+    for (index, Flag { docs, name, rename }) in flags.flags.iter().enumerate() {
         rust_names.extend(quote!(#name,));
 
         let component_name = rename.as_ref().unwrap_or(name);
@@ -1090,7 +1138,7 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
 
         let name = format_ident!("{}", name);
 
-        constants.extend(quote!(pub const #name: Self = Self { #fields };));
+        constants.extend(quote!(#(#docs)* pub const #name: Self = Self { #fields };));
     }
 
     let generics = syn::Generics {
@@ -1144,7 +1192,10 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
         FlagsSize::Size4Plus(_) => (quote!(#internal::InterfaceType::U32), 4),
     };
 
+    let attrs = &flags.attrs;
+
     let expanded = quote! {
+        #(#attrs)*
         #[derive(Copy, Clone, Default)]
         pub struct #name { #fields }
 
@@ -1160,7 +1211,7 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
             }
 
             pub fn all() -> Self {
-                use std::ops::Not;
+                use core::ops::Not;
                 Self::default().not()
             }
 
@@ -1173,21 +1224,29 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
             }
         }
 
-        impl std::cmp::PartialEq for #name {
+        impl core::cmp::PartialEq for #name {
             fn eq(&self, rhs: &#name) -> bool {
                 #eq
             }
         }
 
-        impl std::cmp::Eq for #name { }
+        impl core::cmp::Eq for #name { }
+
+        // Unused high bits are masked out, like in `eq`, so that flags which
+        // compare equal also hash equally.
+        impl core::hash::Hash for #name {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                #hash
+            }
+        }
 
-        impl std::fmt::Debug for #name {
-            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        impl core::fmt::Debug for #name {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
                 #internal::format_flags(&self.as_array(), &[#rust_names], f)
             }
         }
 
-        impl std::ops::BitOr for #name {
+        impl core::ops::BitOr for #name {
             type Output = #name;
 
             fn bitor(self, rhs: #name) -> #name {
@@ -1195,13 +1254,13 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
             }
         }
 
-        impl std::ops::BitOrAssign for #name {
+        impl core::ops::BitOrAssign for #name {
             fn bitor_assign(&mut self, rhs: #name) {
                 #bitor_assign
             }
         }
 
-        impl std::ops::BitAnd for #name {
+        impl core::ops::BitAnd for #name {
             type Output = #name;
 
             fn bitand(self, rhs: #name) -> #name {
@@ -1209,13 +1268,13 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
             }
         }
 
-        impl std::ops::BitAndAssign for #name {
+        impl core::ops::BitAndAssign for #name {
             fn bitand_assign(&mut self, rhs: #name) {
                 #bitand_assign
             }
         }
 
-        impl std::ops::BitXor for #name {
+        impl core::ops::BitXor for #name {
             type Output = #name;
 
             fn bitxor(self, rhs: #name) -> #name {
@@ -1223,13 +1282,13 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
             }
         }
 
-        impl std::ops::BitXorAssign for #name {
+        impl core::ops::BitXorAssign for #name {
             fn bitxor_assign(&mut self, rhs: #name) {
                 #bitxor_assign
             }
         }
 
-        impl std::ops::Not for #name {
+        impl core::ops::Not for #name {
             type Output = #name;
 
             fn not(self) -> #name {
@@ -1244,7 +1303,7 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
                 &self,
                 cx: &mut #internal::LowerContext<'_, T>,
                 _ty: #internal::InterfaceType,
-                dst: &mut std::mem::MaybeUninit<Self::Lower>,
+                dst: &mut core::mem::MaybeUninit<Self::Lower>,
             ) -> #internal::anyhow::Result<()> {
                 #(
                     self.#field_names.lower(
@@ -1269,7 +1328,7 @@ pub fn expand_flags(flags: &Flags) -> Result<TokenStream> {
                         #field_interface_type,
                         offset,
                     )?;
-                    offset += std::mem::size_of_val(&self.#field_names);
+                    offset += core::mem::size_of_val(&self.#field_names);
                 )*
                 Ok(())
             }
@@ -16,7 +16,58 @@ macro_rules! gentest {
                     tracing: true,
                     ownership: Borrowing {
                         duplicate_if_necessary: true
-                    }
+                    },
+                    borrowed_import_params: true,
+                });
+            }
+            mod boxed_slices {
+                wasmtime::component::bindgen!({
+                    path: $path,
+                    list_representation: BoxedSlice,
+                });
+            }
+            mod options {
+                wasmtime::component::bindgen!({
+                    path: $path,
+                    non_exhaustive: true,
+                    emit_type_asserts: false,
+                    derive_default: true,
+                    export_lookup: true,
+                    must_use_results: true,
+                    raw_identifiers: true,
+                    enum_string_conversions: true,
+                    tracing: true,
+                    tracing_level: debug,
+                    derive_eq: true,
+                    import_vtable: true,
+                    enum_discriminant_consts: true,
+                    typed_func_getters: true,
+                });
+            }
+            mod async_tracing {
+                wasmtime::component::bindgen!({
+                    path: $path,
+                    async: true,
+                    tracing: true,
+                    tracing_instrument: true,
+                });
+            }
+            mod export_async {
+                wasmtime::component::bindgen!({
+                    path: $path,
+                    export_async: true,
+                    tracing: true,
+                    tracing_instrument: true,
+                });
+            }
+            mod lifetime {
+                wasmtime::component::bindgen!({
+                    path: $path,
+                    lifetime: 'input,
+                    ownership: Borrowing {
+                        duplicate_if_necessary: false
+                    },
+                    borrowed_import_params: true,
                 });
             }
         }
@@ -0,0 +1,16 @@
+#![no_std]
+
+extern crate alloc;
+
+macro_rules! gentest {
+    ($id:ident $name:tt $path:tt) => {
+        mod $id {
+            wasmtime::component::bindgen!({
+                path: $path,
+                no_std: true,
+            });
+        }
+    };
+}
+
+component_macro_test_helpers::foreach!(gentest);
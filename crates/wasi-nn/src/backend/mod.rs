@@ -5,9 +5,9 @@
 mod openvino;
 
 use self::openvino::OpenvinoBackend;
-use crate::wit::types::{ExecutionTarget, Tensor};
+use crate::wit::types::{ExecutionTarget, Tensor, TensorType};
 use crate::{ExecutionContext, Graph};
-use std::{error::Error, fmt, path::Path, str::FromStr};
+use std::{error::Error, fmt, io::Read, path::Path, str::FromStr, time::Duration};
 use thiserror::Error;
 use wiggle::GuestError;
 
@@ -21,6 +21,31 @@ pub trait Backend: Send + Sync {
     fn name(&self) -> &str;
     fn load(&mut self, builders: &[&[u8]], target: ExecutionTarget) -> Result<Graph, BackendError>;
     fn as_dir_loadable<'a>(&'a mut self) -> Option<&'a mut dyn BackendFromDir>;
+    /// Load a graph from `readers`, one per builder, so that backends able to
+    /// build a graph incrementally needn't hold every builder in memory at
+    /// once. By default each reader is read to the end and the buffers are
+    /// passed to [Backend::load].
+    fn load_stream(
+        &mut self,
+        readers: &mut [&mut dyn Read],
+        target: ExecutionTarget,
+    ) -> Result<Graph, BackendError> {
+        let mut buffers = Vec::with_capacity(readers.len());
+        for reader in readers.iter_mut() {
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .map_err(|e| BackendError::BackendAccess(e.into()))?;
+            buffers.push(buffer);
+        }
+        let builders = buffers.iter().map(|b| b.as_slice()).collect::<Vec<_>>();
+        self.load(&builders, target)
+    }
+    /// Whether graphs can be loaded to execute on `target`; every target is
+    /// assumed to be supported unless the backend says otherwise.
+    fn supports_target(&self, _target: ExecutionTarget) -> bool {
+        true
+    }
 }
 
 /// Some [Backend]s support loading a [Graph] from a directory on the
@@ -38,6 +63,33 @@ pub trait BackendFromDir: Backend {
 /// implementation for a [crate::witx::types::Graph].
 pub trait BackendGraph: Send + Sync {
     fn init_execution_context(&self) -> Result<ExecutionContext, BackendError>;
+    /// Describe the tensors the graph takes and produces, so that inputs can
+    /// be checked before inference; by default graphs can't be introspected.
+    fn describe(&self) -> Result<GraphDescription, BackendError> {
+        Ok(GraphDescription::Unsupported)
+    }
+}
+
+/// The inputs and outputs of a [Graph], as reported by
+/// [BackendGraph::describe].
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphDescription {
+    /// The backend can't introspect its graphs.
+    Unsupported,
+    /// The graph's input and output tensors, in index order.
+    Tensors {
+        inputs: Vec<TensorDescription>,
+        outputs: Vec<TensorDescription>,
+    },
+}
+
+/// A tensor taken or produced by a graph; the type and dimensions are `None`
+/// when the backend can't tell them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TensorDescription {
+    pub name: String,
+    pub tensor_type: Option<TensorType>,
+    pub dimensions: Option<Vec<u32>>,
 }
 
 /// A [BackendExecutionContext] performs the actual inference; this is the
@@ -45,7 +97,26 @@ pub trait BackendGraph: Send + Sync {
 pub trait BackendExecutionContext: Send + Sync {
     fn set_input(&mut self, index: u32, tensor: &Tensor) -> Result<(), BackendError>;
     fn compute(&mut self) -> Result<(), BackendError>;
+    /// Compute the inference, giving up once `timeout` has elapsed; returns
+    /// whether it completed in time. Backends able to cancel an inference
+    /// should override this. By default, as with OpenVINO, the timeout isn't
+    /// enforced: the inference runs to completion and its result is kept,
+    /// however long it took.
+    fn compute_with_timeout(&mut self, _timeout: Duration) -> Result<bool, BackendError> {
+        self.compute()?;
+        Ok(true)
+    }
     fn get_output(&mut self, index: u32, destination: &mut [u8]) -> Result<u32, BackendError>;
+    /// The number of inputs the model expects to be set before `compute`, if
+    /// the backend can tell; input indices are only checked when it can.
+    fn num_inputs(&self) -> Result<Option<u32>, BackendError> {
+        Ok(None)
+    }
+    /// The number of outputs the model produces, if the backend can tell;
+    /// output indices are only checked when it can.
+    fn num_outputs(&self) -> Result<Option<u32>, BackendError> {
+        Ok(None)
+    }
 }
 
 /// Errors returned by a backend; [BackendError::BackendAccess] is a catch-all
@@ -62,19 +133,41 @@ pub enum BackendError {
     NotEnoughMemory(usize),
 }
 
-#[derive(Hash, PartialEq, Debug, Eq, Clone, Copy)]
+/// Identifies the [Backend] used for each graph encoding; only OpenVINO is
+/// built in, but backends for the other encodings can be registered with
+/// [crate::WasiNnCtx::register_backend].
+#[derive(Hash, PartialEq, Debug, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum BackendKind {
     OpenVINO,
+    Onnx,
+    Tensorflow,
+    PyTorch,
+    TensorflowLite,
 }
 impl FromStr for BackendKind {
     type Err = BackendKindParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "openvino" => Ok(BackendKind::OpenVINO),
+            "onnx" => Ok(BackendKind::Onnx),
+            "tensorflow" => Ok(BackendKind::Tensorflow),
+            "pytorch" => Ok(BackendKind::PyTorch),
+            "tensorflowlite" => Ok(BackendKind::TensorflowLite),
             _ => Err(BackendKindParseError(s.into())),
         }
     }
 }
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BackendKind::OpenVINO => "openvino",
+            BackendKind::Onnx => "onnx",
+            BackendKind::Tensorflow => "tensorflow",
+            BackendKind::PyTorch => "pytorch",
+            BackendKind::TensorflowLite => "tensorflowlite",
+        })
+    }
+}
 #[derive(Debug)]
 pub struct BackendKindParseError(String);
 impl fmt::Display for BackendKindParseError {
@@ -1,6 +1,9 @@
 //! Implements a `wasi-nn` [`Backend`] using OpenVINO.
 
-use super::{Backend, BackendError, BackendExecutionContext, BackendFromDir, BackendGraph};
+use super::{
+    Backend, BackendError, BackendExecutionContext, BackendFromDir, BackendGraph, GraphDescription,
+    TensorDescription,
+};
 use crate::wit::types::{ExecutionTarget, Tensor, TensorType};
 use crate::{ExecutionContext, Graph};
 use openvino::{InferenceError, Layout, Precision, SetupError, TensorDesc};
@@ -92,6 +95,23 @@ impl BackendGraph for OpenvinoGraph {
             Box::new(OpenvinoExecutionContext(self.0.clone(), infer_request));
         Ok(box_.into())
     }
+
+    fn describe(&self) -> Result<GraphDescription, BackendError> {
+        // The OpenVINO bindings only expose the names of a network's inputs
+        // and outputs.
+        let describe = |name: String| TensorDescription {
+            name,
+            tensor_type: None,
+            dimensions: None,
+        };
+        let inputs = (0..self.0.get_inputs_len()?)
+            .map(|i| -> Result<_, BackendError> { Ok(describe(self.0.get_input_name(i)?)) })
+            .collect::<Result<Vec<_>, _>>()?;
+        let outputs = (0..self.0.get_outputs_len()?)
+            .map(|i| -> Result<_, BackendError> { Ok(describe(self.0.get_output_name(i)?)) })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(GraphDescription::Tensors { inputs, outputs })
+    }
 }
 
 struct OpenvinoExecutionContext(Arc<openvino::CNNNetwork>, openvino::InferRequest);
@@ -133,6 +153,14 @@ impl BackendExecutionContext for OpenvinoExecutionContext {
         destination[..blob_size].copy_from_slice(blob.buffer()?);
         Ok(blob_size as u32)
     }
+
+    fn num_inputs(&self) -> Result<Option<u32>, BackendError> {
+        Ok(Some(self.0.get_inputs_len()? as u32))
+    }
+
+    fn num_outputs(&self) -> Result<Option<u32>, BackendError> {
+        Ok(Some(self.0.get_outputs_len()? as u32))
+    }
 }
 
 impl From<InferenceError> for BackendError {
@@ -1,10 +1,10 @@
 //! Implements the host state for the `wasi-nn` API: [WasiNnCtx].
 
-use crate::backend::{Backend, BackendError, BackendKind};
-use crate::wit::types::GraphEncoding;
+use crate::backend::{Backend, BackendError, BackendKind, GraphDescription};
+use crate::wit::types::{ExecutionTarget, GraphEncoding};
 use crate::{ExecutionContext, Graph, GraphRegistry, InMemoryRegistry};
-use anyhow::anyhow;
-use std::{collections::HashMap, hash::Hash, path::Path};
+use anyhow::{anyhow, Context};
+use std::{collections::HashMap, hash::Hash, path::Path, time::Duration};
 use thiserror::Error;
 use wiggle::GuestError;
 
@@ -18,29 +18,122 @@ type GraphDirectory = String;
 /// Construct an in-memory registry from the available backends and a list of
 /// `(<backend name>, <graph directory>)`. This assumes graphs can be loaded
 /// from a local directory, which is a safe assumption currently for the current
-/// model types.
+/// model types. Graphs are loaded to execute on the CPU; see
+/// [preload_with_targets] to choose.
 pub fn preload(
     preload_graphs: &[(BackendName, GraphDirectory)],
+) -> anyhow::Result<(Backends, Registry)> {
+    let preload_graphs = preload_graphs
+        .iter()
+        .map(|(kind, path)| (kind.clone(), path.clone(), ExecutionTarget::Cpu))
+        .collect::<Vec<_>>();
+    preload_with_targets(&preload_graphs)
+}
+
+/// Same as [preload], except that each graph is loaded to execute on the
+/// given [ExecutionTarget].
+pub fn preload_with_targets(
+    preload_graphs: &[(BackendName, GraphDirectory, ExecutionTarget)],
 ) -> anyhow::Result<(Backends, Registry)> {
     let mut backends: HashMap<_, _> = crate::backend::list().into_iter().collect();
     let mut registry = InMemoryRegistry::new();
-    for (kind, path) in preload_graphs {
+    for (kind, path, target) in preload_graphs {
+        let backend_kind: BackendKind = kind
+            .parse()
+            .with_context(|| format!("failed to preload graph from {path}"))?;
         let backend = backends
-            .get_mut(&kind.parse()?)
-            .ok_or(anyhow!("unsupported backend: {}", kind))?
+            .get_mut(&backend_kind)
+            .ok_or_else(|| UsageError::UnsupportedBackend(kind.clone()))
+            .with_context(|| format!("failed to preload graph from {path}"))?
             .as_dir_loadable()
-            .ok_or(anyhow!("{} does not support directory loading", kind))?;
-        registry.load(backend, Path::new(path))?;
+            .ok_or_else(|| UsageError::BackendCannotLoadDirectory(kind.clone()))
+            .with_context(|| format!("failed to preload graph from {path}"))?;
+        registry
+            .load_with_target(backend, Path::new(path), *target)
+            .with_context(|| format!("failed to preload {kind} graph from {path}"))?;
     }
     Ok((backends, Box::new(registry)))
 }
 
+/// Build a [WasiNnCtx], checking up front that the graph encodings and
+/// execution targets guests are expected to `load` have a capable backend,
+/// rather than failing on the first such `load`.
+#[derive(Default)]
+pub struct WasiNnCtxBuilder {
+    backends: Backends,
+    registry: Option<Registry>,
+    required: Vec<(GraphEncoding, ExecutionTarget)>,
+    timeout: Option<Duration>,
+}
+
+impl WasiNnCtxBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `backends`, such as those returned by [preload].
+    pub fn backends(mut self, backends: Backends) -> Self {
+        self.backends.extend(backends);
+        self
+    }
+
+    /// Add `backend` to load graphs of the given `kind`.
+    pub fn backend(mut self, kind: BackendKind, backend: Box<dyn Backend>) -> Self {
+        self.backends.insert(kind, backend);
+        self
+    }
+
+    /// Use `registry` for named graphs; an empty [InMemoryRegistry] is used
+    /// otherwise.
+    pub fn registry(mut self, registry: Registry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Limit how long each inference may take; see [WasiNnCtx::set_timeout].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Require that graphs with `encoding` can be loaded for `target`.
+    pub fn require(mut self, encoding: GraphEncoding, target: ExecutionTarget) -> Self {
+        self.required.push((encoding, target));
+        self
+    }
+
+    /// Check every requirement and build the context.
+    pub fn build(self) -> anyhow::Result<WasiNnCtx> {
+        for (encoding, target) in self.required.iter().copied() {
+            let kind = BackendKind::try_from(encoding)?;
+            let backend = self
+                .backends
+                .get(&kind)
+                .ok_or_else(|| anyhow!("no backend can load graphs with encoding {encoding:?}"))?;
+            if !backend.supports_target(target) {
+                anyhow::bail!(
+                    "the {} backend for graphs with encoding {encoding:?} does not support \
+                     the {target:?} execution target",
+                    backend.name()
+                );
+            }
+        }
+        let registry = self
+            .registry
+            .unwrap_or_else(|| Box::new(InMemoryRegistry::new()));
+        let mut ctx = WasiNnCtx::new(self.backends, registry);
+        ctx.set_timeout(self.timeout);
+        Ok(ctx)
+    }
+}
+
 /// Capture the state necessary for calling into the backend ML libraries.
 pub struct WasiNnCtx {
     pub(crate) backends: Backends,
     pub(crate) registry: Registry,
     pub(crate) graphs: Table<GraphId, Graph>,
     pub(crate) executions: Table<GraphExecutionContextId, ExecutionContext>,
+    pub(crate) timeout: Option<Duration>,
 }
 
 impl WasiNnCtx {
@@ -51,8 +144,140 @@ impl WasiNnCtx {
             registry,
             graphs: Table::default(),
             executions: Table::default(),
+            timeout: None,
         }
     }
+
+    /// The kinds of backends this context can load graphs with, sorted.
+    pub fn available_backends(&self) -> Vec<BackendKind> {
+        let mut kinds = self.backends.keys().copied().collect::<Vec<_>>();
+        kinds.sort();
+        kinds
+    }
+
+    /// Limit the number of graphs and, separately, execution contexts that can
+    /// be live at once; further loads fail until some are dropped.
+    pub fn set_max_handles(&mut self, max_handles: Option<usize>) {
+        self.graphs.set_max_entries(max_handles);
+        self.executions.set_max_entries(max_handles);
+    }
+
+    /// Limit how long each `compute` may take, failing with
+    /// [WasiNnError::Timeout] once `timeout` elapses; `None`, the default,
+    /// waits indefinitely. Only backends that can cancel an inference enforce
+    /// the timeout, see [crate::BackendExecutionContext::compute_with_timeout].
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Register `backend` to load graphs of the given `kind`, such as a backend
+    /// implemented outside of this crate. A later registration for the same
+    /// `kind` replaces the prior one.
+    pub fn register_backend(&mut self, kind: BackendKind, backend: Box<dyn Backend>) {
+        self.backends.insert(kind, backend);
+    }
+
+    /// Same as [WasiNnCtx::register_backend] but for chaining onto
+    /// [WasiNnCtx::new].
+    pub fn with_backend(mut self, kind: BackendKind, backend: Box<dyn Backend>) -> Self {
+        self.register_backend(kind, backend);
+        self
+    }
+
+    /// Look up a loaded graph, failing with
+    /// [UsageError::InvalidGraphHandle] if `id` is unknown.
+    pub(crate) fn graph(&self, id: GraphId) -> WasiNnResult<&Graph> {
+        self.graphs
+            .get(id)
+            .ok_or_else(|| UsageError::InvalidGraphHandle.into())
+    }
+
+    /// Describe the inputs and outputs of the loaded graph `id`, such as to
+    /// check tensors before passing them to `set_input`.
+    pub fn describe_graph(&self, id: GraphId) -> WasiNnResult<GraphDescription> {
+        Ok(self.graph(id)?.describe()?)
+    }
+
+    /// Look up an execution context, failing with
+    /// [UsageError::InvalidExecutionContextHandle] if `id` is unknown.
+    pub(crate) fn execution_mut(
+        &mut self,
+        id: GraphExecutionContextId,
+    ) -> WasiNnResult<&mut ExecutionContext> {
+        self.executions
+            .get_mut(id)
+            .ok_or_else(|| UsageError::InvalidExecutionContextHandle.into())
+    }
+
+    /// The error for a graph `name` missing from the registry, listing the
+    /// names which are available instead.
+    pub(crate) fn graph_not_found(&self, name: &str) -> UsageError {
+        let mut names = self.registry.names();
+        names.sort();
+        UsageError::NotFound(name.to_string(), names)
+    }
+
+    /// Drop the execution context with the given id, releasing its backend
+    /// state.
+    pub fn drop_execution_context(&mut self, id: GraphExecutionContextId) -> WasiNnResult<()> {
+        match self.executions.remove(id) {
+            Some(_) => Ok(()),
+            None => Err(UsageError::InvalidExecutionContextHandle.into()),
+        }
+    }
+
+    /// Drop the graph with the given id; execution contexts already
+    /// initialized from it remain usable.
+    pub fn drop_graph(&mut self, id: GraphId) -> WasiNnResult<()> {
+        match self.graphs.remove(id) {
+            Some(_) => Ok(()),
+            None => Err(UsageError::InvalidGraphHandle.into()),
+        }
+    }
+
+    /// Drop every graph and execution context, such as to reuse this context
+    /// for another run without loading the preloaded graphs again; the
+    /// backends and the registry are kept. Any handles the guest still holds
+    /// become invalid; new handles continue after them so that a stale handle
+    /// doesn't refer to a new graph or execution context.
+    pub fn clear(&mut self) {
+        self.graphs.clear();
+        self.executions.clear();
+    }
+
+    /// The number of graphs currently loaded into this context.
+    pub fn graph_count(&self) -> usize {
+        self.graphs.len()
+    }
+
+    /// The number of execution contexts currently live in this context.
+    pub fn execution_count(&self) -> usize {
+        self.executions.len()
+    }
+
+    /// The ids of the graphs currently loaded into this context, in no
+    /// particular order.
+    pub fn graph_ids(&self) -> impl Iterator<Item = GraphId> + '_ {
+        self.graphs.keys().copied()
+    }
+
+    /// The ids of the execution contexts currently live in this context, in
+    /// no particular order.
+    pub fn execution_ids(&self) -> impl Iterator<Item = GraphExecutionContextId> + '_ {
+        self.executions.keys().copied()
+    }
+
+    /// List the graphs currently known to this context: the named graphs
+    /// available in the registry followed by the ids of graphs that have been
+    /// loaded into this context (e.g., with `load` or `load_by_name`).
+    pub fn loaded_graph_names(&self) -> Vec<String> {
+        let mut names = self.registry.names();
+        names.sort();
+        let mut ids = self.graphs.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        names.extend(ids.into_iter().map(|id| id.to_string()));
+        names
+    }
 }
 
 /// Possible errors while interacting with [WasiNnCtx].
@@ -64,6 +289,8 @@ pub enum WasiNnError {
     GuestError(#[from] GuestError),
     #[error("usage error")]
     UsageError(#[from] UsageError),
+    #[error("inference did not complete within {0:?}")]
+    Timeout(Duration),
 }
 
 #[derive(Debug, Error)]
@@ -80,8 +307,20 @@ pub enum UsageError {
     InvalidExecutionContextHandle,
     #[error("Not enough memory to copy tensor data of size: {0}")]
     NotEnoughMemory(u32),
-    #[error("No graph found with name: {0}")]
-    NotFound(String),
+    #[error("No graph found with name: {0}; available graphs: [{}]", .1.join(", "))]
+    NotFound(String, Vec<String>),
+    #[error("Invalid input index {0}; the model has {1} inputs")]
+    InvalidInputIndex(u32, u32),
+    #[error("Input {0} was not set; the model expects {1} inputs")]
+    MissingInput(u32, u32),
+    #[error("Invalid output index {0}; the model has {1} outputs")]
+    InvalidOutputIndex(u32, u32),
+    #[error("Too many live handles; drop some before creating more")]
+    TooManyHandles,
+    #[error("Unsupported backend: {0}")]
+    UnsupportedBackend(BackendName),
+    #[error("Backend {0} does not support loading graphs from a directory")]
+    BackendCannotLoadDirectory(BackendName),
 }
 
 pub(crate) type WasiNnResult<T> = std::result::Result<T, WasiNnError>;
@@ -90,6 +329,7 @@ pub(crate) type WasiNnResult<T> = std::result::Result<T, WasiNnError>;
 pub struct Table<K, V> {
     entries: HashMap<K, V>,
     next_key: u32,
+    max_entries: Option<usize>,
 }
 
 impl<K, V> Default for Table<K, V> {
@@ -97,6 +337,7 @@ impl<K, V> Default for Table<K, V> {
         Self {
             entries: HashMap::new(),
             next_key: 0,
+            max_entries: None,
         }
     }
 }
@@ -105,10 +346,24 @@ impl<K, V> Table<K, V>
 where
     K: Eq + Hash + From<u32> + Copy,
 {
-    pub fn insert(&mut self, value: V) -> K {
+    /// Insert `value` under a key that isn't in use, failing with
+    /// [UsageError::TooManyHandles] if the table is full.
+    pub fn insert(&mut self, value: V) -> WasiNnResult<K> {
+        let limit = self
+            .max_entries
+            .map_or(u64::from(u32::MAX) + 1, |max| max as u64);
+        if self.entries.len() as u64 >= limit {
+            return Err(UsageError::TooManyHandles.into());
+        }
         let key = self.use_next_key();
         self.entries.insert(key, value);
-        key
+        Ok(key)
+    }
+
+    /// Limit the number of entries the table can hold at once; `None`, the
+    /// default, only limits it to the number of possible keys.
+    pub fn set_max_entries(&mut self, max_entries: Option<usize>) {
+        self.max_entries = max_entries;
     }
 
     pub fn get(&self, key: K) -> Option<&V> {
@@ -119,16 +374,154 @@ where
         self.entries.get_mut(&key)
     }
 
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        self.entries.remove(&key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.keys()
+    }
+
+    /// Iterate over the live entries, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.entries.iter().map(|(key, value)| (*key, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove every entry; keys keep counting up from where they were, so
+    /// the removed keys aren't handed out again right away, and the limit set
+    /// with [Table::set_max_entries] still applies.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Pick the next key that isn't in use, wrapping around once all keys
+    /// have been handed out; `insert` ensures that a free key exists.
     fn use_next_key(&mut self) -> K {
-        let current = self.next_key;
-        self.next_key += 1;
-        K::from(current)
+        loop {
+            let key = K::from(self.next_key);
+            self.next_key = self.next_key.wrapping_add(1);
+            if !self.entries.contains_key(&key) {
+                return key;
+            }
+        }
+    }
+}
+
+impl<K, V> Table<K, V>
+where
+    K: Eq + Hash + From<u32> + Into<u32> + Copy,
+{
+    /// Rebuild a table from entries saved with [Table::entries], keeping
+    /// their keys. New keys continue after the largest restored key rather
+    /// than starting over from zero.
+    pub fn from_entries(entries: impl IntoIterator<Item = (K, V)>) -> Self {
+        let entries: HashMap<K, V> = entries.into_iter().collect();
+        let next_key = entries
+            .keys()
+            .map(|key| (*key).into())
+            .max()
+            .map_or(0, |max: u32| max.wrapping_add(1));
+        Self {
+            entries,
+            next_key,
+            max_entries: None,
+        }
+    }
+}
+
+impl<K, V> Table<K, V>
+where
+    K: Eq + Hash + From<u32> + Copy,
+    V: Clone,
+{
+    /// Copy out the live entries, such as to save them for
+    /// [Table::from_entries].
+    pub fn entries(&self) -> Vec<(K, V)> {
+        self.iter()
+            .map(|(key, value)| (key, value.clone()))
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::backend::{
+        BackendExecutionContext, BackendFromDir, BackendGraph, TensorDescription,
+    };
+    use crate::wit::types::{Tensor, TensorType};
+
+    struct FakeRegistry(HashMap<String, Graph>);
+    impl GraphRegistry for FakeRegistry {
+        fn get_mut(&mut self, name: &str) -> Option<&mut Graph> {
+            self.0.get_mut(name)
+        }
+        fn names(&self) -> Vec<String> {
+            self.0.keys().cloned().collect()
+        }
+    }
+
+    struct FakeGraph;
+    impl BackendGraph for FakeGraph {
+        fn init_execution_context(&self) -> Result<ExecutionContext, BackendError> {
+            unimplemented!()
+        }
+    }
+
+    struct FakeExecutionContext {
+        inputs: u32,
+    }
+    impl BackendExecutionContext for FakeExecutionContext {
+        fn set_input(&mut self, _index: u32, _tensor: &Tensor) -> Result<(), BackendError> {
+            Ok(())
+        }
+        fn compute(&mut self) -> Result<(), BackendError> {
+            Ok(())
+        }
+        fn get_output(
+            &mut self,
+            _index: u32,
+            _destination: &mut [u8],
+        ) -> Result<u32, BackendError> {
+            Ok(0)
+        }
+        fn num_inputs(&self) -> Result<Option<u32>, BackendError> {
+            Ok(Some(self.inputs))
+        }
+        fn num_outputs(&self) -> Result<Option<u32>, BackendError> {
+            Ok(Some(1))
+        }
+    }
+
+    struct FakeBackend(&'static str);
+    impl Backend for FakeBackend {
+        fn name(&self) -> &str {
+            self.0
+        }
+        fn load(
+            &mut self,
+            _builders: &[&[u8]],
+            _target: ExecutionTarget,
+        ) -> Result<Graph, BackendError> {
+            Ok(fake_graph())
+        }
+        fn as_dir_loadable(&mut self) -> Option<&mut dyn BackendFromDir> {
+            None
+        }
+    }
+
+    fn fake_graph() -> Graph {
+        let graph: Box<dyn BackendGraph> = Box::new(FakeGraph);
+        graph.into()
+    }
 
     #[test]
     fn example() {
@@ -141,4 +534,451 @@ mod test {
 
         let ctx = WasiNnCtx::new(HashMap::new(), Box::new(FakeRegistry));
     }
+
+    #[test]
+    fn loaded_graph_names() {
+        let registry = FakeRegistry(HashMap::from([
+            ("mobilenet".to_string(), fake_graph()),
+            ("alexnet".to_string(), fake_graph()),
+            ("resnet".to_string(), fake_graph()),
+        ]));
+        let mut ctx = WasiNnCtx::new(HashMap::new(), Box::new(registry));
+        ctx.graphs.insert(fake_graph()).unwrap();
+        ctx.graphs.insert(fake_graph()).unwrap();
+
+        assert_eq!(
+            ctx.loaded_graph_names(),
+            vec!["alexnet", "mobilenet", "resnet", "0", "1"]
+        );
+    }
+
+    #[test]
+    fn compute_with_missing_input() {
+        let tensor = Tensor {
+            dimensions: vec![1],
+            tensor_type: TensorType::U8,
+            data: vec![0],
+        };
+        let exec: Box<dyn BackendExecutionContext> = Box::new(FakeExecutionContext { inputs: 3 });
+        let mut exec: ExecutionContext = exec.into();
+        exec.set_input(0, &tensor).unwrap();
+        exec.set_input(2, &tensor).unwrap();
+
+        match exec.compute(None).unwrap_err() {
+            WasiNnError::UsageError(e) => {
+                assert!(matches!(e, UsageError::MissingInput(1, 3)));
+                assert_eq!(
+                    e.to_string(),
+                    "Input 1 was not set; the model expects 3 inputs"
+                );
+            }
+            e => panic!("unexpected error: {e:?}"),
+        }
+
+        exec.set_input(1, &tensor).unwrap();
+        exec.compute(None).unwrap();
+    }
+
+    #[test]
+    fn compute_timeout() {
+        struct InterruptibleExecutionContext;
+        impl BackendExecutionContext for InterruptibleExecutionContext {
+            fn set_input(&mut self, _index: u32, _tensor: &Tensor) -> Result<(), BackendError> {
+                Ok(())
+            }
+            fn compute(&mut self) -> Result<(), BackendError> {
+                std::thread::sleep(Duration::from_millis(20));
+                Ok(())
+            }
+            fn compute_with_timeout(&mut self, timeout: Duration) -> Result<bool, BackendError> {
+                if timeout < Duration::from_millis(20) {
+                    std::thread::sleep(timeout);
+                    return Ok(false);
+                }
+                self.compute()?;
+                Ok(true)
+            }
+            fn get_output(
+                &mut self,
+                _index: u32,
+                _destination: &mut [u8],
+            ) -> Result<u32, BackendError> {
+                Ok(0)
+            }
+        }
+
+        let exec: Box<dyn BackendExecutionContext> = Box::new(InterruptibleExecutionContext);
+        let mut exec: ExecutionContext = exec.into();
+        exec.compute(None).unwrap();
+        exec.compute(Some(Duration::from_secs(60))).unwrap();
+        assert!(matches!(
+            exec.compute(Some(Duration::from_millis(1))),
+            Err(WasiNnError::Timeout(_))
+        ));
+    }
+
+    #[test]
+    fn compute_timeout_not_enforced() {
+        struct SlowExecutionContext;
+        impl BackendExecutionContext for SlowExecutionContext {
+            fn set_input(&mut self, _index: u32, _tensor: &Tensor) -> Result<(), BackendError> {
+                Ok(())
+            }
+            fn compute(&mut self) -> Result<(), BackendError> {
+                std::thread::sleep(Duration::from_millis(20));
+                Ok(())
+            }
+            fn get_output(
+                &mut self,
+                _index: u32,
+                _destination: &mut [u8],
+            ) -> Result<u32, BackendError> {
+                Ok(0)
+            }
+        }
+
+        // Without an override of `compute_with_timeout` the inference can't
+        // be interrupted, so its result is kept.
+        let exec: Box<dyn BackendExecutionContext> = Box::new(SlowExecutionContext);
+        let mut exec: ExecutionContext = exec.into();
+        exec.compute(Some(Duration::from_millis(1))).unwrap();
+    }
+
+    /// Computes once a message is received, so that tests can tell when.
+    #[cfg(feature = "async")]
+    struct WaitingExecutionContext(std::sync::Mutex<std::sync::mpsc::Receiver<()>>);
+    #[cfg(feature = "async")]
+    impl BackendExecutionContext for WaitingExecutionContext {
+        fn set_input(&mut self, _index: u32, _tensor: &Tensor) -> Result<(), BackendError> {
+            Ok(())
+        }
+        fn compute(&mut self) -> Result<(), BackendError> {
+            let receiver = self.0.lock().unwrap();
+            receiver
+                .recv_timeout(Duration::from_secs(10))
+                .map_err(|e| BackendError::BackendAccess(e.into()))
+        }
+        fn get_output(
+            &mut self,
+            _index: u32,
+            _destination: &mut [u8],
+        ) -> Result<u32, BackendError> {
+            Ok(0)
+        }
+        fn num_outputs(&self) -> Result<Option<u32>, BackendError> {
+            Ok(Some(1))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn compute_async() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let exec: Box<dyn BackendExecutionContext> =
+            Box::new(WaitingExecutionContext(receiver.into()));
+        let mut exec: ExecutionContext = exec.into();
+
+        // Another task of this single-threaded runtime keeps ticking while
+        // the inference runs, then lets it complete, which it couldn't were
+        // the inference blocking the runtime.
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                for _ in 0..3 {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                }
+                sender.send(()).unwrap();
+            }
+        });
+        exec.compute_async(None).await.unwrap();
+        ticker.await.unwrap();
+        assert_eq!(ticks.load(Ordering::SeqCst), 3);
+        assert_eq!(exec.lock().num_outputs().unwrap(), Some(1));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn compute_async_cancelled() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let exec: Box<dyn BackendExecutionContext> =
+            Box::new(WaitingExecutionContext(receiver.into()));
+        let mut exec: ExecutionContext = exec.into();
+
+        // Drop the future while the inference is still running.
+        tokio::select! {
+            biased;
+            _ = exec.compute_async(None) => unreachable!(),
+            _ = async {} => {}
+        }
+
+        // The execution context waits for that inference, then is usable again.
+        sender.send(()).unwrap();
+        exec.ready().await;
+        assert_eq!(exec.lock().num_outputs().unwrap(), Some(1));
+        sender.send(()).unwrap();
+        exec.compute_async(None).await.unwrap();
+    }
+
+    #[test]
+    fn register_backend() {
+        let mut ctx = WasiNnCtx::new(HashMap::new(), Box::new(FakeRegistry(HashMap::new())))
+            .with_backend(BackendKind::Onnx, Box::new(FakeBackend("first")));
+        assert_eq!(ctx.backends[&BackendKind::Onnx].name(), "first");
+
+        ctx.register_backend(BackendKind::Onnx, Box::new(FakeBackend("second")));
+        assert_eq!(ctx.backends[&BackendKind::Onnx].name(), "second");
+        assert_eq!(ctx.backends.len(), 1);
+    }
+
+    #[test]
+    fn drop_handles() {
+        let mut ctx = WasiNnCtx::new(HashMap::new(), Box::new(FakeRegistry(HashMap::new())));
+        let graph = ctx.graphs.insert(fake_graph()).unwrap();
+        let exec: Box<dyn BackendExecutionContext> = Box::new(FakeExecutionContext { inputs: 1 });
+        let exec = ctx.executions.insert(exec.into()).unwrap();
+
+        ctx.drop_execution_context(exec).unwrap();
+        assert!(ctx.executions.get(exec).is_none());
+        assert!(matches!(
+            ctx.drop_execution_context(exec),
+            Err(WasiNnError::UsageError(
+                UsageError::InvalidExecutionContextHandle
+            ))
+        ));
+
+        ctx.drop_graph(graph).unwrap();
+        assert!(ctx.graphs.get(graph).is_none());
+        assert!(matches!(
+            ctx.drop_graph(graph),
+            Err(WasiNnError::UsageError(UsageError::InvalidGraphHandle))
+        ));
+    }
+
+    #[test]
+    fn clear() {
+        let registry = FakeRegistry(HashMap::from([("mobilenet".to_string(), fake_graph())]));
+        let mut ctx = WasiNnCtx::new(HashMap::new(), Box::new(registry))
+            .with_backend(BackendKind::Onnx, Box::new(FakeBackend("fake")));
+        ctx.graphs.insert(fake_graph()).unwrap();
+        ctx.graphs.insert(fake_graph()).unwrap();
+        let exec: Box<dyn BackendExecutionContext> = Box::new(FakeExecutionContext { inputs: 1 });
+        ctx.executions.insert(exec.into()).unwrap();
+
+        ctx.clear();
+        assert_eq!(ctx.graph_count(), 0);
+        assert_eq!(ctx.execution_count(), 0);
+        assert_eq!(ctx.backends.len(), 1);
+        assert_eq!(ctx.loaded_graph_names(), vec!["mobilenet"]);
+        assert_eq!(ctx.graphs.insert(fake_graph()).unwrap(), 2);
+    }
+
+    #[test]
+    fn graph_not_found() {
+        let registry = FakeRegistry(HashMap::from([
+            ("mobilenet".to_string(), fake_graph()),
+            ("alexnet".to_string(), fake_graph()),
+        ]));
+        let ctx = WasiNnCtx::new(HashMap::new(), Box::new(registry));
+        assert_eq!(
+            ctx.graph_not_found("resnet").to_string(),
+            "No graph found with name: resnet; available graphs: [alexnet, mobilenet]"
+        );
+    }
+
+    #[test]
+    fn preload_errors_name_the_graph() {
+        let err = preload(&[("bogus".to_string(), "/models/a".to_string())])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "failed to preload graph from /models/a");
+        assert_eq!(err.root_cause().to_string(), "unknown backend: bogus");
+
+        let err = preload(&[("onnx".to_string(), "/models/b".to_string())])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "failed to preload graph from /models/b");
+        assert!(matches!(
+            err.downcast_ref::<UsageError>(),
+            Some(UsageError::UnsupportedBackend(name)) if name == "onnx"
+        ));
+
+        let err = preload(&[("openvino".to_string(), "/models/missing".to_string())])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "failed to preload openvino graph from /models/missing"
+        );
+    }
+
+    #[test]
+    fn invalid_handles() {
+        let mut ctx = WasiNnCtx::new(HashMap::new(), Box::new(FakeRegistry(HashMap::new())));
+        assert!(matches!(
+            ctx.graph(0),
+            Err(WasiNnError::UsageError(UsageError::InvalidGraphHandle))
+        ));
+        assert!(matches!(
+            ctx.execution_mut(0),
+            Err(WasiNnError::UsageError(
+                UsageError::InvalidExecutionContextHandle
+            ))
+        ));
+
+        let graph = ctx.graphs.insert(fake_graph()).unwrap();
+        assert!(ctx.graph(graph).is_ok());
+    }
+
+    #[test]
+    fn describe_graph() {
+        struct DescribedGraph;
+        impl BackendGraph for DescribedGraph {
+            fn init_execution_context(&self) -> Result<ExecutionContext, BackendError> {
+                unimplemented!()
+            }
+            fn describe(&self) -> Result<GraphDescription, BackendError> {
+                Ok(GraphDescription::Tensors {
+                    inputs: vec![TensorDescription {
+                        name: "image".to_string(),
+                        tensor_type: Some(TensorType::Fp32),
+                        dimensions: Some(vec![1, 3, 224, 224]),
+                    }],
+                    outputs: vec![],
+                })
+            }
+        }
+
+        let mut ctx = WasiNnCtx::new(HashMap::new(), Box::new(FakeRegistry(HashMap::new())));
+        let fake = ctx.graphs.insert(fake_graph()).unwrap();
+        assert_eq!(
+            ctx.describe_graph(fake).unwrap(),
+            GraphDescription::Unsupported
+        );
+
+        let graph: Box<dyn BackendGraph> = Box::new(DescribedGraph);
+        let described = ctx.graphs.insert(graph.into()).unwrap();
+        match ctx.describe_graph(described).unwrap() {
+            GraphDescription::Tensors { inputs, outputs } => {
+                assert_eq!(inputs[0].name, "image");
+                assert_eq!(inputs[0].dimensions, Some(vec![1, 3, 224, 224]));
+                assert!(outputs.is_empty());
+            }
+            d => panic!("unexpected description: {d:?}"),
+        }
+
+        assert!(matches!(
+            ctx.describe_graph(described + 1),
+            Err(WasiNnError::UsageError(UsageError::InvalidGraphHandle))
+        ));
+    }
+
+    #[test]
+    fn table_capacity() {
+        let mut table = Table::<u32, &str>::default();
+        table.set_max_entries(Some(2));
+        let a = table.insert("a").unwrap();
+        table.insert("b").unwrap();
+        assert!(matches!(
+            table.insert("c"),
+            Err(WasiNnError::UsageError(UsageError::TooManyHandles))
+        ));
+
+        table.remove(a);
+        table.insert("c").unwrap();
+        assert_eq!(table.keys().count(), 2);
+    }
+
+    #[test]
+    fn table_keys_wrap_without_aliasing() {
+        let mut table = Table::<u32, &str>::default();
+        assert_eq!(table.insert("a").unwrap(), 0);
+        table.next_key = u32::MAX;
+        assert_eq!(table.insert("b").unwrap(), u32::MAX);
+        // Key 0 is still live, so it's skipped.
+        assert_eq!(table.insert("c").unwrap(), 1);
+        assert_eq!(table.get(0), Some(&"a"));
+    }
+
+    #[test]
+    fn table_rebuild() {
+        let mut table = Table::<u32, &str>::default();
+        let a = table.insert("a").unwrap();
+        let b = table.insert("b").unwrap();
+        let c = table.insert("c").unwrap();
+        table.remove(a);
+
+        let mut saved = table.entries();
+        saved.sort();
+        assert_eq!(saved, [(b, "b"), (c, "c")]);
+
+        let mut restored = Table::from_entries(saved);
+        let mut entries = restored.iter().collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(entries, [(b, &"b"), (c, &"c")]);
+        assert_eq!(restored.insert("d").unwrap(), c + 1);
+    }
+
+    #[test]
+    fn builder() {
+        let ctx = WasiNnCtxBuilder::new()
+            .backend(BackendKind::Onnx, Box::new(FakeBackend("onnx")))
+            .require(GraphEncoding::Onnx, ExecutionTarget::Cpu)
+            .build()
+            .unwrap();
+        assert_eq!(ctx.backends[&BackendKind::Onnx].name(), "onnx");
+
+        let err = WasiNnCtxBuilder::new()
+            .backend(BackendKind::Onnx, Box::new(FakeBackend("onnx")))
+            .require(GraphEncoding::Pytorch, ExecutionTarget::Cpu)
+            .build()
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "no backend can load graphs with encoding GraphEncoding::Pytorch"
+        );
+    }
+
+    #[test]
+    fn counts_and_ids() {
+        let mut ctx = WasiNnCtx::new(HashMap::new(), Box::new(FakeRegistry(HashMap::new())));
+        assert_eq!(ctx.graph_count(), 0);
+        assert_eq!(ctx.execution_count(), 0);
+
+        let a = ctx.graphs.insert(fake_graph()).unwrap();
+        let b = ctx.graphs.insert(fake_graph()).unwrap();
+        let exec: Box<dyn BackendExecutionContext> = Box::new(FakeExecutionContext { inputs: 1 });
+        let exec = ctx.executions.insert(exec.into()).unwrap();
+        assert_eq!(ctx.graph_count(), 2);
+        assert_eq!(ctx.execution_count(), 1);
+        let mut ids = ctx.graph_ids().collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec![a, b]);
+        assert_eq!(ctx.execution_ids().collect::<Vec<_>>(), vec![exec]);
+
+        ctx.drop_graph(a).unwrap();
+        ctx.drop_execution_context(exec).unwrap();
+        assert_eq!(ctx.graph_ids().collect::<Vec<_>>(), vec![b]);
+        assert_eq!(ctx.execution_count(), 0);
+    }
+
+    #[test]
+    fn available_backends() {
+        let ctx = WasiNnCtx::new(HashMap::new(), Box::new(FakeRegistry(HashMap::new())))
+            .with_backend(BackendKind::Onnx, Box::new(FakeBackend("onnx")))
+            .with_backend(BackendKind::OpenVINO, Box::new(FakeBackend("openvino")));
+        let kinds = ctx.available_backends();
+        assert_eq!(kinds, vec![BackendKind::OpenVINO, BackendKind::Onnx]);
+
+        let names = kinds.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        assert_eq!(names, ["openvino", "onnx"]);
+        for name in names {
+            assert_eq!(name.parse::<BackendKind>().unwrap().to_string(), name);
+        }
+    }
 }
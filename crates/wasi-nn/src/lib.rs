@@ -2,12 +2,21 @@ mod backend;
 mod ctx;
 mod registry;
 
-pub use ctx::{preload, WasiNnCtx};
+pub use backend::{
+    Backend, BackendError, BackendExecutionContext, BackendFromDir, BackendGraph, BackendKind,
+    GraphDescription, TensorDescription,
+};
+pub use ctx::{preload, preload_with_targets, WasiNnCtx, WasiNnCtxBuilder};
 pub use registry::{GraphRegistry, InMemoryRegistry};
 pub mod wit;
 pub mod witx;
 
+use crate::ctx::{UsageError, WasiNnError, WasiNnResult};
+use crate::wit::types::Tensor;
+use std::collections::BTreeSet;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard};
 
 /// A backend-defined graph (i.e., ML model).
 #[derive(Clone)]
@@ -25,20 +34,109 @@ impl std::ops::Deref for Graph {
 }
 
 /// A backend-defined execution context.
-pub struct ExecutionContext(Box<dyn backend::BackendExecutionContext>);
-impl From<Box<dyn backend::BackendExecutionContext>> for ExecutionContext {
-    fn from(value: Box<dyn backend::BackendExecutionContext>) -> Self {
-        Self(value)
+pub struct ExecutionContext {
+    /// Shared with the blocking task of [ExecutionContext::compute_async]
+    /// while it runs the inference.
+    inner: Arc<Mutex<Box<dyn backend::BackendExecutionContext>>>,
+    /// The indices of the inputs that have been set so far.
+    inputs: BTreeSet<u32>,
+}
+impl ExecutionContext {
+    /// Set the input at `index`, checking it against the model's inputs.
+    pub(crate) fn set_input(&mut self, index: u32, tensor: &Tensor) -> WasiNnResult<()> {
+        let mut inner = self.lock();
+        if let Some(count) = inner.num_inputs()? {
+            if index >= count {
+                return Err(UsageError::InvalidInputIndex(index, count).into());
+            }
+        }
+        inner.set_input(index, tensor)?;
+        drop(inner);
+        self.inputs.insert(index);
+        Ok(())
+    }
+
+    /// Compute the inference, checking first that every input the model
+    /// expects has been set, and failing with [WasiNnError::Timeout] if it
+    /// takes longer than `timeout`.
+    pub(crate) fn compute(&mut self, timeout: Option<Duration>) -> WasiNnResult<()> {
+        let mut inner = self.lock();
+        self.check_inputs(inner.as_ref())?;
+        compute(inner.as_mut(), timeout)
+    }
+
+    /// Same as [ExecutionContext::compute] but running the inference with
+    /// [tokio::task::spawn_blocking], so that it doesn't block the executor
+    /// of an async store. Should the returned future be dropped before the
+    /// inference completes, the backend context stays locked until it does.
+    #[cfg(feature = "async")]
+    pub(crate) async fn compute_async(&mut self, timeout: Option<Duration>) -> WasiNnResult<()> {
+        let mut inner = self.inner.clone().lock_owned().await;
+        self.check_inputs(inner.as_ref())?;
+        tokio::task::spawn_blocking(move || compute(inner.as_mut(), timeout))
+            .await
+            .unwrap_or_else(|e| Err(BackendError::BackendAccess(e.into()).into()))
+    }
+
+    /// Wait for the inference of a dropped [ExecutionContext::compute_async]
+    /// future to complete, so that the backend context can be locked without
+    /// blocking.
+    #[cfg(feature = "async")]
+    pub(crate) async fn ready(&self) {
+        drop(self.inner.lock().await);
+    }
+
+    /// Lock the backend context, which is only contended while the inference
+    /// of a dropped [ExecutionContext::compute_async] future completes; async
+    /// callers wait for that with [ExecutionContext::ready] beforehand.
+    fn lock(&self) -> MutexGuard<'_, Box<dyn backend::BackendExecutionContext>> {
+        self.inner
+            .try_lock()
+            .unwrap_or_else(|_| self.inner.blocking_lock())
+    }
+
+    /// Check that every input the model expects has been set, if the
+    /// backend can tell how many it expects.
+    fn check_inputs(&self, inner: &dyn backend::BackendExecutionContext) -> WasiNnResult<()> {
+        if let Some(count) = inner.num_inputs()? {
+            if let Some(missing) = (0..count).find(|i| !self.inputs.contains(i)) {
+                return Err(UsageError::MissingInput(missing, count).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the output at `index` into `destination`, checking it against the
+    /// model's outputs.
+    pub(crate) fn get_output(&mut self, index: u32, destination: &mut [u8]) -> WasiNnResult<u32> {
+        let mut inner = self.lock();
+        if let Some(count) = inner.num_outputs()? {
+            if index >= count {
+                return Err(UsageError::InvalidOutputIndex(index, count).into());
+            }
+        }
+        Ok(inner.get_output(index, destination)?)
     }
 }
-impl std::ops::Deref for ExecutionContext {
-    type Target = dyn backend::BackendExecutionContext;
-    fn deref(&self) -> &Self::Target {
-        self.0.as_ref()
+
+fn compute(
+    inner: &mut dyn backend::BackendExecutionContext,
+    timeout: Option<Duration>,
+) -> WasiNnResult<()> {
+    match timeout {
+        None => Ok(inner.compute()?),
+        Some(timeout) => match inner.compute_with_timeout(timeout)? {
+            true => Ok(()),
+            false => Err(WasiNnError::Timeout(timeout)),
+        },
     }
 }
-impl std::ops::DerefMut for ExecutionContext {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.0.as_mut()
+
+impl From<Box<dyn backend::BackendExecutionContext>> for ExecutionContext {
+    fn from(value: Box<dyn backend::BackendExecutionContext>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(value)),
+            inputs: BTreeSet::new(),
+        }
     }
 }
@@ -1,12 +1,14 @@
 //! Implement a [`GraphRegistry`] with a hash map.
 
 use super::{Graph, GraphRegistry};
-use crate::backend::BackendFromDir;
+use crate::backend::{Backend, BackendFromDir};
 use crate::wit::types::ExecutionTarget;
 use anyhow::{anyhow, bail};
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, io::Read, path::Path};
 
-pub struct InMemoryRegistry(HashMap<String, Graph>);
+/// Each graph is kept with the target it was loaded for, which
+/// [`InMemoryRegistry::replace`] loads its replacement for.
+pub struct InMemoryRegistry(HashMap<String, (Graph, ExecutionTarget)>);
 impl InMemoryRegistry {
     pub fn new() -> Self {
         Self(HashMap::new())
@@ -19,6 +21,17 @@ impl InMemoryRegistry {
     /// suffix: if the backend can find the files it expects in `/my/model/foo`,
     /// the registry will contain a new graph named `foo`.
     pub fn load(&mut self, backend: &mut dyn BackendFromDir, path: &Path) -> anyhow::Result<()> {
+        self.load_with_target(backend, path, ExecutionTarget::Cpu)
+    }
+
+    /// Same as [`InMemoryRegistry::load`], except that the graph is loaded to
+    /// execute on `target` rather than the CPU.
+    pub fn load_with_target(
+        &mut self,
+        backend: &mut dyn BackendFromDir,
+        path: &Path,
+        target: ExecutionTarget,
+    ) -> anyhow::Result<()> {
         if !path.is_dir() {
             bail!(
                 "preload directory is not a valid directory: {}",
@@ -30,14 +43,246 @@ impl InMemoryRegistry {
             .map(|s| s.to_string_lossy())
             .ok_or(anyhow!("no file name in path"))?;
 
-        let graph = backend.load_from_dir(path, ExecutionTarget::Cpu)?;
-        self.0.insert(name.into_owned(), graph);
+        let graph = backend.load_from_dir(path, target)?;
+        self.0.insert(name.into_owned(), (graph, target));
+        Ok(())
+    }
+
+    /// Load a graph from in-memory `builders`, e.g., the bytes of a model's
+    /// files, and register it under `name`.
+    ///
+    /// Unlike [`InMemoryRegistry::load`], this works with any backend and
+    /// needs no filesystem access. A graph previously registered under `name`
+    /// is replaced.
+    pub fn load_bytes(
+        &mut self,
+        backend: &mut dyn Backend,
+        name: &str,
+        builders: &[&[u8]],
+        target: ExecutionTarget,
+    ) -> anyhow::Result<()> {
+        let graph = backend.load(builders, target)?;
+        self.0.insert(name.to_string(), (graph, target));
+        Ok(())
+    }
+
+    /// Load a graph from `readers`, one per builder, and register it under
+    /// `name`.
+    ///
+    /// Backends which support it build the graph as the readers are consumed,
+    /// keeping peak memory down for large models; wrapping the readers also
+    /// allows reporting load progress. See [`Backend::load_stream`].
+    pub fn load_stream(
+        &mut self,
+        backend: &mut dyn Backend,
+        name: &str,
+        readers: &mut [&mut dyn Read],
+        target: ExecutionTarget,
+    ) -> anyhow::Result<()> {
+        let graph = backend.load_stream(readers, target)?;
+        self.0.insert(name.to_string(), (graph, target));
+        Ok(())
+    }
+
+    /// Remove the graph registered under `name`, returning whether there was
+    /// one.
+    ///
+    /// Guests which already loaded the graph keep using it through their
+    /// graph handles, and execution contexts created from it stay valid, until
+    /// they're dropped; only later loads by `name` fail.
+    pub fn unload(&mut self, name: &str) -> bool {
+        self.0.remove(name).is_some()
+    }
+
+    /// Replace the graph registered under `name` with one loaded from the
+    /// files contained in the `path` directory, e.g., a retrained version of
+    /// the model. The new graph is loaded for the same target as the old one.
+    ///
+    /// The new graph is loaded before the swap, so the old graph stays
+    /// registered if loading fails. As with [`InMemoryRegistry::unload`], the
+    /// old graph lives on for guests which already loaded it and for their
+    /// in-flight execution contexts until they're dropped; only later loads
+    /// by `name` resolve to the new graph.
+    pub fn replace(
+        &mut self,
+        backend: &mut dyn BackendFromDir,
+        name: &str,
+        path: &Path,
+    ) -> anyhow::Result<()> {
+        let target = match self.0.get(name) {
+            Some((_, target)) => *target,
+            None => bail!("no graph registered with name: {name}"),
+        };
+        if !path.is_dir() {
+            bail!(
+                "replacement directory is not a valid directory: {}",
+                path.display()
+            );
+        }
+        let graph = backend.load_from_dir(path, target)?;
+        self.0.insert(name.to_string(), (graph, target));
         Ok(())
     }
 }
 
 impl GraphRegistry for InMemoryRegistry {
     fn get_mut(&mut self, name: &str) -> Option<&mut Graph> {
-        self.0.get_mut(name)
+        self.0.get_mut(name).map(|(graph, _)| graph)
+    }
+    fn names(&self) -> Vec<String> {
+        self.0.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::{BackendError, BackendGraph, GraphDescription, TensorDescription};
+    use crate::ExecutionContext;
+    use std::path::PathBuf;
+
+    /// A graph which tells the order it was loaded in through the name of
+    /// its only input.
+    struct FakeGraph(u32);
+    impl BackendGraph for FakeGraph {
+        fn init_execution_context(&self) -> Result<ExecutionContext, BackendError> {
+            unimplemented!()
+        }
+        fn describe(&self) -> Result<GraphDescription, BackendError> {
+            Ok(GraphDescription::Tensors {
+                inputs: vec![TensorDescription {
+                    name: self.0.to_string(),
+                    tensor_type: None,
+                    dimensions: None,
+                }],
+                outputs: vec![],
+            })
+        }
+    }
+
+    /// Records every load, with the builders or directory it was given.
+    #[derive(Default)]
+    struct FakeBackend {
+        builders: Vec<Vec<u8>>,
+        loads: Vec<(Option<PathBuf>, ExecutionTarget)>,
+    }
+    impl FakeBackend {
+        fn graph(&self) -> Graph {
+            let graph: Box<dyn BackendGraph> = Box::new(FakeGraph(self.loads.len() as u32));
+            graph.into()
+        }
+    }
+    impl Backend for FakeBackend {
+        fn name(&self) -> &str {
+            "fake"
+        }
+        fn load(
+            &mut self,
+            builders: &[&[u8]],
+            target: ExecutionTarget,
+        ) -> Result<Graph, BackendError> {
+            self.builders = builders.iter().map(|b| b.to_vec()).collect();
+            self.loads.push((None, target));
+            Ok(self.graph())
+        }
+        fn as_dir_loadable(&mut self) -> Option<&mut dyn BackendFromDir> {
+            Some(self)
+        }
+    }
+    impl BackendFromDir for FakeBackend {
+        fn load_from_dir(
+            &mut self,
+            path: &Path,
+            target: ExecutionTarget,
+        ) -> Result<Graph, BackendError> {
+            self.loads.push((Some(path.to_path_buf()), target));
+            Ok(self.graph())
+        }
+    }
+
+    fn version(graph: &Graph) -> String {
+        match graph.describe().unwrap() {
+            GraphDescription::Tensors { inputs, .. } => inputs[0].name.clone(),
+            GraphDescription::Unsupported => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn load_bytes() {
+        let mut backend = FakeBackend::default();
+        let mut registry = InMemoryRegistry::new();
+        registry
+            .load_bytes(
+                &mut backend,
+                "model",
+                &[&b"xml"[..], &b"weights"[..]],
+                ExecutionTarget::Cpu,
+            )
+            .unwrap();
+        assert_eq!(backend.builders, [b"xml".to_vec(), b"weights".to_vec()]);
+        assert!(registry.get_mut("model").is_some());
+        assert_eq!(registry.names(), ["model"]);
+    }
+
+    #[test]
+    fn load_stream() {
+        let mut backend = FakeBackend::default();
+        let mut registry = InMemoryRegistry::new();
+        let (mut xml, mut weights) = (&b"xml"[..], &b"weights"[..]);
+        registry
+            .load_stream(
+                &mut backend,
+                "model",
+                &mut [&mut xml, &mut weights],
+                ExecutionTarget::Cpu,
+            )
+            .unwrap();
+        assert_eq!(backend.builders, [b"xml".to_vec(), b"weights".to_vec()]);
+        assert!(registry.get_mut("model").is_some());
+    }
+
+    #[test]
+    fn load_with_targets() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let src_dir = crate_dir.join("src");
+        let mut backend = FakeBackend::default();
+        let mut registry = InMemoryRegistry::new();
+        registry.load(&mut backend, crate_dir).unwrap();
+        registry
+            .load_with_target(&mut backend, &src_dir, ExecutionTarget::Gpu)
+            .unwrap();
+        assert_eq!(
+            backend.loads,
+            [
+                (Some(crate_dir.to_path_buf()), ExecutionTarget::Cpu),
+                (Some(src_dir), ExecutionTarget::Gpu),
+            ]
+        );
+        let mut names = registry.names();
+        names.sort();
+        assert_eq!(names, ["src", "wasi-nn"]);
+    }
+
+    #[test]
+    fn replace_and_unload() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut backend = FakeBackend::default();
+        let mut registry = InMemoryRegistry::new();
+        registry
+            .load_with_target(&mut backend, crate_dir, ExecutionTarget::Gpu)
+            .unwrap();
+        let old = registry.get_mut("wasi-nn").unwrap().clone();
+
+        registry
+            .replace(&mut backend, "wasi-nn", crate_dir)
+            .unwrap();
+        assert_eq!(version(registry.get_mut("wasi-nn").unwrap()), "2");
+        assert_eq!(version(&old), "1");
+        assert_eq!(backend.loads[1].1, ExecutionTarget::Gpu);
+        assert!(registry.replace(&mut backend, "other", crate_dir).is_err());
+
+        assert!(registry.unload("wasi-nn"));
+        assert!(!registry.unload("wasi-nn"));
+        assert!(registry.get_mut("wasi-nn").is_none());
     }
 }
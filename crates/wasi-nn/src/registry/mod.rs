@@ -13,4 +13,9 @@ pub use in_memory::InMemoryRegistry;
 
 pub trait GraphRegistry: Send + Sync {
     fn get_mut(&mut self, name: &str) -> Option<&mut Graph>;
+    /// List the names of all graphs available in this registry; by default
+    /// the registry can't enumerate its graphs and none are listed.
+    fn names(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
@@ -5,8 +5,9 @@
 //! with the component model's canonical ABI.
 //!
 //! This module exports its [`types`] for use throughout the crate and the
-//! [`ML`] object, which exposes [`ML::add_to_linker`]. To implement all of
-//! this, this module proceeds in steps:
+//! [`ML`] object, which exposes [`ML::add_to_linker`], as well as `AsyncML`
+//! for async stores with the `async` feature. To implement all of this, this
+//! module proceeds in steps:
 //! 1. generate all of the WIT glue code into a `gen::*` namespace
 //! 2. wire up the `gen::*` glue to the context state, delegating actual
 //!    computation to a [`Backend`]
@@ -23,6 +24,25 @@ mod gen_ {
 }
 use gen_::wasi::nn as gen; // Shortcut to the module containing the types we need.
 
+/// Generate the `inference` interface again with async host functions, so that
+/// inference doesn't block the executor of async stores; the other interfaces
+/// are shared with the synchronous bindings.
+#[cfg(feature = "async")]
+mod gen_async_ {
+    wasmtime::component::bindgen!({
+        world: "ml",
+        path: "spec/wit/wasi-nn.wit",
+        async: true,
+        with: {
+            "wasi:nn/graph": super::gen::graph,
+            "wasi:nn/errors": super::gen::errors,
+            "wasi:nn/tensor": super::gen::tensor,
+        },
+    });
+}
+#[cfg(feature = "async")]
+use gen_async_::wasi::nn as gen_async;
+
 // Export the `types` used in this crate as well as `ML::add_to_linker`.
 pub mod types {
     use super::gen;
@@ -31,6 +51,8 @@ pub mod types {
     pub use gen::tensor::{Tensor, TensorType};
 }
 pub use gen_::Ml as ML;
+#[cfg(feature = "async")]
+pub use gen_async_::Ml as AsyncML;
 
 impl gen::graph::Host for WasiNnCtx {
     /// Load an opaque sequence of bytes to use for inference.
@@ -47,7 +69,7 @@ impl gen::graph::Host for WasiNnCtx {
         } else {
             return Err(UsageError::InvalidEncoding(encoding.into()).into());
         };
-        let graph_id = self.graphs.insert(graph);
+        let graph_id = self.graphs.insert(graph)?;
         Ok(Ok(graph_id))
     }
 
@@ -56,10 +78,10 @@ impl gen::graph::Host for WasiNnCtx {
         name: String,
     ) -> wasmtime::Result<Result<gen::graph::Graph, gen::errors::Error>> {
         if let Some(graph) = self.registry.get_mut(&name) {
-            let graph_id = self.graphs.insert(graph.clone().into());
+            let graph_id = self.graphs.insert(graph.clone().into())?;
             Ok(Ok(graph_id))
         } else {
-            return Err(UsageError::NotFound(name.to_string()).into());
+            return Err(self.graph_not_found(&name).into());
         }
     }
 }
@@ -72,13 +94,8 @@ impl gen::inference::Host for WasiNnCtx {
         &mut self,
         graph_id: gen::graph::Graph,
     ) -> wasmtime::Result<Result<gen::inference::GraphExecutionContext, gen::errors::Error>> {
-        let exec_context = if let Some(graph) = self.graphs.get(graph_id) {
-            graph.init_execution_context()?
-        } else {
-            return Err(UsageError::InvalidGraphHandle.into());
-        };
-
-        let exec_context_id = self.executions.insert(exec_context);
+        let exec_context = self.graph(graph_id)?.init_execution_context()?;
+        let exec_context_id = self.executions.insert(exec_context)?;
         Ok(Ok(exec_context_id))
     }
 
@@ -89,12 +106,9 @@ impl gen::inference::Host for WasiNnCtx {
         index: u32,
         tensor: gen::tensor::Tensor,
     ) -> wasmtime::Result<Result<(), gen::errors::Error>> {
-        if let Some(exec_context) = self.executions.get_mut(exec_context_id) {
-            exec_context.set_input(index, &tensor)?;
-            Ok(Ok(()))
-        } else {
-            Err(UsageError::InvalidGraphHandle.into())
-        }
+        self.execution_mut(exec_context_id)?
+            .set_input(index, &tensor)?;
+        Ok(Ok(()))
     }
 
     /// Compute the inference on the given inputs.
@@ -104,12 +118,9 @@ impl gen::inference::Host for WasiNnCtx {
         &mut self,
         exec_context_id: gen::inference::GraphExecutionContext,
     ) -> wasmtime::Result<Result<(), gen::errors::Error>> {
-        if let Some(exec_context) = self.executions.get_mut(exec_context_id) {
-            exec_context.compute()?;
-            Ok(Ok(()))
-        } else {
-            Err(UsageError::InvalidExecutionContextHandle.into())
-        }
+        let timeout = self.timeout;
+        self.execution_mut(exec_context_id)?.compute(timeout)?;
+        Ok(Ok(()))
     }
 
     /// Extract the outputs after inference.
@@ -118,18 +129,59 @@ impl gen::inference::Host for WasiNnCtx {
         exec_context_id: gen::inference::GraphExecutionContext,
         index: u32,
     ) -> wasmtime::Result<Result<gen::tensor::TensorData, gen::errors::Error>> {
-        if let Some(exec_context) = self.executions.get_mut(exec_context_id) {
-            // Read the output bytes. TODO: this involves a hard-coded upper
-            // limit on the tensor size that is necessary because there is no
-            // way to introspect the graph outputs
-            // (https://github.com/WebAssembly/wasi-nn/issues/37).
-            let mut destination = vec![0; 1024 * 1024];
-            let bytes_read = exec_context.get_output(index, &mut destination)?;
-            destination.truncate(bytes_read as usize);
-            Ok(Ok(destination))
-        } else {
-            Err(UsageError::InvalidGraphHandle.into())
-        }
+        let exec_context = self.execution_mut(exec_context_id)?;
+        // Read the output bytes. TODO: this involves a hard-coded upper limit
+        // on the tensor size that is necessary because there is no way to
+        // introspect the graph outputs
+        // (https://github.com/WebAssembly/wasi-nn/issues/37).
+        let mut destination = vec![0; 1024 * 1024];
+        let bytes_read = exec_context.get_output(index, &mut destination)?;
+        destination.truncate(bytes_read as usize);
+        Ok(Ok(destination))
+    }
+}
+
+/// Only `compute` differs from the synchronous implementation, running the
+/// inference on a blocking thread, see [crate::ExecutionContext]; the other
+/// functions first wait for any inference left running by a dropped `compute`.
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+impl gen_async::inference::Host for WasiNnCtx {
+    async fn init_execution_context(
+        &mut self,
+        graph_id: gen::graph::Graph,
+    ) -> wasmtime::Result<Result<gen::inference::GraphExecutionContext, gen::errors::Error>> {
+        gen::inference::Host::init_execution_context(self, graph_id)
+    }
+
+    async fn set_input(
+        &mut self,
+        exec_context_id: gen::inference::GraphExecutionContext,
+        index: u32,
+        tensor: gen::tensor::Tensor,
+    ) -> wasmtime::Result<Result<(), gen::errors::Error>> {
+        self.execution_mut(exec_context_id)?.ready().await;
+        gen::inference::Host::set_input(self, exec_context_id, index, tensor)
+    }
+
+    async fn compute(
+        &mut self,
+        exec_context_id: gen::inference::GraphExecutionContext,
+    ) -> wasmtime::Result<Result<(), gen::errors::Error>> {
+        let timeout = self.timeout;
+        self.execution_mut(exec_context_id)?
+            .compute_async(timeout)
+            .await?;
+        Ok(Ok(()))
+    }
+
+    async fn get_output(
+        &mut self,
+        exec_context_id: gen::inference::GraphExecutionContext,
+        index: u32,
+    ) -> wasmtime::Result<Result<gen::tensor::TensorData, gen::errors::Error>> {
+        self.execution_mut(exec_context_id)?.ready().await;
+        gen::inference::Host::get_output(self, exec_context_id, index)
     }
 }
 
@@ -142,6 +194,12 @@ impl TryFrom<gen::graph::GraphEncoding> for crate::backend::BackendKind {
     fn try_from(value: gen::graph::GraphEncoding) -> Result<Self, Self::Error> {
         match value {
             gen::graph::GraphEncoding::Openvino => Ok(crate::backend::BackendKind::OpenVINO),
+            gen::graph::GraphEncoding::Onnx => Ok(crate::backend::BackendKind::Onnx),
+            gen::graph::GraphEncoding::Tensorflow => Ok(crate::backend::BackendKind::Tensorflow),
+            gen::graph::GraphEncoding::Pytorch => Ok(crate::backend::BackendKind::PyTorch),
+            gen::graph::GraphEncoding::Tensorflowlite => {
+                Ok(crate::backend::BackendKind::TensorflowLite)
+            }
             _ => Err(UsageError::InvalidEncoding(value.into())),
         }
     }
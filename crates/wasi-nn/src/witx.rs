@@ -44,7 +44,11 @@ mod gen {
             match e {
                 WasiNnError::BackendError(_) => unimplemented!(),
                 WasiNnError::GuestError(_) => unimplemented!(),
+                // The guest may retry once it drops some of its handles.
+                WasiNnError::UsageError(UsageError::TooManyHandles) => Ok(types::NnErrno::Busy),
                 WasiNnError::UsageError(_) => unimplemented!(),
+                // The inference was cancelled; the guest may retry it.
+                WasiNnError::Timeout(_) => Ok(types::NnErrno::RuntimeError),
             }
         }
     }
@@ -74,17 +78,17 @@ impl<'a> gen::wasi_ephemeral_nn::WasiEphemeralNn for WasiNnCtx {
         } else {
             return Err(UsageError::InvalidEncoding(encoding.into()).into());
         };
-        let graph_id = self.graphs.insert(graph);
+        let graph_id = self.graphs.insert(graph)?;
         Ok(graph_id.into())
     }
 
     fn load_by_name<'b>(&mut self, name: &wiggle::GuestPtr<'b, str>) -> Result<gen::types::Graph> {
         let name = name.as_str()?.unwrap();
         if let Some(graph) = self.registry.get_mut(&name) {
-            let graph_id = self.graphs.insert(graph.clone().into());
+            let graph_id = self.graphs.insert(graph.clone().into())?;
             Ok(graph_id.into())
         } else {
-            return Err(UsageError::NotFound(name.to_string()).into());
+            return Err(self.graph_not_found(&name).into());
         }
     }
 
@@ -92,13 +96,8 @@ impl<'a> gen::wasi_ephemeral_nn::WasiEphemeralNn for WasiNnCtx {
         &mut self,
         graph_id: gen::types::Graph,
     ) -> Result<gen::types::GraphExecutionContext> {
-        let exec_context = if let Some(graph) = self.graphs.get_mut(graph_id.into()) {
-            graph.init_execution_context()?
-        } else {
-            return Err(UsageError::InvalidGraphHandle.into());
-        };
-
-        let exec_context_id = self.executions.insert(exec_context);
+        let exec_context = self.graph(graph_id.into())?.init_execution_context()?;
+        let exec_context_id = self.executions.insert(exec_context)?;
         Ok(exec_context_id.into())
     }
 
@@ -108,24 +107,18 @@ impl<'a> gen::wasi_ephemeral_nn::WasiEphemeralNn for WasiNnCtx {
         index: u32,
         tensor: &gen::types::Tensor<'b>,
     ) -> Result<()> {
-        if let Some(exec_context) = self.executions.get_mut(exec_context_id.into()) {
-            let tensor = crate::wit::types::Tensor {
-                dimensions: tensor.dimensions.to_vec()?,
-                tensor_type: tensor.type_.into(),
-                data: tensor.data.to_vec()?,
-            };
-            Ok(exec_context.set_input(index, &tensor)?)
-        } else {
-            Err(UsageError::InvalidGraphHandle.into())
-        }
+        let exec_context = self.execution_mut(exec_context_id.into())?;
+        let tensor = crate::wit::types::Tensor {
+            dimensions: tensor.dimensions.to_vec()?,
+            tensor_type: tensor.type_.into(),
+            data: tensor.data.to_vec()?,
+        };
+        exec_context.set_input(index, &tensor)
     }
 
     fn compute(&mut self, exec_context_id: gen::types::GraphExecutionContext) -> Result<()> {
-        if let Some(exec_context) = self.executions.get_mut(exec_context_id.into()) {
-            Ok(exec_context.compute()?)
-        } else {
-            Err(UsageError::InvalidExecutionContextHandle.into())
-        }
+        let timeout = self.timeout;
+        self.execution_mut(exec_context_id.into())?.compute(timeout)
     }
 
     fn get_output<'b>(
@@ -135,15 +128,12 @@ impl<'a> gen::wasi_ephemeral_nn::WasiEphemeralNn for WasiNnCtx {
         out_buffer: &GuestPtr<'_, u8>,
         out_buffer_max_size: u32,
     ) -> Result<u32> {
-        if let Some(exec_context) = self.executions.get_mut(exec_context_id.into()) {
-            let mut destination = out_buffer
-                .as_array(out_buffer_max_size)
-                .as_slice_mut()?
-                .expect("cannot use with shared memories; see https://github.com/bytecodealliance/wasmtime/issues/5235 (TODO)");
-            Ok(exec_context.get_output(index, &mut destination)?)
-        } else {
-            Err(UsageError::InvalidGraphHandle.into())
-        }
+        let exec_context = self.execution_mut(exec_context_id.into())?;
+        let mut destination = out_buffer
+            .as_array(out_buffer_max_size)
+            .as_slice_mut()?
+            .expect("cannot use with shared memories; see https://github.com/bytecodealliance/wasmtime/issues/5235 (TODO)");
+        exec_context.get_output(index, &mut destination)
     }
 }
 
@@ -154,6 +144,12 @@ impl TryFrom<gen::types::GraphEncoding> for crate::backend::BackendKind {
     fn try_from(value: gen::types::GraphEncoding) -> std::result::Result<Self, Self::Error> {
         match value {
             gen::types::GraphEncoding::Openvino => Ok(crate::backend::BackendKind::OpenVINO),
+            gen::types::GraphEncoding::Onnx => Ok(crate::backend::BackendKind::Onnx),
+            gen::types::GraphEncoding::Tensorflow => Ok(crate::backend::BackendKind::Tensorflow),
+            gen::types::GraphEncoding::Pytorch => Ok(crate::backend::BackendKind::PyTorch),
+            gen::types::GraphEncoding::Tensorflowlite => {
+                Ok(crate::backend::BackendKind::TensorflowLite)
+            }
             _ => Err(UsageError::InvalidEncoding(value.into())),
         }
     }
@@ -191,3 +187,83 @@ impl From<gen::types::TensorType> for crate::wit::types::TensorType {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::gen::types::{NnErrno, UserErrorConversion};
+    use super::gen::wasi_ephemeral_nn::WasiEphemeralNn;
+    use super::*;
+    use crate::backend::{BackendError, BackendExecutionContext, BackendGraph};
+    use crate::wit::types::Tensor;
+    use crate::{ExecutionContext, Graph, InMemoryRegistry};
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    struct FakeGraph;
+    impl BackendGraph for FakeGraph {
+        fn init_execution_context(&self) -> std::result::Result<ExecutionContext, BackendError> {
+            let exec_context: Box<dyn BackendExecutionContext> = Box::new(FakeExecutionContext);
+            Ok(exec_context.into())
+        }
+    }
+
+    struct FakeExecutionContext;
+    impl BackendExecutionContext for FakeExecutionContext {
+        fn set_input(
+            &mut self,
+            _index: u32,
+            _tensor: &Tensor,
+        ) -> std::result::Result<(), BackendError> {
+            Ok(())
+        }
+        fn compute(&mut self) -> std::result::Result<(), BackendError> {
+            Ok(())
+        }
+        fn compute_with_timeout(
+            &mut self,
+            _timeout: Duration,
+        ) -> std::result::Result<bool, BackendError> {
+            Ok(false)
+        }
+        fn get_output(
+            &mut self,
+            _index: u32,
+            _destination: &mut [u8],
+        ) -> std::result::Result<u32, BackendError> {
+            Ok(0)
+        }
+    }
+
+    fn ctx_with_graph() -> (WasiNnCtx, gen::types::Graph) {
+        let mut ctx = WasiNnCtx::new(HashMap::new(), Box::new(InMemoryRegistry::new()));
+        let graph: Box<dyn BackendGraph> = Box::new(FakeGraph);
+        let graph_id = ctx.graphs.insert(Graph::from(graph)).unwrap();
+        (ctx, graph_id.into())
+    }
+
+    #[test]
+    fn too_many_handles() {
+        let (mut ctx, graph) = ctx_with_graph();
+        ctx.set_max_handles(Some(1));
+        ctx.init_execution_context(graph).unwrap();
+
+        let e = ctx.init_execution_context(graph).unwrap_err();
+        assert!(matches!(
+            ctx.nn_errno_from_wasi_nn_error(e),
+            Ok(NnErrno::Busy)
+        ));
+    }
+
+    #[test]
+    fn timeout() {
+        let (mut ctx, graph) = ctx_with_graph();
+        ctx.set_timeout(Some(Duration::from_millis(1)));
+        let exec_context = ctx.init_execution_context(graph).unwrap();
+
+        let e = ctx.compute(exec_context).unwrap_err();
+        assert!(matches!(
+            ctx.nn_errno_from_wasi_nn_error(e),
+            Ok(NnErrno::RuntimeError)
+        ));
+    }
+}
@@ -577,6 +577,11 @@ forward_type_impls! {
     (T: ComponentType) Vec<T> => [T],
 }
 
+#[cfg(feature = "bytes")]
+forward_type_impls! {
+    () bytes::Bytes => [u8],
+}
+
 macro_rules! forward_lowers {
     ($(($($generics:tt)*) $a:ty => $b:ty,)*) => ($(
         unsafe impl <$($generics)*> Lower for $a {
@@ -610,6 +615,11 @@ forward_lowers! {
     (T: Lower) Vec<T> => [T],
 }
 
+#[cfg(feature = "bytes")]
+forward_lowers! {
+    () bytes::Bytes => [u8],
+}
+
 macro_rules! forward_string_lifts {
     ($($a:ty,)*) => ($(
         unsafe impl Lift for $a {
@@ -654,6 +664,26 @@ forward_list_lifts! {
     Vec<T>,
 }
 
+// Bytes are copied out of linear memory in one go, rather than lifted one by
+// one like the elements of other lists, after which clones of the `Bytes`
+// share them.
+#[cfg(feature = "bytes")]
+unsafe impl Lift for bytes::Bytes {
+    fn lift(cx: &mut LiftContext<'_>, ty: InterfaceType, src: &Self::Lower) -> Result<Self> {
+        let list = <WasmList<u8> as Lift>::lift(cx, ty, src)?;
+        Ok(bytes::Bytes::copy_from_slice(
+            &cx.memory()[list.ptr..][..list.len],
+        ))
+    }
+
+    fn load(cx: &mut LiftContext<'_>, ty: InterfaceType, bytes: &[u8]) -> Result<Self> {
+        let list = <WasmList<u8> as Lift>::load(cx, ty, bytes)?;
+        Ok(bytes::Bytes::copy_from_slice(
+            &cx.memory()[list.ptr..][..list.len],
+        ))
+    }
+}
+
 // Macro to help generate `ComponentType` implementations for primitive types
 // such as integers, char, bool, etc.
 macro_rules! integers {
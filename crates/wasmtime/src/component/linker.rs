@@ -394,19 +394,19 @@ impl<T> LinkerInstance<'_, T> {
     ///
     /// The `dtor` closure is provided the store state as the first argument
     /// along with the representation of the resource that was just destroyed.
+    /// An error returned from `dtor` traps the guest which dropped the
+    /// resource.
     ///
     /// [`Resource<U>`]: crate::component::Resource
     pub fn resource<U: 'static>(
         &mut self,
         name: &str,
-        dtor: impl Fn(StoreContextMut<'_, T>, u32) + Send + Sync + 'static,
+        dtor: impl Fn(StoreContextMut<'_, T>, u32) -> Result<()> + Send + Sync + 'static,
     ) -> Result<()> {
         let name = self.strings.intern(name);
         let dtor = Arc::new(crate::func::HostFunc::wrap(
             &self.engine,
-            move |mut cx: crate::Caller<'_, T>, param: u32| {
-                dtor(cx.as_context_mut(), param);
-            },
+            move |mut cx: crate::Caller<'_, T>, param: u32| dtor(cx.as_context_mut(), param),
         ));
         self.insert(name, Definition::Resource(ResourceType::host::<U>(), dtor))
     }
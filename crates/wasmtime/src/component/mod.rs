@@ -40,10 +40,36 @@ pub mod __internal {
     };
     pub use super::matching::InstanceType;
     pub use crate::map_maybe_uninit;
+
+    /// The component names of the fields of a record deriving
+    /// `ComponentType`, in order, which generated bindings check against
+    /// their WIT names at compile time.
+    pub trait ComponentRecord {
+        const FIELD_NAMES: &'static [&'static str];
+    }
+
+    /// Compares two strings in a `const` context, for compile-time checks in
+    /// generated bindings.
+    pub const fn str_eq(a: &str, b: &str) -> bool {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
     pub use crate::store::StoreOpaque;
     pub use anyhow;
     #[cfg(feature = "async")]
     pub use async_trait::async_trait;
+    #[cfg(feature = "bytes")]
+    pub use bytes;
     pub use wasmtime_environ;
     pub use wasmtime_environ::component::{CanonicalAbiInfo, ComponentTypes, InterfaceType};
 }
@@ -283,6 +309,14 @@ pub(crate) use self::store::ComponentStoreData;
 ///     // This option defaults to `false`.
 ///     async: true,
 ///
+///     // Like `async`, but only for imports or only for exports, such as to
+///     // keep host imports synchronous while calling exports with
+///     // `call_async`. Async imports can't be combined with sync exports as
+///     // `Config::async_support` requires exports to be called with
+///     // `call_async`. Both default to `false`.
+///     import_async: false,
+///     export_async: true,
+///
 ///     // This can be used to translate WIT return values of the form
 ///     // `result<T, error-type>` into `Result<T, RustErrorType>` in Rust.
 ///     // The `RustErrorType` structure will have an automatically generated
@@ -290,9 +324,14 @@ pub(crate) use self::store::ComponentStoreData;
 ///     // `RustErrorType` additionally can also represent a trap to
 ///     // conveniently flatten all errors into one container.
 ///     //
+///     // Mappings apply to imported functions unless prefixed with `export`,
+///     // which applies them to the `call_*` methods of exported functions
+///     // instead, so one error type can be mapped differently for each.
+///     //
 ///     // By default this option is not specified.
 ///     trappable_error_type: {
 ///         interface::ErrorType: RustErrorType,
+///         export interface::ErrorType: RustExportErrorType,
 ///     },
 ///
 ///     // Restrict the code generated to what's needed for the interface
@@ -309,6 +348,200 @@ pub(crate) use self::store::ComponentStoreData;
 ///     with: {
 ///         "a": somewhere::else::a,
 ///     },
+///
+///     // Derive `serde::Serialize` and `serde::Deserialize` for generated
+///     // records, variants, unions, and enums. The serialized names are the
+///     // original WIT names rather than the Rust names. Requires `serde` to be
+///     // a dependency of the crate invoking this macro.
+///     //
+///     // This option defaults to `false`.
+///     serde: true,
+///
+///     // Additional derives to add to generated records, variants, unions,
+///     // enums, and flags. Traits which are already derived or implemented
+///     // for a type, such as `Clone` or `Debug`, are not derived a second
+///     // time.
+///     additional_derives: [Hash, PartialOrd],
+///
+///     // Mark generated enums and variants as `#[non_exhaustive]` so that
+///     // adding new cases to them in WIT isn't a breaking change for Rust
+///     // code matching on them.
+///     //
+///     // This option defaults to `false`.
+///     non_exhaustive: true,
+///
+///     // Emit compile-time assertions that the size and alignment of each
+///     // generated type matches its component model definition. Turning this
+///     // off reduces the number of items the compiler has to process for
+///     // large WIT files.
+///     //
+///     // This option defaults to `true`.
+///     emit_type_asserts: false,
+///
+///     // Map host-implemented resources in WIT to the Rust types that
+///     // represent them. Handles to these resources are generated as
+///     // `Resource<T>` of the mapped type, and `add_to_linker` registers them
+///     // with a destructor that calls the `drop` method of the generated
+///     // `Host{Resource}` trait. Handles to any other resources are generated
+///     // as `ResourceAny`.
+///     resources: {
+///         "file": crate::MyFile,
+///     },
+///
+///     // Fail to compile, listing them, if any host-implemented resource
+///     // imported by the world isn't mapped in `resources` above, or if an
+///     // entry there maps no such resource. Defaults to `false`.
+///     require_resources: true,
+///
+///     // Refer to the `anyhow` and `async_trait` crates directly in generated
+///     // code instead of through wasmtime's internal re-exports of them. Both
+///     // crates must then be dependencies of the crate invoking this macro.
+///     //
+///     // This option defaults to `false`.
+///     use_external_deps: true,
+///
+///     // Allow instances to omit exported interfaces of the world. Accessors for
+///     // exported interfaces then return `Option<&Interface>`, which is `None` when
+///     // the instance didn't export that interface, rather than instantiation
+///     // failing with an error.
+///     //
+///     // This option defaults to `false`.
+///     optional_exports: true,
+///
+///     // How the modules for interfaces in WIT packages are laid out. `Nested`
+///     // places them in modules named after the package's namespace and name,
+///     // such as `ns::pkg::iface`, while `Flat` places them directly at the root
+///     // as `iface`.
+///     //
+///     // This option defaults to `Nested`.
+///     module_path_style: Flat,
+///
+///     // Parameters, given as a function and the WIT name of one of its
+///     // parameters, whose values are logged as `"<redacted>"` in `tracing`
+///     // events instead of their debug representation. This keeps secrets such
+///     // as passwords out of logs when `tracing` is enabled. Functions of
+///     // interfaces are named `interface#func` and world-level functions by
+///     // their bare WIT name.
+///     tracing_redact: [("foo:bar/auth#login", "password")],
+///
+///     // Derive `Default` for generated records whose fields all have a default
+///     // value. Records containing enums, variants, results or resource handles
+///     // don't derive it.
+///     //
+///     // This option defaults to `false`.
+///     derive_default: true,
+///
+///     // Generate code which can be used within a `#![no_std]` crate that
+///     // declares `extern crate alloc;`. Error types then don't implement
+///     // `std::error::Error`. Defaults to `false`.
+///     no_std: false,
+///
+///     // Lints to allow on the generated interface modules, replacing the
+///     // default set of lints which generated code is known to trip. An empty
+///     // list lints generated code like any other code.
+///     allowed_lints: [dead_code, clippy::type_complexity],
+///
+///     // How owned lists are represented: `Vec` generates `Vec<T>` while
+///     // `BoxedSlice` generates `Box<[T]>`, which avoids keeping spare capacity
+///     // around. Borrowed lists are always `&[T]`.
+///     //
+///     // This option defaults to `Vec`.
+///     list_representation: BoxedSlice,
+///
+///     // The lifetime used by generated types which borrow their contents, which
+///     // may need to be renamed to avoid clashing with surrounding code.
+///     //
+///     // This option defaults to `'a`.
+///     lifetime: 'input,
+///
+///     // Generate an `EXPORT_NAMES` constant and an `export_by_name` method on
+///     // the world's struct so that exports can be looked up dynamically. Each
+///     // export is returned as a `&dyn Any` which downcasts to its generated
+///     // type, or to `wasmtime::component::Func` for exported functions. Defaults
+///     // to `false`.
+///     export_lookup: true,
+///
+///     // Mark generated `call_*` methods with `#[must_use]` and a message about
+///     // ignoring traps, on top of the plain warning for unused `Result`s.
+///     // Defaults to `false`.
+///     must_use_results: true,
+///
+///     // Escape WIT names which are Rust keywords as raw identifiers, such as
+///     // `r#type`, instead of with a trailing underscore, such as `type_`.
+///     // Defaults to `false`.
+///     raw_identifiers: true,
+///
+///     // Generate `AsRef<str>` and `FromStr` impls for enums which use the WIT
+///     // names of their cases, such as `"not-found"`. Defaults to `false`.
+///     enum_string_conversions: true,
+///
+///     // Remap individual WIT types to existing Rust types, which are
+///     // re-exported under their generated name instead of being generated. Keys
+///     // are the package and interface, or world, of the type followed by its
+///     // name. Unlike `with`, the rest of the interface is still generated.
+///     type_with: {
+///         "foo:bar/baz/timestamp": crate::time::Timestamp,
+///     },
+///
+///     // The `tracing::Level` of the spans and events emitted with `tracing`.
+///     // Defaults to `TRACE`.
+///     tracing_level: DEBUG,
+///
+///     // Derive `PartialEq` and `Eq` for records, variants and unions, or just
+///     // `PartialEq` when they contain floats. Defaults to `false`.
+///     derive_eq: true,
+///
+///     // Imported functions, named like the functions in `tracing_redact`,
+///     // whose host trait methods take `&self` rather than `&mut self`, for
+///     // read-only functions which hosts want to call with shared access to
+///     // their state. `add_to_linker` still gets the host with its `&mut T`
+///     // getter.
+///     read_only_imports: ["foo:bar/baz#get-size"],
+///
+///     // Generate a `HostVtable` struct for each imported interface which holds
+///     // its host functions as boxed closures, for embeddings calling them
+///     // without a `Linker`. Can't be combined with `async`. Defaults to `false`.
+///     import_vtable: false,
+///
+///     // Records, named like the types in `type_with`, which get a
+///     // `{field}_iter` method for each of their list fields.
+///     list_field_iters: ["foo:bar/baz/inventory"],
+///
+///     // Generate an associated `{CASE}_VALUE: u32` constant for each case
+///     // of enums, holding the discriminant it's lifted and lowered as.
+///     // Defaults to `false`.
+///     enum_discriminant_consts: true,
+///
+///     // Text emitted verbatim before and after the generated bindings,
+///     // respectively. Both default to nothing.
+///     header: "use crate::prelude::*;",
+///     footer: "impl MyWorld {}",
+///
+///     // Generate a `{function}_typed` method returning the `TypedFunc` of each
+///     // exported function, for callers which hold onto it. Defaults to `false`.
+///     typed_func_getters: true,
+///
+///     // Attach the spans of `tracing` to the futures of async functions with
+///     // `tracing::Instrument` rather than entering them. Defaults to `false`.
+///     tracing_instrument: true,
+///
+///     // Records, named like the types in `type_with`, which get a `#[repr(C)]`
+///     // `{Record}Ffi` companion struct with `From` conversions both ways.
+///     ffi_records: ["foo:bar/baz/point"],
+///
+///     // Imported interfaces, named like the keys of `with`, whose host functions
+///     // are async while those of other interfaces stay synchronous. Like
+///     // `import_async`, this requires `export_async`.
+///     async_interfaces: ["foo:bar/filesystem"],
+///
+///     // Represent owned `list<u8>` values as `bytes::Bytes`, which requires the
+///     // `bytes` feature of `wasmtime`. Defaults to `false`.
+///     bytes_for_u8_lists: true,
+///
+///     // With `ownership: Borrowing`, have host trait methods of imported
+///     // functions take strings as `&str` and plain lists as slices, borrowed
+///     // from the lifted arguments. Defaults to `false`.
+///     borrowed_import_params: true,
 /// });
 /// ```
 ///
@@ -294,6 +294,62 @@ where
         }
     }
 
+    /// Converts an owned [`ResourceAny`] into a typed [`Resource`], for
+    /// example one returned by a dynamic API such as
+    /// [`Func::call`](crate::component::Func::call).
+    ///
+    /// This fails if `resource` isn't of the host resource type for `T`, if
+    /// it's a borrow, or if it's currently lent out as a borrow.
+    pub fn try_from_resource_any(
+        resource: ResourceAny,
+        mut store: impl AsContextMut,
+    ) -> Result<Self> {
+        let store = store.as_context_mut();
+        if resource.ty != ResourceType::host::<T>() {
+            bail!("resource type mismatch");
+        }
+        let own_state = match &resource.own_state {
+            Some(state) => state,
+            None => bail!("cannot convert a `borrow` resource into a `Resource`"),
+        };
+        assert_eq!(
+            store.0.id(),
+            own_state.store,
+            "wrong store used to convert resource"
+        );
+        let rep = host_resource_tables(store.0).resource_lift_own(None, resource.idx)?;
+        Ok(Resource::new_own(rep))
+    }
+
+    /// Converts this owned resource into a [`ResourceAny`], for example to
+    /// pass it to a dynamic API such as
+    /// [`Func::call`](crate::component::Func::call).
+    ///
+    /// Note that the host is responsible for the representation of the
+    /// resource either way, so dropping the returned [`ResourceAny`] with
+    /// [`ResourceAny::resource_drop`] doesn't run a destructor.
+    ///
+    /// This fails if this is a borrow or if it's already been moved into a
+    /// guest.
+    pub fn try_into_resource_any(self, mut store: impl AsContextMut) -> Result<ResourceAny> {
+        let store = store.as_context_mut();
+        let idx = match self.state.load(Relaxed) {
+            BORROW => bail!("cannot convert a `borrow` resource into a `ResourceAny`"),
+            TAKEN => bail!("host resource already consumed"),
+            NOT_IN_TABLE => host_resource_tables(store.0).resource_lower_own(None, self.rep),
+            idx => idx,
+        };
+        Ok(ResourceAny {
+            idx,
+            ty: ResourceType::host::<T>(),
+            own_state: Some(OwnState {
+                store: store.0.id(),
+                flags: None,
+                dtor: None,
+            }),
+        })
+    }
+
     fn lower_to_index<U>(&self, cx: &mut LowerContext<'_, U>, ty: InterfaceType) -> Result<u32> {
         match ty {
             InterfaceType::Own(t) => {
@@ -510,6 +566,19 @@ impl ResourceAny {
         self.own_state.is_some()
     }
 
+    /// Same as [`Resource::try_from_resource_any`].
+    pub fn try_into_resource<T: 'static>(self, store: impl AsContextMut) -> Result<Resource<T>> {
+        Resource::try_from_resource_any(self, store)
+    }
+
+    /// Same as [`Resource::try_into_resource_any`].
+    pub fn try_from_resource<T: 'static>(
+        resource: Resource<T>,
+        store: impl AsContextMut,
+    ) -> Result<Self> {
+        resource.try_into_resource_any(store)
+    }
+
     /// Destroy this resource and release any state associated with it.
     ///
     /// This is required to be called (or the async version) for all instances
@@ -1,9 +1,11 @@
-use crate::rust::{to_rust_ident, to_rust_upper_camel_case, RustGenerator, TypeMode};
+use crate::rust::{
+    to_rust_ident, to_rust_raw_ident, to_rust_upper_camel_case, Lifetime, RustGenerator, TypeMode,
+};
 use crate::types::{TypeInfo, Types};
 use anyhow::{anyhow, bail, Context};
 use heck::*;
 use indexmap::IndexMap;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write as _;
 use std::io::{Read, Write};
 use std::mem;
@@ -47,13 +49,37 @@ struct Wasmtime {
     sizes: SizeAlign,
     interface_names: HashMap<InterfaceId, InterfaceName>,
     with_name_counter: usize,
+    /// The `use` alias generated for each path remapped through `with`, so
+    /// that interfaces remapped to the same path share one alias.
+    with_aliases: HashMap<String, String>,
+    /// The interface whose module is generated at each path, to detect
+    /// interfaces which collide under [`Opts::module_path_style`].
+    module_paths: HashMap<String, InterfaceId>,
+    /// The `tracing::Level` variant named by [`Opts::tracing_level`].
+    tracing_level: &'static str,
+    /// A mapping of wit types, and the direction they're mapped in, to their
+    /// rust type name equivalent. This is the pre-processed version of
+    /// `opts.trappable_error_type`, where the types have been eagerly resolved.
+    trappable_errors: IndexMap<(TypeId, Direction), String>,
+    /// The pre-processed version of `opts.type_with`, mapping wit types to
+    /// the Rust paths they're remapped to.
+    remapped_types: HashMap<TypeId, String>,
+    /// The pre-processed version of `opts.list_field_iters`.
+    list_field_iter_types: HashSet<TypeId>,
+    /// The pre-processed version of `opts.ffi_records`.
+    ffi_record_types: HashSet<TypeId>,
 }
 
 struct ImportInterface {
+    /// The name of the interface in the world, such as `foo:bar/baz`.
+    name: String,
     snake: String,
     module: String,
+    /// Whether the interface's host functions are async.
+    async_: bool,
 }
 struct ImportFunction {
+    name: String,
     add_to_linker: String,
     sig: String,
 }
@@ -63,6 +89,9 @@ struct Exports {
     fields: BTreeMap<String, (String, String)>,
     modules: BTreeMap<Option<PackageName>, Vec<String>>,
     funcs: Vec<String>,
+    /// The WIT name of each export paired with an expression looking it up
+    /// as an `Option<&dyn Any>`, for [`Opts::export_lookup`].
+    lookups: Vec<(String, String)>,
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -84,17 +113,147 @@ pub enum Ownership {
     },
 }
 
-#[derive(Default, Debug, Clone)]
+impl std::str::FromStr for Ownership {
+    type Err = anyhow::Error;
+
+    /// Parses the spelling used by [`Ownership`]'s `Display` implementation,
+    /// which is one of `owning`, `borrowing`, or
+    /// `borrowing-duplicate-if-necessary`.
+    fn from_str(s: &str) -> anyhow::Result<Ownership> {
+        match s {
+            "owning" => Ok(Ownership::Owning),
+            "borrowing" => Ok(Ownership::Borrowing {
+                duplicate_if_necessary: false,
+            }),
+            "borrowing-duplicate-if-necessary" => Ok(Ownership::Borrowing {
+                duplicate_if_necessary: true,
+            }),
+            _ => bail!(
+                "unrecognized ownership: `{s}`; expected `owning`, `borrowing`, or \
+                 `borrowing-duplicate-if-necessary`"
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Ownership {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Ownership::Owning => "owning",
+            Ownership::Borrowing {
+                duplicate_if_necessary: false,
+            } => "borrowing",
+            Ownership::Borrowing {
+                duplicate_if_necessary: true,
+            } => "borrowing-duplicate-if-necessary",
+        })
+    }
+}
+
+/// How the modules generated for interfaces defined in WIT packages are laid
+/// out, see [`Opts::module_path_style`].
+#[derive(Default, Debug, Clone, Copy)]
+pub enum ModulePathStyle {
+    /// Modules for interfaces are nested within a module for their package,
+    /// which is itself nested within a module for the package's namespace,
+    /// as in `ns::pkg::iface`.
+    #[default]
+    Nested,
+
+    /// Modules for interfaces are placed at the root of the generated code,
+    /// as in `iface`.
+    Flat,
+
+    /// Modules for interfaces are nested within the modules returned by the
+    /// function for their package, from the outermost to the innermost. An
+    /// empty list is equivalent to [`ModulePathStyle::Flat`].
+    Custom(fn(&PackageName) -> Vec<String>),
+}
+
+/// The formatter used for generated code, see [`Opts::formatter`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formatter {
+    /// Generated code isn't formatted.
+    #[default]
+    None,
+
+    /// Generated code is formatted by running `rustfmt`, which must be in
+    /// `PATH`.
+    Rustfmt,
+
+    /// Generated code is formatted in-process with the `prettyplease` crate.
+    /// Plain comments, unlike doc comments, are dropped from the output.
+    ///
+    /// Requires the `prettyplease` feature.
+    #[cfg(feature = "prettyplease")]
+    Prettyplease,
+}
+
+/// The Rust type used for owned lists, see [`Opts::list_representation`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListRepresentation {
+    /// Owned lists are `Vec<T>`.
+    #[default]
+    Vec,
+
+    /// Owned lists are `Box<[T]>`, which doesn't carry spare capacity.
+    BoxedSlice,
+}
+
+/// Lints which generated interface modules allow by default, see
+/// [`Opts::allowed_lints`]. These are the lints tripped by the shapes of code
+/// generated for arbitrary WIT, such as unit results bound to `r`, clones of
+/// fields which happen to be `Copy` or `into` conversions of FFI fields which
+/// happen to have the same type.
+const DEFAULT_ALLOWED_LINTS: &[&str] = &[
+    "dead_code",
+    "unused_imports",
+    "clippy::borrow_deref_ref",
+    "clippy::clone_on_copy",
+    "clippy::let_unit_value",
+    "clippy::needless_lifetimes",
+    "clippy::too_many_arguments",
+    "clippy::type_complexity",
+    "clippy::unused_unit",
+    "clippy::useless_conversion",
+];
+
+/// The equality traits derived, with [`Opts::derive_eq`], for types without
+/// floats or typed resource handles.
+const EQ_TRAITS: &[&str] = &["PartialEq", "Eq"];
+
+/// Options for generating bindings.
+///
+/// Fields are public for backwards compatibility, but [`Opts::builder`] is the
+/// preferred way to construct options since it isn't affected by new options
+/// being added.
+#[derive(Debug, Clone)]
 pub struct Opts {
     /// Whether or not `rustfmt` is executed to format generated code.
+    ///
+    /// This is equivalent to setting [`Opts::formatter`] to
+    /// [`Formatter::Rustfmt`].
     pub rustfmt: bool,
 
     /// Whether or not to emit `tracing` macro calls on function entry/exit.
     pub tracing: bool,
 
-    /// Whether or not to use async rust functions and traits.
+    /// Whether or not to use async rust functions and traits, for both
+    /// imports and exports. This is a shorthand for setting both
+    /// [`Opts::import_async`] and [`Opts::export_async`].
     pub async_: bool,
 
+    /// Whether host imports are async functions, added to linkers with
+    /// `func_wrap_async`. Since async imports require
+    /// `Config::async_support`, which in turn requires exports to be called
+    /// with `call_async`, this can only be enabled with
+    /// [`Opts::export_async`].
+    pub import_async: bool,
+
+    /// Whether exports are called, and worlds instantiated, with async
+    /// functions, while imports may stay synchronous.
+    pub export_async: bool,
+
     /// A list of "trappable errors" which are used to replace the `E` in
     /// `result<T, E>` found in WIT.
     pub trappable_error_type: Vec<TrappableError>,
@@ -108,11 +267,277 @@ pub struct Opts {
     /// Remapping of interface names to rust module names.
     /// TODO: is there a better type to use for the value of this map?
     pub with: HashMap<String, String>,
+
+    /// Whether or not to derive `serde::Serialize` and `serde::Deserialize`
+    /// for records, variants, unions, and enums, using the original WIT names
+    /// when serialized.
+    pub serde: bool,
+
+    /// Additional derive attributes to add to generated types, such as
+    /// `Hash` or `PartialOrd`.
+    pub additional_derives: Vec<String>,
+
+    /// Whether or not to mark generated enums and variants as
+    /// `#[non_exhaustive]`.
+    pub non_exhaustive: bool,
+
+    /// Whether or not to emit compile-time assertions that the size and
+    /// alignment of generated types match their component model definitions.
+    pub emit_type_asserts: bool,
+
+    /// The number of spaces used per level of indentation in generated code
+    /// when `rustfmt` isn't executed.
+    pub indent: usize,
+
+    /// Mapping of names of host-implemented resources in WIT to the Rust
+    /// type used to represent them, which should be a path that's valid from
+    /// anywhere in the crate, such as `crate::MyResource`.
+    ///
+    /// Handles to mapped resources are `wasmtime::component::Resource<T>`.
+    /// Handles to other resources are `wasmtime::component::ResourceAny`.
+    pub resources: HashMap<String, String>,
+
+    /// Whether every host-implemented resource imported by the world must be
+    /// mapped in [`Opts::resources`] rather than falling back to
+    /// `ResourceAny`, failing to generate otherwise, like
+    /// [`Opts::try_generate`].
+    pub require_resources: bool,
+
+    /// Whether or not generated code refers to the `anyhow` and `async_trait`
+    /// crates directly, rather than through wasmtime's internal re-exports.
+    /// Both crates must then be dependencies of the crate using the bindings.
+    pub use_external_deps: bool,
+
+    /// Whether or not exported interfaces which are missing from an instance are
+    /// tolerated. When enabled the accessor for each exported interface returns
+    /// an `Option` which is `None` if the instance doesn't export it, instead of
+    /// instantiation failing.
+    pub optional_exports: bool,
+
+    /// How the modules generated for interfaces defined in WIT packages are
+    /// laid out.
+    ///
+    /// Two interfaces must not end up with the same module path, otherwise
+    /// generating the bindings fails.
+    pub module_path_style: ModulePathStyle,
+
+    /// Parameters which are logged as `"<redacted>"` rather than their debug
+    /// representation in `tracing` events, identified by the function, as
+    /// `interface#func` such as `foo:bar/baz#login` or just the WIT name of a
+    /// world-level function, and by the WIT name of the parameter.
+    pub tracing_redact: HashSet<(String, String)>,
+
+    /// Whether or not to derive `Default` for generated records whose fields
+    /// all have a default value, such as integers, strings, lists, options and
+    /// other such records.
+    pub derive_default: bool,
+
+    /// The formatter used for generated code. `Formatter::Prettyplease`, with
+    /// the `prettyplease` feature, formats in-process instead of running
+    /// `rustfmt` as a subprocess.
+    ///
+    /// Setting [`Opts::rustfmt`] is equivalent to [`Formatter::Rustfmt`].
+    pub formatter: Formatter,
+
+    /// Whether to generate code for use in a `#![no_std]` crate.
+    ///
+    /// `alloc` types are then named by their full paths, which requires the crate
+    /// to declare `extern crate alloc;`, and error types don't implement
+    /// `std::error::Error`. Not supported together with `async`.
+    pub no_std: bool,
+
+    /// Lints allowed on each generated interface module, such as `dead_code`
+    /// or `clippy::type_complexity`.
+    ///
+    /// Defaults to the lints which the generated code is known to trip, and may
+    /// be cleared to lint generated code like any other.
+    pub allowed_lints: Vec<String>,
+
+    /// How owned lists are represented in generated types.
+    pub list_representation: ListRepresentation,
+
+    /// The lifetime used by generated types which borrow their contents, such as
+    /// `'a` (the default) in `Foo<'a>`.
+    pub lifetime: String,
+
+    /// Whether to generate `export_by_name` on the world's struct, along with an
+    /// `EXPORT_NAMES` list, so that exports can be enumerated and fetched by
+    /// their WIT name rather than through their own accessor methods.
+    pub export_lookup: bool,
+
+    /// Whether to mark the generated `call_*` methods of exports with
+    /// `#[must_use]`, explaining that ignoring their result ignores traps.
+    pub must_use_results: bool,
+
+    /// Whether WIT names which are Rust keywords are escaped as raw identifiers,
+    /// such as `r#type`, rather than with a trailing underscore, such as `type_`.
+    /// `crate`, `self` and `super` can't be raw identifiers and always use the
+    /// underscore.
+    pub raw_identifiers: bool,
+
+    /// Whether to generate `AsRef<str>` and `FromStr` impls for enums which
+    /// convert to and from the WIT names of their cases.
+    pub enum_string_conversions: bool,
+
+    /// Remapping of individual WIT types to existing Rust types, keyed by the
+    /// package and interface, or world, that define the type followed by its
+    /// name, such as `foo:bar/baz/timestamp`.
+    ///
+    /// Remapped types are re-exported with `pub use` under their generated name
+    /// instead of being generated, and the rest of their interface is still
+    /// generated as usual.
+    pub type_with: HashMap<String, String>,
+
+    /// The level of the spans and events emitted with [`Opts::tracing`], one of
+    /// `TRACE`, `DEBUG`, `INFO`, `WARN` or `ERROR` in any case.
+    pub tracing_level: String,
+
+    /// Whether to derive `PartialEq` and `Eq` for generated records, variants
+    /// and unions, with only `PartialEq` for those holding floats, which
+    /// aren't `Eq`. Types holding typed resource handles derive neither.
+    pub derive_eq: bool,
+
+    /// Imported functions, identified as `interface#func` such as
+    /// `foo:bar/baz#get-size` or just by the WIT name of a world-level
+    /// function, whose host trait methods take `&self` rather than `&mut self`,
+    /// so that implementations of read-only functions can be called with
+    /// shared access to the host state.
+    ///
+    /// The generated `add_to_linker` still reaches the host through its
+    /// `get: impl Fn(&mut T) -> &mut U` closure and `caller.data_mut()`: that
+    /// getter can't be called with a `&T`, taking a second shared getter would
+    /// change the signature of every `add_to_linker`, and calls into a store
+    /// are never concurrent anyway, so this only relaxes what the trait
+    /// implementations require.
+    pub read_only_imports: HashSet<String>,
+
+    /// Whether to generate a `HostVtable` struct for each imported interface,
+    /// holding its host functions as boxed closures so that they can be called
+    /// without a `Linker`. Not supported with [`Opts::async_`].
+    pub import_vtable: bool,
+
+    /// Records, identified like the keys of [`Opts::type_with`], for which a
+    /// `{field}_iter` method is generated for each of their list-typed fields,
+    /// iterating over the elements without exposing the list representation.
+    pub list_field_iters: HashSet<String>,
+
+    /// Whether to generate an associated `{CASE}_VALUE: u32` constant on enums
+    /// for each case, holding its discriminant in WIT case order.
+    pub enum_discriminant_consts: bool,
+
+    /// Text emitted verbatim at the very start of the generated bindings, such
+    /// as inner attributes, module docs or `use` items. It's left untouched by
+    /// the formatter.
+    pub header: Option<String>,
+
+    /// Text emitted verbatim at the very end of the generated bindings, such
+    /// as additional impls. It's left untouched by the formatter.
+    pub footer: Option<String>,
+
+    /// Whether to generate a `{function}_typed` method alongside each exported
+    /// function's `call_{function}`, returning its `TypedFunc` for callers which
+    /// hold onto it, such as to call it repeatedly.
+    pub typed_func_getters: bool,
+
+    /// Whether the spans of [`Opts::tracing`] are attached to the futures of
+    /// async functions with `tracing::Instrument`, rather than entered within
+    /// them, which would wrongly keep them entered across `.await` points. Sync
+    /// functions enter their spans either way.
+    pub tracing_instrument: bool,
+
+    /// Records, identified like the keys of [`Opts::type_with`], for which a
+    /// `#[repr(C)]` companion struct named `{Record}Ffi` is generated along with
+    /// `From` conversions both ways, for passing them on to native code. Their
+    /// fields must be booleans, integers, floats or records also listed here.
+    pub ffi_records: HashSet<String>,
+
+    /// Imported interfaces, identified like the keys of [`Opts::with`], whose
+    /// host functions are async even without [`Opts::import_async`], while those
+    /// of other interfaces stay synchronous. Like [`Opts::import_async`], this
+    /// requires [`Opts::export_async`].
+    pub async_interfaces: HashSet<String>,
+
+    /// Whether owned `list<u8>` values are `bytes::Bytes`, rather than lists of
+    /// the [`Opts::list_representation`], so that they're lifted with a single
+    /// copy out of linear memory and clones share the data. Requires the `bytes`
+    /// feature of `wasmtime`, as well as a dependency on the `bytes` crate with
+    /// [`Opts::use_external_deps`].
+    pub bytes_for_u8_lists: bool,
+
+    /// Whether host trait methods of imported functions take strings as
+    /// `&str`, and anonymous lists of types without lists or handles as
+    /// slices, with [`Ownership::Borrowing`]. They're borrowed from the lifted
+    /// arguments rather than handed over.
+    ///
+    /// Opt-in since it changes the signatures of existing host traits.
+    pub borrowed_import_params: bool,
 }
 
-#[derive(Debug, Clone)]
+impl Default for Opts {
+    fn default() -> Opts {
+        Opts {
+            rustfmt: false,
+            tracing: false,
+            async_: false,
+            import_async: false,
+            export_async: false,
+            trappable_error_type: Vec::new(),
+            ownership: Ownership::default(),
+            only_interfaces: false,
+            with: HashMap::new(),
+            serde: false,
+            additional_derives: Vec::new(),
+            non_exhaustive: false,
+            emit_type_asserts: true,
+            indent: 4,
+            resources: HashMap::new(),
+            use_external_deps: false,
+            optional_exports: false,
+            module_path_style: ModulePathStyle::default(),
+            tracing_redact: HashSet::new(),
+            derive_default: false,
+            formatter: Formatter::default(),
+            no_std: false,
+            allowed_lints: DEFAULT_ALLOWED_LINTS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            list_representation: ListRepresentation::default(),
+            lifetime: "'a".to_string(),
+            export_lookup: false,
+            must_use_results: false,
+            raw_identifiers: false,
+            enum_string_conversions: false,
+            type_with: HashMap::new(),
+            tracing_level: "TRACE".to_string(),
+            derive_eq: false,
+            read_only_imports: HashSet::new(),
+            import_vtable: false,
+            list_field_iters: HashSet::new(),
+            enum_discriminant_consts: false,
+            header: None,
+            footer: None,
+            typed_func_getters: false,
+            tracing_instrument: false,
+            ffi_records: HashSet::new(),
+            async_interfaces: HashSet::new(),
+            bytes_for_u8_lists: false,
+            borrowed_import_params: false,
+            require_resources: false,
+        }
+    }
+}
+
+/// A WIT error type mapped to a Rust error type, see
+/// [`Opts::trappable_error_type`].
+///
+/// Created with [`TrappableError::new`], or with a struct literal ending in
+/// `..Default::default()` so that it doesn't have to spell out fields which
+/// have a default, such as [`TrappableError::direction`].
+#[derive(Debug, Default, Clone)]
 pub struct TrappableError {
-    /// The package and interface that define the error type being mapped.
+    /// The package and interface, or world, that define the error type being
+    /// mapped, such as `foo:bar/baz`.
     pub wit_package_path: String,
 
     /// The name of the error type in WIT that is being mapped.
@@ -120,24 +545,625 @@ pub struct TrappableError {
 
     /// The name, in Rust, of the error type to generate.
     pub rust_type_name: String,
+
+    /// Whether the mapping applies to imported functions, whose host
+    /// implementations return the Rust error type, or to exported functions,
+    /// whose `call_*` methods return it.
+    pub direction: Direction,
+}
+
+impl TrappableError {
+    /// Maps the error type `wit_type_name` of `wit_package_path` to the Rust
+    /// type `rust_type_name` for imported functions.
+    pub fn new(
+        wit_package_path: impl Into<String>,
+        wit_type_name: impl Into<String>,
+        rust_type_name: impl Into<String>,
+    ) -> Self {
+        TrappableError {
+            wit_package_path: wit_package_path.into(),
+            wit_type_name: wit_type_name.into(),
+            rust_type_name: rust_type_name.into(),
+            direction: Direction::default(),
+        }
+    }
+
+    /// Sets [`TrappableError::direction`].
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+}
+
+/// Which side of a world a [`TrappableError`] applies to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Functions imported into the world, implemented by the host.
+    #[default]
+    Import,
+    /// Functions exported from the world, called by the host.
+    Export,
 }
 
 impl Opts {
+    /// Creates a builder for [`Opts`], starting from the default options.
+    pub fn builder() -> OptsBuilder {
+        OptsBuilder {
+            opts: Opts::default(),
+        }
+    }
+
+    /// Whether imports are async, taking [`Opts::async_`] into account.
+    pub fn imports_async(&self) -> bool {
+        self.async_ || self.import_async
+    }
+
+    /// Whether exports are async, taking [`Opts::async_`] into account.
+    pub fn exports_async(&self) -> bool {
+        self.async_ || self.export_async
+    }
+
+    /// The formatter in effect, taking [`Opts::rustfmt`] into account.
+    fn effective_formatter(&self) -> Formatter {
+        match self.formatter {
+            Formatter::None if self.rustfmt => Formatter::Rustfmt,
+            formatter => formatter,
+        }
+    }
+
     pub fn generate(&self, resolve: &Resolve, world: WorldId) -> String {
+        self.build(resolve, world)
+            .unwrap_or_else(|e| panic!("{e:?}"))
+    }
+
+    /// Generates the bindings, returning an error if the options conflict or
+    /// the wit types named in them can't be resolved.
+    fn build(&self, resolve: &Resolve, world: WorldId) -> anyhow::Result<String> {
+        if self.no_std && (self.imports_async() || self.exports_async()) {
+            bail!("`no_std` bindings can't be generated with `async` enabled");
+        }
+        if self.import_vtable && self.imports_async() {
+            bail!("`import_vtable` can't be generated with `async` enabled");
+        }
+        if self.import_vtable && !self.async_interfaces.is_empty() {
+            bail!("`import_vtable` can't be generated with `async_interfaces`");
+        }
+        if self.imports_async() && !self.exports_async() {
+            bail!(
+                "`import_async` requires `export_async`, as exports must be called with \
+                 `call_async` once `Config::async_support` is enabled for async imports"
+            );
+        }
+        if !self.async_interfaces.is_empty() && !self.exports_async() {
+            bail!(
+                "`async_interfaces` requires `export_async`, as exports must be called with \
+                 `call_async` once `Config::async_support` is enabled for async imports"
+            );
+        }
+        let ident = self.lifetime.strip_prefix('\'').unwrap_or("");
+        if ident.is_empty()
+            || ident == "_"
+            || ident == "static"
+            || ident.starts_with(|c: char| c.is_ascii_digit())
+            || !ident.chars().all(|c| c.is_alphanumeric() || c == '_')
+        {
+            bail!("invalid lifetime `{}` for generated types", self.lifetime);
+        }
+        let tracing_level = match self.tracing_level.to_ascii_uppercase().as_str() {
+            "TRACE" => "TRACE",
+            "DEBUG" => "DEBUG",
+            "INFO" => "INFO",
+            "WARN" => "WARN",
+            "ERROR" => "ERROR",
+            _ => bail!("invalid tracing level `{}`", self.tracing_level),
+        };
+        if self.require_resources {
+            self.check_resources(resolve, world)?;
+        }
         let mut r = Wasmtime::default();
         r.sizes.fill(resolve);
         r.opts = self.clone();
+        r.tracing_level = tracing_level;
+        r.src.set_indent_width(self.indent);
+        r.resolve_opts(resolve)?;
         r.generate(resolve, world)
     }
+
+    /// Same as [`Opts::generate`] with [`Opts::require_resources`] set, so
+    /// that every host-implemented resource imported by `world` must be
+    /// mapped in [`Opts::resources`] rather than falling back to `ResourceAny`.
+    ///
+    /// Returns an error listing all the resources which aren't mapped, along
+    /// with any entries of [`Opts::resources`] naming no such resource.
+    pub fn try_generate(&self, resolve: &Resolve, world: WorldId) -> anyhow::Result<String> {
+        let mut opts = self.clone();
+        opts.require_resources = true;
+        opts.build(resolve, world)
+    }
+
+    /// Checks that [`Opts::resources`] maps exactly the resources of the
+    /// interfaces imported by `world`, for [`Opts::require_resources`].
+    /// Interfaces remapped with [`Opts::with`] aren't generated, so their
+    /// resources don't need an entry.
+    fn check_resources(&self, resolve: &Resolve, world: WorldId) -> anyhow::Result<()> {
+        let mut imported = HashSet::new();
+        let mut missing = Vec::new();
+        for (key, item) in resolve.worlds[world].imports.iter() {
+            let id = match item {
+                WorldItem::Interface(id) => *id,
+                _ => continue,
+            };
+            if self.with.contains_key(&resolve.name_world_key(key)) {
+                continue;
+            }
+            for (name, ty) in resolve.interfaces[id].types.iter() {
+                if !matches!(resolve.types[*ty].kind, TypeDefKind::Resource) {
+                    continue;
+                }
+                imported.insert(name.as_str());
+                if !self.resources.contains_key(name) {
+                    missing.push(format!("`{name}` of `{}`", resolve.name_world_key(key)));
+                }
+            }
+        }
+        let mut unknown = self
+            .resources
+            .keys()
+            .filter(|name| !imported.contains(name.as_str()))
+            .map(|name| format!("`{name}`"))
+            .collect::<Vec<_>>();
+        unknown.sort();
+
+        let mut errors = Vec::new();
+        if !missing.is_empty() {
+            errors.push(format!(
+                "no entry in `resources` for {}",
+                missing.join(", ")
+            ));
+        }
+        if !unknown.is_empty() {
+            errors.push(format!(
+                "no imported resource for the `resources` entries {}",
+                unknown.join(", ")
+            ));
+        }
+        if !errors.is_empty() {
+            bail!("{}", errors.join("; "));
+        }
+        Ok(())
+    }
+
+    /// Same as [`Opts::generate`], except that the world is selected by name,
+    /// either `foo:bar/baz` for the world `baz` of the package `foo:bar`, or
+    /// a bare `baz` for the only world named `baz` among all packages. An
+    /// empty name selects the only world in `resolve`.
+    ///
+    /// Returns an error if no world, or more than one, matches.
+    pub fn generate_by_name(&self, resolve: &Resolve, world_name: &str) -> anyhow::Result<String> {
+        let world = select_world_by_name(resolve, world_name)?;
+        self.build(resolve, world)
+    }
+
+    /// Same as [`Opts::generate`] except that the generated code is returned
+    /// as tokens, suitable for splicing directly into the output of a
+    /// procedural macro.
+    ///
+    /// Formatting is irrelevant for tokens so `rustfmt` is never executed
+    /// here, regardless of [`Opts::rustfmt`] and [`Opts::formatter`].
+    ///
+    /// Returns an error if the generated code can't be tokenized, e.g.
+    /// because [`Opts::header`] or [`Opts::footer`] aren't valid Rust.
+    pub fn generate_tokens(
+        &self,
+        resolve: &Resolve,
+        world: WorldId,
+    ) -> anyhow::Result<proc_macro2::TokenStream> {
+        let mut opts = self.clone();
+        opts.rustfmt = false;
+        opts.formatter = Formatter::None;
+        opts.build(resolve, world)?
+            .parse()
+            .map_err(|e| anyhow!("failed to tokenize the generated bindings: {e}"))
+    }
+}
+
+/// Builder for [`Opts`], created with [`Opts::builder`].
+///
+/// Each method corresponds to the field of [`Opts`] of the same name.
+#[derive(Debug, Clone)]
+pub struct OptsBuilder {
+    opts: Opts,
+}
+
+impl OptsBuilder {
+    pub fn rustfmt(mut self, rustfmt: bool) -> Self {
+        self.opts.rustfmt = rustfmt;
+        self
+    }
+
+    pub fn tracing(mut self, tracing: bool) -> Self {
+        self.opts.tracing = tracing;
+        self
+    }
+
+    pub fn async_(mut self, async_: bool) -> Self {
+        self.opts.async_ = async_;
+        self
+    }
+
+    pub fn import_async(mut self, import_async: bool) -> Self {
+        self.opts.import_async = import_async;
+        self
+    }
+
+    pub fn export_async(mut self, export_async: bool) -> Self {
+        self.opts.export_async = export_async;
+        self
+    }
+
+    /// Adds an entry to [`Opts::trappable_error_type`].
+    pub fn trappable_error(mut self, error: TrappableError) -> Self {
+        self.opts.trappable_error_type.push(error);
+        self
+    }
+
+    pub fn ownership(mut self, ownership: Ownership) -> Self {
+        self.opts.ownership = ownership;
+        self
+    }
+
+    pub fn only_interfaces(mut self, only_interfaces: bool) -> Self {
+        self.opts.only_interfaces = only_interfaces;
+        self
+    }
+
+    /// Adds an entry to [`Opts::with`].
+    pub fn with(mut self, interface: impl Into<String>, path: impl Into<String>) -> Self {
+        self.opts.with.insert(interface.into(), path.into());
+        self
+    }
+
+    pub fn serde(mut self, serde: bool) -> Self {
+        self.opts.serde = serde;
+        self
+    }
+
+    /// Adds an entry to [`Opts::additional_derives`].
+    pub fn additional_derive(mut self, path: impl Into<String>) -> Self {
+        self.opts.additional_derives.push(path.into());
+        self
+    }
+
+    pub fn non_exhaustive(mut self, non_exhaustive: bool) -> Self {
+        self.opts.non_exhaustive = non_exhaustive;
+        self
+    }
+
+    pub fn emit_type_asserts(mut self, emit_type_asserts: bool) -> Self {
+        self.opts.emit_type_asserts = emit_type_asserts;
+        self
+    }
+
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.opts.indent = indent;
+        self
+    }
+
+    /// Adds an entry to [`Opts::resources`].
+    pub fn resource(mut self, resource: impl Into<String>, rust_type: impl Into<String>) -> Self {
+        self.opts
+            .resources
+            .insert(resource.into(), rust_type.into());
+        self
+    }
+
+    pub fn require_resources(mut self, require_resources: bool) -> Self {
+        self.opts.require_resources = require_resources;
+        self
+    }
+
+    pub fn use_external_deps(mut self, use_external_deps: bool) -> Self {
+        self.opts.use_external_deps = use_external_deps;
+        self
+    }
+
+    pub fn optional_exports(mut self, optional_exports: bool) -> Self {
+        self.opts.optional_exports = optional_exports;
+        self
+    }
+
+    pub fn module_path_style(mut self, module_path_style: ModulePathStyle) -> Self {
+        self.opts.module_path_style = module_path_style;
+        self
+    }
+
+    /// Adds an entry to [`Opts::tracing_redact`].
+    pub fn tracing_redact_param(
+        mut self,
+        function: impl Into<String>,
+        param: impl Into<String>,
+    ) -> Self {
+        self.opts
+            .tracing_redact
+            .insert((function.into(), param.into()));
+        self
+    }
+
+    pub fn derive_default(mut self, derive_default: bool) -> Self {
+        self.opts.derive_default = derive_default;
+        self
+    }
+
+    pub fn formatter(mut self, formatter: Formatter) -> Self {
+        self.opts.formatter = formatter;
+        self
+    }
+
+    pub fn no_std(mut self, no_std: bool) -> Self {
+        self.opts.no_std = no_std;
+        self
+    }
+
+    pub fn allowed_lints(mut self, allowed_lints: Vec<String>) -> Self {
+        self.opts.allowed_lints = allowed_lints;
+        self
+    }
+
+    pub fn list_representation(mut self, list_representation: ListRepresentation) -> Self {
+        self.opts.list_representation = list_representation;
+        self
+    }
+
+    pub fn lifetime(mut self, lifetime: String) -> Self {
+        self.opts.lifetime = lifetime;
+        self
+    }
+
+    pub fn export_lookup(mut self, export_lookup: bool) -> Self {
+        self.opts.export_lookup = export_lookup;
+        self
+    }
+
+    pub fn must_use_results(mut self, must_use_results: bool) -> Self {
+        self.opts.must_use_results = must_use_results;
+        self
+    }
+
+    pub fn raw_identifiers(mut self, raw_identifiers: bool) -> Self {
+        self.opts.raw_identifiers = raw_identifiers;
+        self
+    }
+
+    pub fn enum_string_conversions(mut self, enum_string_conversions: bool) -> Self {
+        self.opts.enum_string_conversions = enum_string_conversions;
+        self
+    }
+
+    /// Adds an entry to [`Opts::type_with`].
+    pub fn type_with(mut self, wit_type: impl Into<String>, path: impl Into<String>) -> Self {
+        self.opts.type_with.insert(wit_type.into(), path.into());
+        self
+    }
+
+    pub fn tracing_level(mut self, tracing_level: impl Into<String>) -> Self {
+        self.opts.tracing_level = tracing_level.into();
+        self
+    }
+
+    pub fn derive_eq(mut self, derive_eq: bool) -> Self {
+        self.opts.derive_eq = derive_eq;
+        self
+    }
+
+    /// Adds an entry to [`Opts::read_only_imports`].
+    pub fn read_only_import(mut self, function: impl Into<String>) -> Self {
+        self.opts.read_only_imports.insert(function.into());
+        self
+    }
+
+    pub fn import_vtable(mut self, import_vtable: bool) -> Self {
+        self.opts.import_vtable = import_vtable;
+        self
+    }
+
+    /// Adds an entry to [`Opts::list_field_iters`].
+    pub fn list_field_iter(mut self, wit_type: impl Into<String>) -> Self {
+        self.opts.list_field_iters.insert(wit_type.into());
+        self
+    }
+
+    pub fn enum_discriminant_consts(mut self, enum_discriminant_consts: bool) -> Self {
+        self.opts.enum_discriminant_consts = enum_discriminant_consts;
+        self
+    }
+
+    pub fn header(mut self, header: impl Into<String>) -> Self {
+        self.opts.header = Some(header.into());
+        self
+    }
+
+    pub fn footer(mut self, footer: impl Into<String>) -> Self {
+        self.opts.footer = Some(footer.into());
+        self
+    }
+
+    pub fn typed_func_getters(mut self, typed_func_getters: bool) -> Self {
+        self.opts.typed_func_getters = typed_func_getters;
+        self
+    }
+
+    pub fn tracing_instrument(mut self, tracing_instrument: bool) -> Self {
+        self.opts.tracing_instrument = tracing_instrument;
+        self
+    }
+
+    /// Adds an entry to [`Opts::ffi_records`].
+    pub fn ffi_record(mut self, wit_type: impl Into<String>) -> Self {
+        self.opts.ffi_records.insert(wit_type.into());
+        self
+    }
+
+    /// Adds an entry to [`Opts::async_interfaces`].
+    pub fn async_interface(mut self, interface: impl Into<String>) -> Self {
+        self.opts.async_interfaces.insert(interface.into());
+        self
+    }
+
+    pub fn bytes_for_u8_lists(mut self, bytes_for_u8_lists: bool) -> Self {
+        self.opts.bytes_for_u8_lists = bytes_for_u8_lists;
+        self
+    }
+
+    pub fn borrowed_import_params(mut self, borrowed_import_params: bool) -> Self {
+        self.opts.borrowed_import_params = borrowed_import_params;
+        self
+    }
+
+    pub fn build(self) -> Opts {
+        self.opts
+    }
 }
 
 impl Wasmtime {
-    fn name_interface(&mut self, resolve: &Resolve, id: InterfaceId, name: &WorldKey) -> bool {
+    /// Resolves the wit types named in the options, once for the whole
+    /// world, returning an error for any which can't be resolved.
+    fn resolve_opts(&mut self, resolve: &Resolve) -> anyhow::Result<()> {
+        self.trappable_errors = self
+            .opts
+            .trappable_error_type
+            .iter()
+            .map(|te| {
+                let id = resolve_type_in_package(resolve, &te.wit_package_path, &te.wit_type_name)
+                    .context(format!("resolving {:?}", te))?;
+                Ok(((id, te.direction), te.rust_type_name.clone()))
+            })
+            .collect::<anyhow::Result<IndexMap<_, _>>>()?;
+
+        self.remapped_types = self
+            .opts
+            .type_with
+            .iter()
+            .map(|(wit_type, path)| Ok((resolve_type_path(resolve, wit_type)?, path.clone())))
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+        self.list_field_iter_types = self
+            .opts
+            .list_field_iters
+            .iter()
+            .map(|wit_type| resolve_type_path(resolve, wit_type))
+            .collect::<anyhow::Result<HashSet<_>>>()?;
+
+        self.ffi_record_types = self
+            .opts
+            .ffi_records
+            .iter()
+            .map(|wit_type| {
+                let id = resolve_type_path(resolve, wit_type)?;
+                match resolve.types[resolve_type_definition_id(resolve, id)].kind {
+                    TypeDefKind::Record(_) => Ok(id),
+                    _ => bail!("{wit_type:?} isn't a record"),
+                }
+            })
+            .collect::<anyhow::Result<HashSet<_>>>()?;
+
+        // The companion structs can only hold plain data, copied as is, and
+        // the companions of other listed records.
+        let is_ffi_record = |ty: TypeId| {
+            let def = resolve_type_definition_id(resolve, ty);
+            self.ffi_record_types
+                .iter()
+                .any(|id| resolve_type_definition_id(resolve, *id) == def)
+        };
+        for wit_type in self.opts.ffi_records.iter() {
+            let id = resolve_type_definition_id(resolve, resolve_type_path(resolve, wit_type)?);
+            let record = match &resolve.types[id].kind {
+                TypeDefKind::Record(record) => record,
+                _ => unreachable!(),
+            };
+            for field in record.fields.iter() {
+                match field.ty {
+                    Type::Bool
+                    | Type::U8
+                    | Type::U16
+                    | Type::U32
+                    | Type::U64
+                    | Type::S8
+                    | Type::S16
+                    | Type::S32
+                    | Type::S64
+                    | Type::Float32
+                    | Type::Float64 => {}
+                    Type::Id(field_id) if is_ffi_record(field_id) => {}
+                    _ => bail!(
+                        "field `{}` of the FFI record {wit_type:?} must be a boolean, \
+                         an integer, a float or a record also listed in `ffi_records`",
+                        field.name
+                    ),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The `#[allow]` attribute placed on generated interface modules, if any.
+    fn module_allows(&self) -> String {
+        if self.opts.allowed_lints.is_empty() {
+            return String::new();
+        }
+        format!("#[allow({})]", self.opts.allowed_lints.join(", "))
+    }
+
+    /// The item which brings `anyhow` into scope in generated modules, which
+    /// is nothing when it's an external dependency of the user's crate.
+    fn anyhow_import(&self) -> &'static str {
+        if self.opts.use_external_deps {
+            ""
+        } else {
+            "#[allow(unused_imports)] use wasmtime::component::__internal::anyhow;"
+        }
+    }
+
+    /// The path to the `async_trait` crate used for async host traits.
+    fn async_trait_crate(&self) -> &'static str {
+        if self.opts.use_external_deps {
+            "async_trait"
+        } else {
+            "wasmtime::component::__internal::async_trait"
+        }
+    }
+
+    /// The modules, from the root of the generated code, that the modules of
+    /// interfaces in the package `pkg` are nested within.
+    fn package_module_path(&self, pkg: &PackageName) -> Vec<String> {
+        match self.opts.module_path_style {
+            ModulePathStyle::Nested => {
+                vec![pkg.namespace.to_snake_case(), pkg.name.to_snake_case()]
+            }
+            ModulePathStyle::Flat => Vec::new(),
+            ModulePathStyle::Custom(f) => f(pkg),
+        }
+    }
+
+    fn name_interface(
+        &mut self,
+        resolve: &Resolve,
+        id: InterfaceId,
+        name: &WorldKey,
+    ) -> anyhow::Result<bool> {
         let with_name = resolve.name_world_key(name);
         let entry = if let Some(remapped_path) = self.opts.with.get(&with_name) {
-            let name = format!("__with_name{}", self.with_name_counter);
-            self.with_name_counter += 1;
-            uwriteln!(self.src, "use {remapped_path} as {name};");
+            let name = match self.with_aliases.get(remapped_path) {
+                Some(name) => name.clone(),
+                None => {
+                    let name = format!("__with_name{}", self.with_name_counter);
+                    self.with_name_counter += 1;
+                    uwriteln!(self.src, "use {remapped_path} as {name};");
+                    self.with_aliases
+                        .insert(remapped_path.clone(), name.clone());
+                    name
+                }
+            };
             InterfaceName {
                 remapped: true,
                 path: name,
@@ -148,14 +1174,15 @@ impl Wasmtime {
                 WorldKey::Interface(_) => {
                     let iface = &resolve.interfaces[id];
                     let pkgname = &resolve.packages[iface.package.unwrap()].name;
-                    format!(
-                        "{}::{}::{}",
-                        pkgname.namespace.to_snake_case(),
-                        pkgname.name.to_snake_case(),
-                        iface.name.as_ref().unwrap().to_snake_case()
-                    )
+                    let mut path = self.package_module_path(pkgname);
+                    path.push(iface.name.as_ref().unwrap().to_snake_case());
+                    path.join("::")
                 }
             };
+            let prev = self.module_paths.insert(path.clone(), id);
+            if prev.map_or(false, |prev| prev != id) {
+                bail!("multiple interfaces are generated at the module path `{path}`");
+            }
             InterfaceName {
                 remapped: false,
                 path,
@@ -165,26 +1192,31 @@ impl Wasmtime {
         let remapped = entry.remapped;
         self.interface_names.insert(id, entry);
 
-        remapped
+        Ok(remapped)
     }
 
-    fn generate(&mut self, resolve: &Resolve, id: WorldId) -> String {
+    fn generate(&mut self, resolve: &Resolve, id: WorldId) -> anyhow::Result<String> {
         self.types.analyze(resolve, id);
         let world = &resolve.worlds[id];
         for (name, import) in world.imports.iter() {
             if !self.opts.only_interfaces || matches!(import, WorldItem::Interface(_)) {
-                self.import(resolve, name, import);
+                self.import(resolve, name, import)?;
             }
         }
         for (name, export) in world.exports.iter() {
             if !self.opts.only_interfaces || matches!(export, WorldItem::Interface(_)) {
-                self.export(resolve, name, export);
+                self.export(resolve, name, export)?;
             }
         }
-        self.finish(resolve, id)
+        Ok(self.finish(resolve, id))
     }
 
-    fn import(&mut self, resolve: &Resolve, name: &WorldKey, item: &WorldItem) {
+    fn import(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        item: &WorldItem,
+    ) -> anyhow::Result<()> {
         let mut gen = InterfaceGenerator::new(self, resolve);
         match item {
             WorldItem::Function(func) => {
@@ -192,17 +1224,21 @@ impl Wasmtime {
                 let sig = mem::take(&mut gen.src).into();
                 gen.generate_add_function_to_linker(TypeOwner::None, func, "linker");
                 let add_to_linker = gen.src.into();
-                self.import_functions
-                    .push(ImportFunction { sig, add_to_linker });
+                self.import_functions.push(ImportFunction {
+                    name: func.name.clone(),
+                    sig,
+                    add_to_linker,
+                });
             }
             WorldItem::Interface(id) => {
-                if gen.gen.name_interface(resolve, *id, name) {
-                    return;
+                if gen.gen.name_interface(resolve, *id, name)? {
+                    return Ok(());
                 }
                 gen.current_interface = Some((*id, name, false));
                 gen.types(*id);
                 let key_name = resolve.name_world_key(name);
                 gen.generate_add_to_linker(*id, &key_name);
+                let async_ = gen.imports_async();
 
                 let module = &gen.src[..];
 
@@ -216,14 +1252,15 @@ impl Wasmtime {
                 };
                 let module = format!(
                     "
-                        #[allow(clippy::all)]
+                        {allows}
                         pub mod {snake} {{
-                            #[allow(unused_imports)]
-                            use wasmtime::component::__internal::anyhow;
+                            {anyhow}
 
                             {module}
                         }}
-                    "
+                    ",
+                    allows = gen.gen.module_allows(),
+                    anyhow = gen.gen.anyhow_import(),
                 );
                 let pkg = resolve.interfaces[*id].package.unwrap();
                 let pkgname = match name {
@@ -233,7 +1270,12 @@ impl Wasmtime {
                 self.import_interfaces
                     .entry(pkgname)
                     .or_insert(Vec::new())
-                    .push(ImportInterface { snake, module });
+                    .push(ImportInterface {
+                        name: key_name,
+                        snake,
+                        module,
+                        async_,
+                    });
             }
             WorldItem::Type(ty) => {
                 let name = match name {
@@ -241,13 +1283,20 @@ impl Wasmtime {
                     WorldKey::Interface(_) => unreachable!(),
                 };
                 gen.define_type(name, *ty);
+                gen.define_trappable_error_types(*ty);
                 let body = mem::take(&mut gen.src);
                 self.src.push_str(&body);
             }
         };
+        Ok(())
     }
 
-    fn export(&mut self, resolve: &Resolve, name: &WorldKey, item: &WorldItem) {
+    fn export(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        item: &WorldItem,
+    ) -> anyhow::Result<()> {
         let mut gen = InterfaceGenerator::new(self, resolve);
         let (field, ty, getter) = match item {
             WorldItem::Function(func) => {
@@ -256,6 +1305,13 @@ impl Wasmtime {
                 let (_name, getter) = gen.extract_typed_function(func);
                 assert!(gen.src.is_empty());
                 self.exports.funcs.push(body);
+                self.exports.lookups.push((
+                    func.name.clone(),
+                    format!(
+                        "Some(&self.{} as &dyn core::any::Any)",
+                        func.name.to_snake_case()
+                    ),
+                ));
                 (
                     func.name.to_snake_case(),
                     "wasmtime::component::Func".to_string(),
@@ -264,7 +1320,7 @@ impl Wasmtime {
             }
             WorldItem::Type(_) => unreachable!(),
             WorldItem::Interface(id) => {
-                gen.gen.name_interface(resolve, *id, name);
+                gen.gen.name_interface(resolve, *id, name)?;
                 gen.current_interface = Some((*id, name, true));
                 gen.types(*id);
                 let iface = &resolve.interfaces[*id];
@@ -314,14 +1370,15 @@ impl Wasmtime {
 
                 let module = format!(
                     "
-                        #[allow(clippy::all)]
+                        {allows}
                         pub mod {snake} {{
-                            #[allow(unused_imports)]
-                            use wasmtime::component::__internal::anyhow;
+                            {anyhow}
 
                             {module}
                         }}
-                    "
+                    ",
+                    allows = gen.gen.module_allows(),
+                    anyhow = gen.gen.anyhow_import(),
                 );
                 let pkgname = match name {
                     WorldKey::Name(_) => None,
@@ -336,42 +1393,63 @@ impl Wasmtime {
                     .push(module);
 
                 let name = resolve.name_world_key(name);
-                let (path, method_name) = match pkgname {
-                    Some(pkgname) => (
-                        format!(
-                            "exports::{}::{}::{snake}::{camel}",
-                            pkgname.namespace.to_snake_case(),
-                            pkgname.name.to_snake_case(),
-                        ),
-                        format!(
-                            "{}_{}_{snake}",
-                            pkgname.namespace.to_snake_case(),
-                            pkgname.name.to_snake_case()
-                        ),
-                    ),
-                    None => (format!("exports::{snake}::{camel}"), snake.clone()),
+                let mut module_path = match &pkgname {
+                    Some(pkgname) => self.package_module_path(pkgname),
+                    None => Vec::new(),
                 };
-                let getter = format!(
-                    "\
-                        {path}::new(
-                            &mut __exports.instance(\"{name}\")
-                                .ok_or_else(|| anyhow::anyhow!(\"exported instance `{name}` not present\"))?
-                        )?\
-                    "
-                );
-                let field = format!("interface{}", self.exports.fields.len());
-                self.exports.funcs.push(format!(
-                    "
-                        pub fn {method_name}(&self) -> &{path} {{
-                            &self.{field}
-                        }}
-                    ",
-                ));
-                (field, path, getter)
+                module_path.push(snake);
+                let path = format!("exports::{}::{camel}", module_path.join("::"));
+                let method_name = module_path.join("_");
+                // Named after the interface's module path so that fields are
+                // ordered by content rather than by the order of exports.
+                let field = format!("interface_{method_name}");
+                if self.opts.optional_exports {
+                    let getter = format!(
+                        "\
+                            match __exports.instance(\"{name}\") {{
+                                Some(mut __instance) => Some({path}::new(&mut __instance)?),
+                                None => None,
+                            }}\
+                        "
+                    );
+                    self.exports.funcs.push(format!(
+                        "
+                            pub fn {method_name}(&self) -> Option<&{path}> {{
+                                self.{field}.as_ref()
+                            }}
+                        ",
+                    ));
+                    self.exports.lookups.push((
+                        name,
+                        format!("self.{field}.as_ref().map(|e| e as &dyn core::any::Any)"),
+                    ));
+                    (field, format!("Option<{path}>"), getter)
+                } else {
+                    let getter = format!(
+                        "\
+                            {path}::new(
+                                &mut __exports.instance(\"{name}\")
+                                    .ok_or_else(|| anyhow::anyhow!(\"exported instance `{name}` not present\"))?
+                            )?\
+                        "
+                    );
+                    self.exports.funcs.push(format!(
+                        "
+                            pub fn {method_name}(&self) -> &{path} {{
+                                &self.{field}
+                            }}
+                        ",
+                    ));
+                    self.exports
+                        .lookups
+                        .push((name, format!("Some(&self.{field} as &dyn core::any::Any)")));
+                    (field, path, getter)
+                }
             }
         };
         let prev = self.exports.fields.insert(field, (ty, getter));
         assert!(prev.is_none());
+        Ok(())
     }
 
     fn build_struct(&mut self, resolve: &Resolve, world: WorldId) {
@@ -382,7 +1460,7 @@ impl Wasmtime {
         }
         self.src.push_str("}\n");
 
-        let (async_, async__, send, await_) = if self.opts.async_ {
+        let (async_, async__, send, await_) = if self.opts.exports_async() {
             ("async", "_async", ":Send", ".await")
         } else {
             ("", "", "", "")
@@ -391,7 +1469,7 @@ impl Wasmtime {
         self.toplevel_import_trait(resolve, world);
 
         uwriteln!(self.src, "const _: () = {{");
-        uwriteln!(self.src, "use wasmtime::component::__internal::anyhow;");
+        uwriteln!(self.src, "{}", self.anyhow_import());
 
         uwriteln!(self.src, "impl {camel} {{");
         self.toplevel_add_to_linker(resolve, world);
@@ -420,6 +1498,33 @@ impl Wasmtime {
                     let instance = instance_pre.instantiate{async__}(&mut store){await_}?;
                     Ok((Self::new(store, &instance)?, instance))
                 }}
+            ",
+        );
+        if self.opts.exports_async() {
+            uwriteln!(
+                self.src,
+                "
+                    /// Returns the future which instantiates `instance_pre`,
+                    /// without wrapping up the instance's exports.
+                    ///
+                    /// This splits up [`Self::instantiate_pre`] for callers
+                    /// which drive the future themselves and need to run setup
+                    /// once the instance exists: afterwards the instance is
+                    /// passed to [`Self::new`].
+                    pub fn instantiate_pre_async_future<'a, T: Send>(
+                        mut store: impl wasmtime::AsContextMut<Data = T> + 'a,
+                        instance_pre: &'a wasmtime::component::InstancePre<T>,
+                    ) -> impl core::future::Future<
+                        Output = wasmtime::Result<wasmtime::component::Instance>,
+                    > + 'a {{
+                        async move {{ instance_pre.instantiate_async(&mut store).await }}
+                    }}
+                ",
+            );
+        }
+        uwriteln!(
+            self.src,
+            "
 
                 /// Low-level creation wrapper for wrapping up the exports
                 /// of the `instance` provided in this structure of wasm
@@ -452,11 +1557,49 @@ impl Wasmtime {
             self.src.push_str(func);
         }
 
+        if self.opts.export_lookup {
+            self.export_lookup();
+        }
+
         uwriteln!(self.src, "}}"); // close `impl {camel}`
 
         uwriteln!(self.src, "}};"); // close `const _: () = ...
     }
 
+    fn export_lookup(&mut self) {
+        uwriteln!(
+            self.src,
+            "
+                /// The names of this world's exports, which may be looked up
+                /// with [`Self::export_by_name`].
+                pub const EXPORT_NAMES: &[&str] = &[
+            "
+        );
+        for (name, _) in self.exports.lookups.iter() {
+            uwriteln!(self.src, "\"{name}\",");
+        }
+        uwriteln!(
+            self.src,
+            "
+                ];
+
+                /// Returns the export named `name`, or `None` if there's no such
+                /// export or it's missing from the instance.
+                ///
+                /// Exported interfaces downcast to their generated type and
+                /// exported functions to `wasmtime::component::Func`.
+                pub fn export_by_name(&self, name: &str) -> Option<&dyn core::any::Any> {{
+                    match name {{
+            "
+        );
+        for (name, lookup) in self.exports.lookups.iter() {
+            uwriteln!(self.src, "\"{name}\" => {lookup},");
+        }
+        uwriteln!(self.src, "_ => None,");
+        uwriteln!(self.src, "}}");
+        uwriteln!(self.src, "}}");
+    }
+
     fn finish(&mut self, resolve: &Resolve, world: WorldId) -> String {
         if !self.opts.only_interfaces {
             self.build_struct(resolve, world)
@@ -477,28 +1620,48 @@ impl Wasmtime {
         }
 
         let mut src = mem::take(&mut self.src);
-        if self.opts.rustfmt {
-            let mut child = Command::new("rustfmt")
-                .arg("--edition=2018")
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .spawn()
-                .expect("failed to spawn `rustfmt`");
-            child
-                .stdin
-                .take()
-                .unwrap()
-                .write_all(src.as_bytes())
-                .unwrap();
-            src.as_mut_string().truncate(0);
-            child
-                .stdout
-                .take()
-                .unwrap()
-                .read_to_string(src.as_mut_string())
-                .unwrap();
-            let status = child.wait().unwrap();
-            assert!(status.success());
+        match self.opts.effective_formatter() {
+            Formatter::None => {}
+            Formatter::Rustfmt => {
+                let mut child = Command::new("rustfmt")
+                    .arg("--edition=2018")
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()
+                    .expect("failed to spawn `rustfmt`");
+                child
+                    .stdin
+                    .take()
+                    .unwrap()
+                    .write_all(src.as_bytes())
+                    .unwrap();
+                src.as_mut_string().truncate(0);
+                child
+                    .stdout
+                    .take()
+                    .unwrap()
+                    .read_to_string(src.as_mut_string())
+                    .unwrap();
+                let status = child.wait().unwrap();
+                assert!(status.success());
+            }
+            #[cfg(feature = "prettyplease")]
+            Formatter::Prettyplease => {
+                let file = syn::parse_file(&src).expect("generated bindings should be valid Rust");
+                let formatted = prettyplease::unparse(&file);
+                src.as_mut_string().truncate(0);
+                src.as_mut_string().push_str(&formatted);
+            }
+        }
+
+        if let Some(header) = &self.opts.header {
+            src.as_mut_string().insert_str(0, header);
+            if !header.ends_with('\n') {
+                src.as_mut_string().insert(header.len(), '\n');
+            }
+        }
+        if let Some(footer) = &self.opts.footer {
+            src.as_mut_string().push_str(footer);
         }
 
         src.into()
@@ -507,30 +1670,31 @@ impl Wasmtime {
     fn emit_modules(&mut self, modules: &BTreeMap<Option<PackageName>, Vec<String>>) {
         let mut map = BTreeMap::new();
         for (pkg, modules) in modules {
-            match pkg {
-                Some(pkg) => {
-                    let prev = map
-                        .entry(&pkg.namespace)
-                        .or_insert(BTreeMap::new())
-                        .insert(&pkg.name, modules);
-                    assert!(prev.is_none());
-                }
-                None => {
-                    for module in modules {
-                        uwriteln!(self.src, "{module}");
-                    }
-                }
-            }
+            let path = match pkg {
+                Some(pkg) => self.package_module_path(pkg),
+                None => Vec::new(),
+            };
+            map.entry(path).or_insert(Vec::new()).extend(modules);
         }
-        for (ns, pkgs) in map {
-            uwriteln!(self.src, "pub mod {} {{", ns.to_snake_case());
-            for (pkg, modules) in pkgs {
-                uwriteln!(self.src, "pub mod {} {{", pkg.to_snake_case());
-                for module in modules {
-                    uwriteln!(self.src, "{module}");
-                }
+
+        // Paths are visited in sorted order, so modules shared with the
+        // previous path are kept open and only the rest are closed.
+        let mut open: Vec<String> = Vec::new();
+        for (path, modules) in map {
+            let common = open.iter().zip(&path).take_while(|(a, b)| a == b).count();
+            for _ in common..open.len() {
                 uwriteln!(self.src, "}}");
             }
+            open.truncate(common);
+            for name in &path[common..] {
+                uwriteln!(self.src, "pub mod {name} {{");
+                open.push(name.clone());
+            }
+            for module in modules {
+                uwriteln!(self.src, "{module}");
+            }
+        }
+        for _ in 0..open.len() {
             uwriteln!(self.src, "}}");
         }
     }
@@ -543,8 +1707,8 @@ impl Wasmtime {
         }
 
         let world_camel = to_rust_upper_camel_case(&resolve.worlds[world].name);
-        if self.opts.async_ {
-            uwriteln!(self.src, "#[wasmtime::component::__internal::async_trait]")
+        if self.opts.imports_async() {
+            uwriteln!(self.src, "#[{}::async_trait]", self.async_trait_crate())
         }
         uwriteln!(self.src, "pub trait {world_camel}Imports {{");
         for f in self.import_functions.iter() {
@@ -559,32 +1723,22 @@ impl Wasmtime {
             return;
         }
         let mut interfaces = Vec::new();
+        let mut interface_names = Vec::new();
         for (pkg, imports) in self.import_interfaces.iter() {
             for import in imports {
-                let mut path = String::new();
-                if let Some(pkg) = pkg {
-                    path.push_str(&pkg.namespace.to_snake_case());
-                    path.push_str("::");
-                    path.push_str(&pkg.name.to_snake_case());
-                    path.push_str("::");
-                }
-                path.push_str(&import.snake);
-                interfaces.push(path)
+                let mut path = match pkg {
+                    Some(pkg) => self.package_module_path(pkg),
+                    None => Vec::new(),
+                };
+                path.push(import.snake.clone());
+                interfaces.push(path.join("::"));
+                interface_names.push(import.name.clone());
             }
         }
 
-        uwrite!(
-            self.src,
-            "
-                pub fn add_to_linker<T, U>(
-                    linker: &mut wasmtime::component::Linker<T>,
-                    get: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
-                ) -> wasmtime::Result<()>
-                    where U: \
-            "
-        );
         let world_camel = to_rust_upper_camel_case(&resolve.worlds[world].name);
         let world_trait = format!("{world_camel}Imports");
+        let mut bounds = String::new();
         for (i, name) in interfaces
             .iter()
             .map(|n| format!("{n}::Host"))
@@ -596,24 +1750,122 @@ impl Wasmtime {
             .enumerate()
         {
             if i > 0 {
-                self.src.push_str(" + ");
+                bounds.push_str(" + ");
             }
-            self.src.push_str(&name);
+            bounds.push_str(&name);
         }
-        let maybe_send = if self.opts.async_ {
+        // Async interfaces take `Send` host state, which is then required of
+        // the whole world.
+        let any_async = self
+            .import_interfaces
+            .values()
+            .flatten()
+            .any(|import| import.async_);
+        let maybe_send = if self.opts.imports_async() || any_async {
             " + Send, T: Send"
         } else {
             ""
         };
-        self.src.push_str(maybe_send);
-        self.src.push_str(",\n{\n");
+        uwrite!(
+            self.src,
+            "
+                pub fn add_to_linker<T, U>(
+                    linker: &mut wasmtime::component::Linker<T>,
+                    get: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
+                ) -> wasmtime::Result<()>
+                    where U: {bounds}{maybe_send},
+                {{
+                    Self::add_to_linker_get_result(linker, move |t| Ok(get(t)))
+                }}
+
+                /// Same as [`Self::add_to_linker`], except that `get` may fail,
+                /// in which case the error traps the calling guest.
+                pub fn add_to_linker_get_result<T, U>(
+                    linker: &mut wasmtime::component::Linker<T>,
+                    get: impl Fn(&mut T) -> wasmtime::Result<&mut U> + Send + Sync + Copy + 'static,
+                ) -> wasmtime::Result<()>
+                    where U: {bounds}{maybe_send},
+                {{
+            "
+        );
         for name in interfaces.iter() {
-            uwriteln!(self.src, "{name}::add_to_linker(linker, get)?;");
+            uwriteln!(self.src, "{name}::add_to_linker_get_result(linker, get)?;");
         }
         if !self.import_functions.is_empty() {
-            uwriteln!(self.src, "Self::add_root_to_linker(linker, get)?;");
+            uwriteln!(
+                self.src,
+                "Self::add_root_to_linker_get_result(linker, get)?;"
+            );
         }
         uwriteln!(self.src, "Ok(())\n}}");
+
+        // The same composition as `add_to_linker_get_result`, but as a table
+        // of which hosts can add any subset.
+        let linker = "&mut wasmtime::component::Linker<T>";
+        let get = "fn(&mut T) -> wasmtime::Result<&mut U>";
+        let add = format!("fn({linker}, {get}) -> wasmtime::Result<()>");
+        let vec = if self.opts.no_std {
+            "alloc::vec::Vec"
+        } else {
+            "Vec"
+        };
+        uwrite!(
+            self.src,
+            "
+                /// Each import of this world, named as in WIT, along with the
+                /// function adding it to a linker like
+                /// [`Self::add_to_linker_get_result`] does for all of them, so
+                /// that hosts can choose which imports to provide.
+                pub fn add_to_linker_table<T, U>() -> {vec}<(&'static str, {add})>
+                    where T: 'static, U: 'static + {bounds}{maybe_send},
+                {{
+                    let mut table: {vec}<(&'static str, {add})> = {vec}::new();
+            "
+        );
+        for (name, path) in interface_names.iter().zip(&interfaces) {
+            uwriteln!(
+                self.src,
+                "table.push((\"{name}\", |linker: {linker}, get: {get}| {{
+                    {path}::add_to_linker_get_result(linker, get)
+                }}));"
+            );
+        }
+        for f in self.import_functions.iter() {
+            uwriteln!(
+                self.src,
+                "table.push((\"{}\", |linker: {linker}, get: {get}| {{
+                    let mut linker = linker.root();
+                    {}
+                    Ok(())
+                }}));",
+                f.name,
+                f.add_to_linker,
+            );
+        }
+        uwriteln!(self.src, "table\n}}");
+
+        uwrite!(
+            self.src,
+            "
+                /// Returns the host state implementing this world's imports,
+                /// found in the data of `store` with `get` like in
+                /// [`Self::add_to_linker`], to inspect what the host recorded
+                /// while the guest ran.
+                ///
+                /// `self` isn't used, but is taken so that the imports are
+                /// reached from the instantiated world like its exports are.
+                /// The bindings don't know the store's data type, hence `get`.
+                pub fn imports<'a, T: 'a, U>(
+                    &self,
+                    store: impl Into<wasmtime::StoreContext<'a, T>>,
+                    get: impl Fn(&T) -> &U,
+                ) -> &'a U
+                    where U: {bounds},
+                {{
+                    get(store.into().data())
+                }}
+            "
+        );
         if self.import_functions.is_empty() {
             return;
         }
@@ -626,6 +1878,17 @@ impl Wasmtime {
                     get: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
                 ) -> wasmtime::Result<()>
                     where U: {world_trait}{maybe_send}
+                {{
+                    Self::add_root_to_linker_get_result(linker, move |t| Ok(get(t)))
+                }}
+
+                /// Same as [`Self::add_root_to_linker`], except that `get` may
+                /// fail, in which case the error traps the calling guest.
+                pub fn add_root_to_linker_get_result<T, U>(
+                    linker: &mut wasmtime::component::Linker<T>,
+                    get: impl Fn(&mut T) -> wasmtime::Result<&mut U> + Send + Sync + Copy + 'static,
+                ) -> wasmtime::Result<()>
+                    where U: {world_trait}{maybe_send}
                 {{
                     let mut linker = linker.root();
             ",
@@ -649,7 +1912,7 @@ fn resolve_type_in_package(
         .split_once(':')
         .ok_or_else(|| anyhow!("Invalid package path: missing package identifier"))?;
 
-    let (package_name, iface_name) = rest
+    let (package_name, owner_name) = rest
         .split_once('/')
         .ok_or_else(|| anyhow!("Invalid package path: missing namespace separator"))?;
 
@@ -682,33 +1945,114 @@ fn resolve_type_in_package(
     let (_, &package_id) = packages[0];
     let package = &resolve.packages[package_id];
 
-    let (_, &iface_id) = package
-        .interfaces
-        .iter()
-        .find(|(name, _)| name.as_str() == iface_name)
-        .ok_or_else(|| {
-            anyhow!(
-                "Unknown interface `{}` in package `{}`",
-                iface_name,
-                package_path
-            )
-        })?;
+    let type_id = if let Some(iface_id) = package.interfaces.get(owner_name) {
+        resolve.interfaces[*iface_id].types.get(type_name).copied()
+    } else if let Some(world_id) = package.worlds.get(owner_name) {
+        // Types defined directly in a world are recorded as its imports.
+        match resolve.worlds[*world_id]
+            .imports
+            .get(&WorldKey::Name(type_name.to_string()))
+        {
+            Some(WorldItem::Type(id)) => Some(*id),
+            _ => None,
+        }
+    } else {
+        bail!(
+            "Unknown interface or world `{}` in package `{}`",
+            owner_name,
+            package_path
+        );
+    };
+
+    type_id.ok_or_else(|| {
+        anyhow!(
+            "No type named `{}` in package `{}`",
+            type_name,
+            package_path
+        )
+    })
+}
+
+/// Resolves a type named by its package and interface, or world, followed by
+/// its name, such as `foo:bar/baz/timestamp`.
+fn resolve_type_path(resolve: &Resolve, wit_type: &str) -> anyhow::Result<TypeId> {
+    wit_type
+        .rsplit_once('/')
+        .ok_or_else(|| anyhow!("Invalid type path: missing type name"))
+        .and_then(|(package_path, type_name)| {
+            resolve_type_in_package(resolve, package_path, type_name)
+        })
+        .context(format!("resolving {wit_type:?}"))
+}
 
-    let iface = &resolve.interfaces[iface_id];
+/// Selects the world named by `world_name` for [`Opts::generate_by_name`]
+/// with [`Resolve::select_world`], like `bindgen!` does. A bare or empty
+/// name is looked up in the only package with such a world, since there's no
+/// root package to look it up in.
+fn select_world_by_name(resolve: &Resolve, world_name: &str) -> anyhow::Result<WorldId> {
+    let world = Some(world_name).filter(|name| !name.is_empty());
+
+    // foo:bar/baz, which names its own package
+    if world_name.contains(':') {
+        let (pkg, _) = resolve
+            .packages
+            .iter()
+            .next()
+            .ok_or_else(|| anyhow!("No worlds to generate bindings for"))?;
+        return resolve.select_world(pkg, world);
+    }
 
-    let (_, &type_id) = iface
-        .types
+    let pkgs = resolve
+        .packages
         .iter()
-        .find(|(n, _)| n.as_str() == type_name)
-        .ok_or_else(|| {
-            anyhow!(
-                "No type named `{}` in package `{}`",
-                package_name,
-                package_path
-            )
-        })?;
+        .filter(|(_, pkg)| match world {
+            Some(name) => pkg.worlds.contains_key(name),
+            None => !pkg.worlds.is_empty(),
+        })
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    match (&pkgs[..], world) {
+        ([pkg], _) => resolve.select_world(*pkg, world),
+        ([], None) => bail!("No worlds to generate bindings for"),
+        ([], Some(name)) => bail!("No world named `{name}`"),
+        (_, _) => {
+            let names = pkgs
+                .iter()
+                .flat_map(|id| {
+                    let pkg = &resolve.packages[*id];
+                    pkg.worlds
+                        .keys()
+                        .filter(|name| world.map_or(true, |world| world == name.as_str()))
+                        .map(move |name| format!("{}/{name}", pkg.name))
+                })
+                .collect::<Vec<_>>();
+            match world {
+                None => bail!(
+                    "Multiple worlds found, select one by name: {}",
+                    names.join(", ")
+                ),
+                Some(name) => bail!(
+                    "Multiple worlds named `{name}` found, select one with \
+                     its package: {}",
+                    names.join(", ")
+                ),
+            }
+        }
+    }
+}
 
-    Ok(type_id)
+/// How an imported function's parameter holding `borrow` handles is passed to
+/// the host: by reference, as the handles are only valid during the call.
+enum BorrowedHandles {
+    /// A handle itself, passed as `&T`.
+    Handle,
+    /// An `option` of a handle, passed as `Option<&T>`.
+    Option(Type),
+    /// A `list` of handles, passed as `&[T]`.
+    List(Type),
+    /// A `tuple` of handles and types without them, passed with each handle
+    /// by reference.
+    Tuple(Vec<Type>),
 }
 
 struct InterfaceGenerator<'a> {
@@ -716,46 +2060,55 @@ struct InterfaceGenerator<'a> {
     gen: &'a mut Wasmtime,
     resolve: &'a Resolve,
     current_interface: Option<(InterfaceId, &'a WorldKey, bool)>,
-
-    /// A mapping of wit types to their rust type name equivalent. This is the pre-processed
-    /// version of `gen.opts.trappable_error_types`, where the types have been eagerly resolved.
-    trappable_errors: IndexMap<TypeId, String>,
 }
 
 impl<'a> InterfaceGenerator<'a> {
     fn new(gen: &'a mut Wasmtime, resolve: &'a Resolve) -> InterfaceGenerator<'a> {
-        let trappable_errors = gen
-            .opts
-            .trappable_error_type
-            .iter()
-            .map(|te| {
-                let id = resolve_type_in_package(resolve, &te.wit_package_path, &te.wit_type_name)
-                    .context(format!("resolving {:?}", te))?;
-                Ok((id, te.rust_type_name.clone()))
-            })
-            .collect::<anyhow::Result<IndexMap<_, _>>>()
-            .unwrap();
-
         InterfaceGenerator {
-            src: Source::default(),
+            src: Source::with_indent_width(gen.opts.indent),
             gen,
             resolve,
             current_interface: None,
-            trappable_errors,
         }
     }
 
+    /// Swaps in an empty source, with the configured indentation, to capture
+    /// what's printed next; the returned source is restored afterwards.
+    fn take_src(&mut self) -> Source {
+        let src = Source::with_indent_width(self.gen.opts.indent);
+        mem::replace(&mut self.src, src)
+    }
+
     fn types(&mut self, id: InterfaceId) {
         for (name, id) in self.resolve.interfaces[id].types.iter() {
             self.define_type(name, *id);
+            self.define_trappable_error_types(*id);
+        }
+    }
 
-            if let Some(rust_name) = self.trappable_errors.get(id) {
-                self.define_trappable_error_type(*id, rust_name.clone())
-            }
+    /// Defines the Rust error types which the wit type `id` is mapped to, a
+    /// type mapped to the same name in both directions being defined once.
+    fn define_trappable_error_types(&mut self, id: TypeId) {
+        let mut rust_names = self
+            .gen
+            .trappable_errors
+            .iter()
+            .filter(|((ty, _), _)| *ty == id)
+            .map(|(_, rust_name)| rust_name.clone())
+            .collect::<Vec<_>>();
+        rust_names.dedup();
+        for rust_name in rust_names {
+            self.define_trappable_error_type(id, rust_name);
         }
     }
 
     fn define_type(&mut self, name: &str, id: TypeId) {
+        if let Some(path) = self.gen.remapped_types.get(&id).cloned() {
+            for (name, _) in self.modes_of(id) {
+                uwriteln!(self.src, "pub use {path} as {name};");
+            }
+            return;
+        }
         let ty = &self.resolve.types[id];
         match &ty.kind {
             TypeDefKind::Record(record) => self.type_record(id, name, record, &ty.docs),
@@ -770,8 +2123,8 @@ impl<'a> InterfaceGenerator<'a> {
             TypeDefKind::Type(t) => self.type_alias(id, name, t, &ty.docs),
             TypeDefKind::Future(_) => todo!("generate for future"),
             TypeDefKind::Stream(_) => todo!("generate for stream"),
-            TypeDefKind::Handle(_) => todo!("#6722"),
-            TypeDefKind::Resource => todo!("#6722"),
+            TypeDefKind::Handle(handle) => self.type_handle(id, name, handle, &ty.docs),
+            TypeDefKind::Resource => self.type_resource(id, name, &ty.docs),
             TypeDefKind::Unknown => unreachable!(),
         }
     }
@@ -789,19 +2142,30 @@ impl<'a> InterfaceGenerator<'a> {
             self.push_str("#[derive(wasmtime::component::Lower)]\n");
             self.push_str("#[component(record)]\n");
 
-            if !info.has_list {
+            if self.has_unclonable_handle(id) {
+                // Resource handles can't be duplicated.
+            } else if !info.has_list {
                 self.push_str("#[derive(Copy, Clone)]\n");
             } else {
                 self.push_str("#[derive(Clone)]\n");
             }
+            self.print_serde_derives(lt.is_none());
+            let mut derived = self.print_eq_derives(id).to_vec();
+            if self.gen.opts.derive_default && self.is_defaultable(&Type::Id(id)) {
+                self.push_str("#[derive(Default)]\n");
+                derived.push("Default");
+            }
+            self.print_additional_derives(&derived);
             self.push_str(&format!("pub struct {}", name));
             self.print_generics(lt);
             self.push_str(" {\n");
             for field in record.fields.iter() {
                 self.rustdoc(&field.docs);
                 self.push_str(&format!("#[component(name = \"{}\")]\n", field.name));
+                let rust_name = self.rust_ident(&field.name);
+                self.print_serde_rename(&field.name, &rust_name.replace('_', "-"));
                 self.push_str("pub ");
-                self.push_str(&to_rust_ident(&field.name));
+                self.push_str(&rust_name);
                 self.push_str(": ");
                 self.print_ty(&field.ty, mode);
                 self.push_str(",\n");
@@ -822,13 +2186,17 @@ impl<'a> InterfaceGenerator<'a> {
                 self.push_str(&format!(
                     ".field(\"{}\", &self.{})",
                     field.name,
-                    to_rust_ident(&field.name)
+                    self.rust_ident(&field.name)
                 ));
             }
             self.push_str(".finish()\n");
             self.push_str("}\n");
             self.push_str("}\n");
 
+            if self.gen.list_field_iter_types.contains(&id) {
+                self.print_list_field_iters(&name, lt, record, mode);
+            }
+
             if info.error {
                 self.push_str("impl");
                 self.print_generics(lt);
@@ -839,97 +2207,800 @@ impl<'a> InterfaceGenerator<'a> {
                 self.push_str(
                     "fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {\n",
                 );
-                self.push_str("write!(f, \"{:?}\", self)\n");
+                // Errors carrying a human-readable `message` display just that,
+                // everything else falls back to the debug representation.
+                let has_message = record
+                    .fields
+                    .iter()
+                    .any(|field| field.name == "message" && matches!(field.ty, Type::String));
+                if has_message {
+                    self.push_str("write!(f, \"{}\", self.message)\n");
+                } else {
+                    self.push_str("write!(f, \"{:?}\", self)\n");
+                }
                 self.push_str("}\n");
                 self.push_str("}\n");
-                self.push_str("impl std::error::Error for ");
-                self.push_str(&name);
-                self.push_str("{}\n");
+                self.print_error_impl(&name, lt);
             }
             self.assert_type(id, &name);
+            self.assert_record_field_names(&name, record);
+
+            if self.gen.ffi_record_types.contains(&id) {
+                self.print_ffi_record(id, &name, record);
+            }
+        }
+
+        if self.uses_two_names(&info) {
+            self.print_record_conversions(id, record);
+        }
+    }
+
+    /// Emits conversions between the borrowed and owned types generated for a
+    /// record which uses two names, in each direction all of its fields
+    /// support.
+    fn print_record_conversions(&mut self, id: TypeId, record: &Record) {
+        let mut borrowed = Some(String::new());
+        let mut owned = Some(String::new());
+        for field in record.fields.iter() {
+            let rust_name = self.rust_ident(&field.name);
+            let expr = format!("value.{rust_name}");
+            match (self.borrowed_conversion(&field.ty, &expr), &mut borrowed) {
+                (Some(b), Some(borrowed)) => uwriteln!(borrowed, "{rust_name}: {b},"),
+                _ => borrowed = None,
+            }
+            match (self.owned_conversion(&field.ty, &expr), &mut owned) {
+                (Some(o), Some(owned)) => uwriteln!(owned, "{rust_name}: {o},"),
+                _ => owned = None,
+            }
+        }
+        let result = self.result_name(id);
+        let param = self.param_name(id);
+        if let Some(borrowed) = borrowed {
+            uwriteln!(
+                self.src,
+                "
+                    impl<'a> From<&'a {result}> for {param}<'a> {{
+                        fn from(value: &'a {result}) -> Self {{
+                            {param} {{ {borrowed} }}
+                        }}
+                    }}
+                "
+            );
+        }
+        if let Some(owned) = owned {
+            uwriteln!(
+                self.src,
+                "
+                    impl From<{param}<'_>> for {result} {{
+                        fn from(value: {param}<'_>) -> Self {{
+                            {result} {{ {owned} }}
+                        }}
+                    }}
+                "
+            );
+            self.print_into_owned(&param, &result);
+        }
+    }
+
+    /// Emits `into_owned` on the borrowed type `param`, converting it through
+    /// the `From` impl into the owned type `result`.
+    fn print_into_owned(&mut self, param: &str, result: &str) {
+        uwriteln!(
+            self.src,
+            "
+                impl {param}<'_> {{
+                    /// Converts into the owned [`{result}`], cloning the data
+                    /// borrowed, such as to store it.
+                    pub fn into_owned(self) -> {result} {{
+                        From::from(self)
+                    }}
+                }}
+            "
+        );
+    }
+
+    /// Emits conversions between the borrowed and owned types generated for a
+    /// variant which uses two names, in each direction all of its payloads
+    /// support.
+    fn print_rust_enum_conversions<'b>(
+        &mut self,
+        id: TypeId,
+        cases: impl IntoIterator<Item = (String, Option<&'b Type>)>,
+    ) {
+        let result = self.result_name(id);
+        let param = self.param_name(id);
+        let mut borrowed = Some(String::new());
+        let mut owned = Some(String::new());
+        for (case_name, payload) in cases {
+            match payload {
+                Some(ty) => {
+                    match (self.borrowed_conversion(ty, "(*e)"), &mut borrowed) {
+                        (Some(b), Some(borrowed)) => uwriteln!(
+                            borrowed,
+                            "{result}::{case_name}(e) => {param}::{case_name}({b}),"
+                        ),
+                        _ => borrowed = None,
+                    }
+                    match (self.owned_conversion(ty, "e"), &mut owned) {
+                        (Some(o), Some(owned)) => uwriteln!(
+                            owned,
+                            "{param}::{case_name}(e) => {result}::{case_name}({o}),"
+                        ),
+                        _ => owned = None,
+                    }
+                }
+                None => {
+                    if let Some(borrowed) = &mut borrowed {
+                        uwriteln!(borrowed, "{result}::{case_name} => {param}::{case_name},");
+                    }
+                    if let Some(owned) = &mut owned {
+                        uwriteln!(owned, "{param}::{case_name} => {result}::{case_name},");
+                    }
+                }
+            }
+        }
+        if let Some(borrowed) = borrowed {
+            uwriteln!(
+                self.src,
+                "
+                    impl<'a> From<&'a {result}> for {param}<'a> {{
+                        fn from(value: &'a {result}) -> Self {{
+                            match value {{ {borrowed} }}
+                        }}
+                    }}
+                "
+            );
+        }
+        if let Some(owned) = owned {
+            uwriteln!(
+                self.src,
+                "
+                    impl From<{param}<'_>> for {result} {{
+                        fn from(value: {param}<'_>) -> Self {{
+                            match value {{ {owned} }}
+                        }}
+                    }}
+                "
+            );
+            self.print_into_owned(&param, &result);
+        }
+    }
+
+    /// Expression borrowing `expr`, a place of type `ty` within an owned type,
+    /// as `ty` within the corresponding borrowed type. Returns `None` if that
+    /// isn't possible, such as for lists of strings.
+    fn borrowed_conversion(&self, ty: &Type, expr: &str) -> Option<String> {
+        let id = match ty {
+            Type::String => return Some(format!("{expr}.as_str()")),
+            Type::Id(id) => *id,
+            _ => return Some(expr.to_string()),
+        };
+        if self.has_unclonable_handle(id) {
+            return None;
+        }
+        let info = self.info(id);
+        if !info.has_list {
+            return Some(format!("Clone::clone(&{expr})"));
+        }
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Type(t) => self.borrowed_conversion(t, expr),
+            TypeDefKind::List(Type::U8) if self.bytes_type().is_some() => {
+                Some(format!("&{expr}[..]"))
+            }
+            TypeDefKind::List(t) if !self.ty_has_list(t) => {
+                Some(match self.gen.opts.list_representation {
+                    ListRepresentation::Vec => format!("{expr}.as_slice()"),
+                    ListRepresentation::BoxedSlice => format!("&{expr}[..]"),
+                })
+            }
+            TypeDefKind::Option(t) => {
+                let inner = self.borrowed_conversion(t, "(*x)")?;
+                Some(format!("{expr}.as_ref().map(|x| {inner})"))
+            }
+            TypeDefKind::Record(_) | TypeDefKind::Variant(_) | TypeDefKind::Union(_)
+                if self.uses_two_names(&info) =>
+            {
+                Some(format!("From::from(&{expr})"))
+            }
+            _ => None,
+        }
+    }
+
+    /// Expression converting `expr`, a value of type `ty` within a borrowed
+    /// type, into `ty` within the corresponding owned type. Returns `None` if
+    /// that isn't supported.
+    fn owned_conversion(&self, ty: &Type, expr: &str) -> Option<String> {
+        let id = match ty {
+            Type::String if self.gen.opts.no_std => {
+                return Some(format!("alloc::string::String::from({expr})"))
+            }
+            Type::String => return Some(format!("{expr}.to_string()")),
+            Type::Id(id) => *id,
+            _ => return Some(expr.to_string()),
+        };
+        if self.has_unclonable_handle(id) {
+            return None;
+        }
+        let info = self.info(id);
+        if !info.has_list {
+            return Some(expr.to_string());
+        }
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Type(t) => self.owned_conversion(t, expr),
+            TypeDefKind::List(Type::U8) if self.bytes_type().is_some() => Some(format!(
+                "{}::copy_from_slice({expr})",
+                self.bytes_type().unwrap()
+            )),
+            TypeDefKind::List(t) if !self.ty_has_list(t) => {
+                Some(match self.gen.opts.list_representation {
+                    ListRepresentation::Vec => format!("{expr}.to_vec()"),
+                    ListRepresentation::BoxedSlice => format!("From::from({expr})"),
+                })
+            }
+            TypeDefKind::List(t) => {
+                let inner = self.owned_conversion(t, "Clone::clone(x)")?;
+                Some(format!("{expr}.iter().map(|x| {inner}).collect()"))
+            }
+            TypeDefKind::Option(t) => {
+                let inner = self.owned_conversion(t, "x")?;
+                Some(format!("{expr}.map(|x| {inner})"))
+            }
+            TypeDefKind::Record(_) | TypeDefKind::Variant(_) | TypeDefKind::Union(_)
+                if self.uses_two_names(&info) =>
+            {
+                Some(format!("From::from({expr})"))
+            }
+            _ => None,
+        }
+    }
+
+    fn ty_has_list(&self, ty: &Type) -> bool {
+        match ty {
+            Type::String => true,
+            Type::Id(id) => self.info(*id).has_list,
+            _ => false,
+        }
+    }
+
+    /// Prints a `{field}_iter` method for each list field of the record
+    /// `name`, which keeps callers independent of the list representation.
+    fn print_list_field_iters(
+        &mut self,
+        name: &str,
+        lt: Option<Lifetime>,
+        record: &Record,
+        mode: TypeMode,
+    ) {
+        let elem_mode = match self.ownership() {
+            Ownership::Owning => TypeMode::Owned,
+            Ownership::Borrowing { .. } => mode,
+        };
+        self.push_str("impl");
+        self.print_generics(lt);
+        uwrite!(self.src, " {name}");
+        self.print_generics(lt);
+        self.push_str(" {\n");
+        for field in record.fields.iter() {
+            let elem = match self.list_element(&field.ty) {
+                Some(elem) => elem,
+                None => continue,
+            };
+            let rust_name = self.rust_ident(&field.name);
+            uwriteln!(
+                self.src,
+                "/// Iterates over the elements of [`{name}::{rust_name}`]."
+            );
+            uwrite!(
+                self.src,
+                "pub fn {}_iter(&self) -> impl Iterator<Item = &",
+                field.name.to_snake_case()
+            );
+            self.print_ty(&elem, elem_mode);
+            uwriteln!(self.src, "> + '_ {{ self.{rust_name}.iter() }}");
+        }
+        self.push_str("}\n");
+    }
+
+    /// Emits the `#[repr(C)]` companion struct `{name}Ffi` of the record
+    /// `name`, with `From` conversions both ways.
+    fn print_ffi_record(&mut self, id: TypeId, name: &str, record: &Record) {
+        let ffi_name = format!("{name}Ffi");
+        // Each field is either copied as is or converted through the
+        // companion of the record it holds.
+        let mut fields = Vec::new();
+        for field in record.fields.iter() {
+            let rust_name = self.rust_ident(&field.name);
+            let (ty, nested) = match field.ty {
+                Type::Bool => ("bool".to_string(), false),
+                Type::U8 => ("u8".to_string(), false),
+                Type::U16 => ("u16".to_string(), false),
+                Type::U32 => ("u32".to_string(), false),
+                Type::U64 => ("u64".to_string(), false),
+                Type::S8 => ("i8".to_string(), false),
+                Type::S16 => ("i16".to_string(), false),
+                Type::S32 => ("i32".to_string(), false),
+                Type::S64 => ("i64".to_string(), false),
+                Type::Float32 => ("f32".to_string(), false),
+                Type::Float64 => ("f64".to_string(), false),
+                Type::Id(field_id)
+                    if self.gen.ffi_record_types.iter().any(|ffi_id| {
+                        resolve_type_definition_id(self.resolve, *ffi_id)
+                            == resolve_type_definition_id(self.resolve, field_id)
+                    }) =>
+                {
+                    // The companion is named after the record itself, not
+                    // after any alias the field goes through.
+                    let def = resolve_type_definition_id(self.resolve, field_id);
+                    let prev = self.take_src();
+                    self.print_ty(&Type::Id(def), TypeMode::Owned);
+                    let path = mem::replace(&mut self.src, prev).to_string();
+                    (format!("{path}Ffi"), true)
+                }
+                // Rejected by `Wasmtime::resolve_opts`.
+                _ => unreachable!(),
+            };
+            fields.push((rust_name, ty, nested));
+        }
+
+        uwriteln!(
+            self.src,
+            "/// The `#[repr(C)]` companion of [`{name}`], for passing it on to native code."
+        );
+        self.push_str("#[repr(C)]\n");
+        self.push_str("#[derive(Copy, Clone, Debug)]\n");
+        uwriteln!(self.src, "pub struct {ffi_name} {{");
+        for (rust_name, ty, _) in fields.iter() {
+            uwriteln!(self.src, "pub {rust_name}: {ty},");
+        }
+        self.push_str("}\n");
+
+        for (from, to) in [(name, ffi_name.as_str()), (ffi_name.as_str(), name)] {
+            uwriteln!(self.src, "impl From<{from}> for {to} {{");
+            uwriteln!(self.src, "fn from(value: {from}) -> Self {{");
+            self.push_str("Self {\n");
+            for (rust_name, _, nested) in fields.iter() {
+                if *nested {
+                    uwriteln!(self.src, "{rust_name}: value.{rust_name}.into(),");
+                } else {
+                    uwriteln!(self.src, "{rust_name}: value.{rust_name},");
+                }
+            }
+            self.push_str("}\n");
+            self.push_str("}\n");
+            self.push_str("}\n");
+        }
+
+        // Native code relies on the companion's layout, so unlike
+        // `assert_type` this is checked regardless of `emit_type_asserts`.
+        self.push_str("const _: () = {\n");
+        uwriteln!(
+            self.src,
+            "assert!({} == core::mem::size_of::<{ffi_name}>());",
+            self.gen.sizes.size(&Type::Id(id)),
+        );
+        uwriteln!(
+            self.src,
+            "assert!({} == core::mem::align_of::<{ffi_name}>());",
+            self.gen.sizes.align(&Type::Id(id)),
+        );
+        self.push_str("};\n");
+    }
+
+    /// The element type of `ty` if it's a list, looking through aliases.
+    fn list_element(&self, ty: &Type) -> Option<Type> {
+        let id = match ty {
+            Type::Id(id) => *id,
+            _ => return None,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::List(elem) => Some(*elem),
+            TypeDefKind::Type(ty) => self.list_element(ty),
+            _ => None,
+        }
+    }
+
+    fn type_tuple(&mut self, id: TypeId, _name: &str, tuple: &Tuple, docs: &Docs) {
+        let info = self.info(id);
+        for (name, mode) in self.modes_of(id) {
+            let lt = self.lifetime_for(&info, mode);
+            self.rustdoc(docs);
+            self.push_str(&format!("pub type {}", name));
+            self.print_generics(lt);
+            self.push_str(" = (");
+            for ty in tuple.types.iter() {
+                self.print_ty(ty, mode);
+                self.push_str(",");
+            }
+            self.push_str(");\n");
+            self.assert_type(id, &name);
+        }
+    }
+
+    fn type_flags(&mut self, id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+        self.rustdoc(docs);
+        let rust_name = to_rust_upper_camel_case(name);
+        self.src.push_str("wasmtime::component::flags!(\n");
+        self.print_additional_derives(&["Default", "PartialEq", "Eq", "Hash"]);
+        self.src.push_str(&format!("{rust_name} {{\n"));
+        for flag in flags.flags.iter() {
+            self.rustdoc(&flag.docs);
+            uwrite!(
+                self.src,
+                "#[component(name=\"{}\")] const {};\n",
+                flag.name,
+                flag.name.to_shouty_snake_case()
+            );
+        }
+        self.src.push_str("}\n");
+        self.src.push_str(");\n\n");
+        self.assert_type(id, &rust_name);
+    }
+
+    fn type_variant(&mut self, id: TypeId, _name: &str, variant: &Variant, docs: &Docs) {
+        self.print_rust_enum(
+            id,
+            variant.cases.iter().map(|c| {
+                (
+                    c.name.to_upper_camel_case(),
+                    Some(c.name.clone()),
+                    &c.docs,
+                    c.ty.as_ref(),
+                )
+            }),
+            docs,
+            "variant",
+        );
+    }
+
+    fn type_union(&mut self, id: TypeId, _name: &str, union: &Union, docs: &Docs) {
+        self.print_rust_enum(
+            id,
+            std::iter::zip(self.union_case_names(union), &union.cases)
+                .map(|(name, case)| (name, None, &case.docs, Some(&case.ty))),
+            docs,
+            "union",
+        );
+    }
+
+    fn type_option(&mut self, id: TypeId, _name: &str, payload: &Type, docs: &Docs) {
+        let info = self.info(id);
+
+        for (name, mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            let lt = self.lifetime_for(&info, mode);
+            self.push_str(&format!("pub type {}", name));
+            self.print_generics(lt);
+            self.push_str("= Option<");
+            self.print_ty(payload, mode);
+            self.push_str(">;\n");
+            self.assert_type(id, &name);
+        }
+    }
+
+    fn type_handle(&mut self, id: TypeId, _name: &str, handle: &Handle, docs: &Docs) {
+        for (name, _mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            self.push_str(&format!("pub type {name} = "));
+            self.print_handle(handle);
+            self.push_str(";\n");
+            self.assert_type(id, &name);
+        }
+    }
+
+    fn type_resource(&mut self, id: TypeId, name: &str, docs: &Docs) {
+        // Resources themselves don't have a Rust representation, only handles
+        // to them do. Host-implemented resources additionally get a trait for
+        // the host to be notified when the guest drops them.
+        let rust_type = match self.resource_impl(id) {
+            Some(rust_type) => rust_type,
+            None => {
+                uwriteln!(
+                    self.src,
+                    "// Handles to the resource `{name}` are represented as `wasmtime::component::ResourceAny`."
+                );
+                return;
+            }
+        };
+        let camel = to_rust_upper_camel_case(name);
+        self.rustdoc(docs);
+        uwriteln!(
+            self.src,
+            "
+                pub trait Host{camel} {{
+                    /// Called when the guest drops its owned handle `rep`;
+                    /// an error traps the guest.
+                    fn drop(&mut self, rep: wasmtime::component::Resource<{rust_type}>) -> wasmtime::Result<()>;
+                }}
+            "
+        );
+    }
+
+    /// Returns whether `ty` is a `borrow` handle, possibly through aliases.
+    fn is_borrow_handle(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Id(id) => match &self.resolve.types[*id].kind {
+                TypeDefKind::Handle(Handle::Borrow(_)) => true,
+                TypeDefKind::Type(ty) => self.is_borrow_handle(ty),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns how the import parameter `ty` is passed to the host if it's a
+    /// `borrow` handle, an `option` or `list` of one, or a `tuple` with some,
+    /// possibly through aliases.
+    fn borrowed_handles(&self, ty: &Type) -> Option<BorrowedHandles> {
+        if self.is_borrow_handle(ty) {
+            return Some(BorrowedHandles::Handle);
+        }
+        let id = match ty {
+            Type::Id(id) => *id,
+            _ => return None,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Type(t) => self.borrowed_handles(t),
+            TypeDefKind::Option(t) if self.is_borrow_handle(t) => Some(BorrowedHandles::Option(*t)),
+            TypeDefKind::List(t) if self.is_borrow_handle(t) => Some(BorrowedHandles::List(*t)),
+            TypeDefKind::Tuple(t)
+                if t.types.iter().any(|t| self.is_borrow_handle(t))
+                    && t.types
+                        .iter()
+                        .all(|t| self.is_borrow_handle(t) || !self.has_borrow_handle(t)) =>
+            {
+                Some(BorrowedHandles::Tuple(t.types.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether `ty` contains a `borrow` handle anywhere.
+    fn has_borrow_handle(&self, ty: &Type) -> bool {
+        let id = match ty {
+            Type::Id(id) => *id,
+            _ => return false,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Record(r) => r.fields.iter().any(|f| self.has_borrow_handle(&f.ty)),
+            TypeDefKind::Variant(v) => v
+                .cases
+                .iter()
+                .filter_map(|c| c.ty.as_ref())
+                .any(|t| self.has_borrow_handle(t)),
+            TypeDefKind::Union(u) => u.cases.iter().any(|c| self.has_borrow_handle(&c.ty)),
+            TypeDefKind::Tuple(t) => t.types.iter().any(|t| self.has_borrow_handle(t)),
+            TypeDefKind::Option(t) | TypeDefKind::List(t) | TypeDefKind::Type(t) => {
+                self.has_borrow_handle(t)
+            }
+            TypeDefKind::Result(r) => {
+                r.ok.as_ref().map_or(false, |t| self.has_borrow_handle(t))
+                    || r.err.as_ref().map_or(false, |t| self.has_borrow_handle(t))
+            }
+            TypeDefKind::Handle(Handle::Borrow(_)) => true,
+            TypeDefKind::Handle(Handle::Own(_))
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Unknown => false,
+        }
+    }
+
+    /// Returns whether the type `id` contains a handle which mustn't be copied
+    /// or cloned: any owned handle, since each copy would be dropped
+    /// separately, or a borrowed typed `Resource` handle, which can't be.
+    fn has_unclonable_handle(&self, id: TypeId) -> bool {
+        let has_unclonable_handle = |ty: &Type| match ty {
+            Type::Id(id) => self.has_unclonable_handle(*id),
+            _ => false,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Record(r) => r.fields.iter().any(|f| has_unclonable_handle(&f.ty)),
+            TypeDefKind::Variant(v) => v
+                .cases
+                .iter()
+                .filter_map(|c| c.ty.as_ref())
+                .any(has_unclonable_handle),
+            TypeDefKind::Union(u) => u.cases.iter().any(|c| has_unclonable_handle(&c.ty)),
+            TypeDefKind::Tuple(t) => t.types.iter().any(has_unclonable_handle),
+            TypeDefKind::Option(t) | TypeDefKind::List(t) | TypeDefKind::Type(t) => {
+                has_unclonable_handle(t)
+            }
+            TypeDefKind::Result(r) => {
+                r.ok.as_ref().map_or(false, has_unclonable_handle)
+                    || r.err.as_ref().map_or(false, has_unclonable_handle)
+            }
+            TypeDefKind::Handle(Handle::Own(_)) => true,
+            TypeDefKind::Handle(Handle::Borrow(id)) => self.resource_impl(*id).is_some(),
+            TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Unknown => false,
+        }
+    }
+
+    /// Whether the Rust type generated for `ty` implements `Default`.
+    fn is_defaultable(&self, ty: &Type) -> bool {
+        let id = match ty {
+            Type::Id(id) => *id,
+            _ => return true,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Record(r) => r.fields.iter().all(|f| self.is_defaultable(&f.ty)),
+            TypeDefKind::Tuple(t) => t.types.iter().all(|t| self.is_defaultable(t)),
+            TypeDefKind::Type(t) => self.is_defaultable(t),
+            TypeDefKind::Option(_) | TypeDefKind::List(_) | TypeDefKind::Flags(_) => true,
+            TypeDefKind::Variant(_)
+            | TypeDefKind::Union(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Result(_)
+            | TypeDefKind::Handle(_)
+            | TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Unknown => false,
+        }
+    }
+
+    /// The equality traits which the Rust type generated for `ty` can derive:
+    /// `PartialEq` alone if it holds a float, or nothing if it holds a typed
+    /// `Resource` handle.
+    fn eq_traits(&self, ty: &Type) -> &'static [&'static str] {
+        let id = match ty {
+            Type::Float32 | Type::Float64 => return &["PartialEq"],
+            Type::Id(id) => *id,
+            _ => return EQ_TRAITS,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Record(r) => self.weakest_eq_traits(r.fields.iter().map(|f| &f.ty)),
+            TypeDefKind::Variant(v) => {
+                self.weakest_eq_traits(v.cases.iter().filter_map(|c| c.ty.as_ref()))
+            }
+            TypeDefKind::Union(u) => self.weakest_eq_traits(u.cases.iter().map(|c| &c.ty)),
+            TypeDefKind::Tuple(t) => self.weakest_eq_traits(t.types.iter()),
+            TypeDefKind::Option(t) | TypeDefKind::List(t) | TypeDefKind::Type(t) => {
+                self.eq_traits(t)
+            }
+            TypeDefKind::Result(r) => self.weakest_eq_traits(r.ok.iter().chain(r.err.iter())),
+            TypeDefKind::Handle(Handle::Own(id) | Handle::Borrow(id)) => {
+                if self.resource_impl(*id).is_some() {
+                    &[]
+                } else {
+                    EQ_TRAITS
+                }
+            }
+            TypeDefKind::Flags(_) | TypeDefKind::Enum(_) => EQ_TRAITS,
+            TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Unknown => &[],
+        }
+    }
+
+    /// The equality traits which a type made up of `tys` can derive.
+    fn weakest_eq_traits<'b>(
+        &self,
+        tys: impl Iterator<Item = &'b Type>,
+    ) -> &'static [&'static str] {
+        tys.map(|ty| self.eq_traits(ty))
+            .min_by_key(|traits| traits.len())
+            .unwrap_or(EQ_TRAITS)
+    }
+
+    /// Emits the equality derives for the type `id` if [`Opts::derive_eq`] is
+    /// enabled, returning the traits derived.
+    fn print_eq_derives(&mut self, id: TypeId) -> &'static [&'static str] {
+        if !self.gen.opts.derive_eq {
+            return &[];
         }
+        let traits = self.eq_traits(&Type::Id(id));
+        if !traits.is_empty() {
+            uwriteln!(self.src, "#[derive({})]", traits.join(", "));
+        }
+        traits
     }
 
-    fn type_tuple(&mut self, id: TypeId, _name: &str, tuple: &Tuple, docs: &Docs) {
-        let info = self.info(id);
-        for (name, mode) in self.modes_of(id) {
-            let lt = self.lifetime_for(&info, mode);
-            self.rustdoc(docs);
-            self.push_str(&format!("pub type {}", name));
-            self.print_generics(lt);
-            self.push_str(" = (");
-            for ty in tuple.types.iter() {
-                self.print_ty(ty, mode);
-                self.push_str(",");
-            }
-            self.push_str(");\n");
-            self.assert_type(id, &name);
+    /// Emits the serde derives, if enabled, along with a container-level
+    /// kebab-case rename which matches the common WIT naming convention.
+    fn print_serde_derives(&mut self, deserialize: bool) {
+        if !self.gen.opts.serde {
+            return;
         }
+        // Borrowed types can't generally be deserialized, so only serialize
+        // those.
+        if deserialize {
+            self.push_str("#[derive(serde::Serialize, serde::Deserialize)]\n");
+        } else {
+            self.push_str("#[derive(serde::Serialize)]\n");
+        }
+        self.push_str("#[serde(rename_all = \"kebab-case\")]\n");
     }
 
-    fn type_flags(&mut self, id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
-        self.rustdoc(docs);
-        let rust_name = to_rust_upper_camel_case(name);
-        self.src.push_str("wasmtime::component::flags!(\n");
-        self.src.push_str(&format!("{rust_name} {{\n"));
-        for flag in flags.flags.iter() {
-            // TODO wasmtime-component-macro doesnt support docs for flags rn
-            uwrite!(
-                self.src,
-                "#[component(name=\"{}\")] const {};\n",
-                flag.name,
-                flag.name.to_shouty_snake_case()
-            );
+    /// Emits the user-configured `additional_derives`, skipping any which are
+    /// already derived or implemented for the type being generated.
+    ///
+    /// `Clone`, `Copy`, and `Debug` are always skipped: `Clone` is always
+    /// derived, `Copy` is derived whenever the type allows it, and `Debug` is
+    /// implemented by hand. Types which otherwise derive or implement more
+    /// traits list them in `derived`.
+    fn print_additional_derives(&mut self, derived: &[&str]) {
+        let serde = self.gen.opts.serde;
+        let derives = self
+            .gen
+            .opts
+            .additional_derives
+            .iter()
+            .filter(|path| {
+                let name = path.rsplit("::").next().unwrap().trim();
+                !matches!(name, "Clone" | "Copy" | "Debug")
+                    && !derived.contains(&name)
+                    && !(serde && matches!(name, "Serialize" | "Deserialize"))
+            })
+            .map(|path| path.as_str())
+            .collect::<Vec<_>>();
+        if !derives.is_empty() {
+            uwriteln!(self.src, "#[derive({})]", derives.join(", "));
         }
-        self.src.push_str("}\n");
-        self.src.push_str(");\n\n");
-        self.assert_type(id, &rust_name);
     }
 
-    fn type_variant(&mut self, id: TypeId, _name: &str, variant: &Variant, docs: &Docs) {
-        self.print_rust_enum(
-            id,
-            variant.cases.iter().map(|c| {
-                (
-                    c.name.to_upper_camel_case(),
-                    Some(c.name.clone()),
-                    &c.docs,
-                    c.ty.as_ref(),
-                )
-            }),
-            docs,
-            "variant",
-        );
+    fn print_non_exhaustive(&mut self) {
+        if self.gen.opts.non_exhaustive {
+            self.push_str("#[non_exhaustive]\n");
+        }
     }
 
-    fn type_union(&mut self, id: TypeId, _name: &str, union: &Union, docs: &Docs) {
-        self.print_rust_enum(
-            id,
-            std::iter::zip(self.union_case_names(union), &union.cases)
-                .map(|(name, case)| (name, None, &case.docs, Some(&case.ty))),
-            docs,
-            "union",
-        );
+    /// The Rust identifier for the WIT name `name`, escaping keywords as
+    /// configured by [`Opts::raw_identifiers`].
+    fn rust_ident(&self, name: &str) -> String {
+        if self.gen.opts.raw_identifiers {
+            to_rust_raw_ident(name)
+        } else {
+            to_rust_ident(name)
+        }
     }
 
-    fn type_option(&mut self, id: TypeId, _name: &str, payload: &Type, docs: &Docs) {
-        let info = self.info(id);
+    /// Emits a per-item serde rename for the exceptions where the kebab-case
+    /// name serde derives, `kebab_name`, doesn't match the WIT name.
+    fn print_serde_rename(&mut self, wit_name: &str, kebab_name: &str) {
+        if self.gen.opts.serde && wit_name != kebab_name {
+            uwrite!(self.src, "#[serde(rename = \"{wit_name}\")] ");
+        }
+    }
 
-        for (name, mode) in self.modes_of(id) {
-            self.rustdoc(docs);
-            let lt = self.lifetime_for(&info, mode);
-            self.push_str(&format!("pub type {}", name));
+    /// Emits the impls which let the error type `name` be converted into an
+    /// `anyhow::Error`, e.g. with `?`: `std::error::Error` normally, or a
+    /// direct `From` impl when generating `no_std` code.
+    fn print_error_impl(&mut self, name: &str, lt: Option<Lifetime>) {
+        if !self.gen.opts.no_std {
+            self.push_str("impl");
             self.print_generics(lt);
-            self.push_str("= Option<");
-            self.print_ty(payload, mode);
-            self.push_str(">;\n");
-            self.assert_type(id, &name);
+            self.push_str(" std::error::Error for ");
+            self.push_str(name);
+            self.print_generics(lt);
+            self.push_str(" {}\n");
+        } else if lt.is_none() {
+            uwriteln!(
+                self.src,
+                "
+                    impl From<{name}> for anyhow::Error {{
+                        fn from(e: {name}) -> anyhow::Error {{
+                            anyhow::Error::msg(e)
+                        }}
+                    }}
+                "
+            );
         }
     }
 
     // Emit a double-check that the wit-parser-understood size of a type agrees
     // with the Wasmtime-understood size of a type.
     fn assert_type(&mut self, id: TypeId, name: &str) {
+        if !self.gen.opts.emit_type_asserts {
+            return;
+        }
         self.push_str("const _: () = {\n");
         uwriteln!(
             self.src,
@@ -944,6 +3015,37 @@ impl<'a> InterfaceGenerator<'a> {
         self.push_str("};\n");
     }
 
+    // Along with `assert_type`, double-check field by field that the names
+    // the component macro sees for the record `name` are its WIT field names,
+    // so that any drift is reported at the field concerned.
+    fn assert_record_field_names(&mut self, name: &str, record: &Record) {
+        if !self.gen.opts.emit_type_asserts {
+            return;
+        }
+        self.push_str("const _: () = {\n");
+        uwriteln!(
+            self.src,
+            "let names = <{name} as wasmtime::component::__internal::ComponentRecord>::FIELD_NAMES;"
+        );
+        uwriteln!(
+            self.src,
+            "assert!(names.len() == {}, \"`{name}` doesn't have {0} component fields\");",
+            record.fields.len(),
+        );
+        for (i, field) in record.fields.iter().enumerate() {
+            let rust_name = self.rust_ident(&field.name);
+            uwriteln!(
+                self.src,
+                "assert!(
+                    wasmtime::component::__internal::str_eq(names[{i}], \"{}\"),
+                    \"the component name of `{name}::{rust_name}` isn't `{0}`\",
+                );",
+                field.name,
+            );
+        }
+        self.push_str("};\n");
+    }
+
     fn print_rust_enum<'b>(
         &mut self,
         id: TypeId,
@@ -966,11 +3068,17 @@ impl<'a> InterfaceGenerator<'a> {
             }
             self.push_str("#[derive(wasmtime::component::Lower)]\n");
             self.push_str(&format!("#[component({})]\n", derive_component));
-            if !info.has_list {
+            if self.has_unclonable_handle(id) {
+                // Resource handles can't be duplicated.
+            } else if !info.has_list {
                 self.push_str("#[derive(Clone, Copy)]\n");
             } else {
                 self.push_str("#[derive(Clone)]\n");
             }
+            self.print_serde_derives(lt.is_none());
+            let derived = self.print_eq_derives(id);
+            self.print_additional_derives(derived);
+            self.print_non_exhaustive();
             self.push_str(&format!("pub enum {name}"));
             self.print_generics(lt);
             self.push_str("{\n");
@@ -978,6 +3086,7 @@ impl<'a> InterfaceGenerator<'a> {
                 self.rustdoc(docs);
                 if let Some(n) = component_name {
                     self.push_str(&format!("#[component(name = \"{}\")] ", n));
+                    self.print_serde_rename(&n, &serde_kebab_variant(&case_name));
                 }
                 self.push_str(&case_name);
                 if let Some(ty) = payload {
@@ -1014,16 +3123,18 @@ impl<'a> InterfaceGenerator<'a> {
                 self.push_str("}\n");
                 self.push_str("\n");
 
-                self.push_str("impl");
-                self.print_generics(lt);
-                self.push_str(" std::error::Error for ");
-                self.push_str(&name);
-                self.print_generics(lt);
-                self.push_str(" {}\n");
+                self.print_error_impl(&name, lt);
             }
 
             self.assert_type(id, &name);
         }
+
+        if self.uses_two_names(&info) {
+            self.print_rust_enum_conversions(
+                id,
+                cases.into_iter().map(|(name, _attr, _docs, ty)| (name, ty)),
+            );
+        }
     }
 
     fn print_rust_enum_debug<'b>(
@@ -1091,12 +3202,17 @@ impl<'a> InterfaceGenerator<'a> {
         self.push_str("#[derive(wasmtime::component::Lift)]\n");
         self.push_str("#[derive(wasmtime::component::Lower)]\n");
         self.push_str("#[component(enum)]\n");
-        self.push_str("#[derive(Clone, Copy, PartialEq, Eq)]\n");
+        self.push_str("#[derive(Clone, Copy, PartialEq, Eq, Hash)]\n");
+        self.print_serde_derives(true);
+        self.print_additional_derives(&["PartialEq", "Eq", "Hash"]);
+        self.print_non_exhaustive();
         self.push_str(&format!("pub enum {} {{\n", name));
         for case in enum_.cases.iter() {
             self.rustdoc(&case.docs);
             self.push_str(&format!("#[component(name = \"{}\")]", case.name));
-            self.push_str(&case.name.to_upper_camel_case());
+            let rust_name = case.name.to_upper_camel_case();
+            self.print_serde_rename(&case.name, &serde_kebab_variant(&rust_name));
+            self.push_str(&rust_name);
             self.push_str(",\n");
         }
         self.push_str("}\n");
@@ -1162,9 +3278,7 @@ impl<'a> InterfaceGenerator<'a> {
             self.push_str("}\n");
             self.push_str("}\n");
             self.push_str("\n");
-            self.push_str("impl std::error::Error for ");
-            self.push_str(&name);
-            self.push_str("{}\n");
+            self.print_error_impl(&name, None);
         } else {
             self.print_rust_enum_debug(
                 id,
@@ -1176,9 +3290,83 @@ impl<'a> InterfaceGenerator<'a> {
                     .map(|c| (c.name.to_upper_camel_case(), None)),
             )
         }
+        self.print_enum_try_from(&name, enum_);
+        if self.gen.opts.enum_discriminant_consts {
+            self.print_enum_discriminant_consts(&name, enum_);
+        }
+        if self.gen.opts.enum_string_conversions {
+            self.print_enum_string_conversions(&name, enum_);
+        }
         self.assert_type(id, &name);
     }
 
+    /// Emits the associated constants of [`Opts::enum_discriminant_consts`]
+    /// for the enum `name`.
+    fn print_enum_discriminant_consts(&mut self, name: &str, enum_: &Enum) {
+        uwriteln!(self.src, "impl {name} {{");
+        for (i, case) in enum_.cases.iter().enumerate() {
+            let case_name = case.name.to_upper_camel_case();
+            uwriteln!(self.src, "/// The discriminant of [`{name}::{case_name}`].");
+            uwriteln!(
+                self.src,
+                "pub const {}_VALUE: u32 = {i};",
+                case.name.to_shouty_snake_case()
+            );
+        }
+        self.push_str("}\n");
+    }
+
+    /// Emits a `TryFrom<u32>` impl for the enum `name` which maps
+    /// discriminants, in WIT case order, to its cases.
+    fn print_enum_try_from(&mut self, name: &str, enum_: &Enum) {
+        uwriteln!(self.src, "impl core::convert::TryFrom<u32> for {name} {{");
+        self.push_str("type Error = anyhow::Error;\n");
+        self.push_str("fn try_from(value: u32) -> anyhow::Result<Self> {\n");
+        self.push_str("match value {\n");
+        for (i, case) in enum_.cases.iter().enumerate() {
+            let case_name = case.name.to_upper_camel_case();
+            uwriteln!(self.src, "{i} => Ok({name}::{case_name}),");
+        }
+        uwriteln!(
+            self.src,
+            "_ => Err(anyhow::anyhow!(\"invalid `{name}` discriminant `{{value}}`\")),"
+        );
+        self.push_str("}\n");
+        self.push_str("}\n");
+        self.push_str("}\n");
+    }
+
+    /// Emits `AsRef<str>` and `FromStr` impls for the enum `name` which
+    /// convert to and from the WIT spellings of its cases.
+    fn print_enum_string_conversions(&mut self, name: &str, enum_: &Enum) {
+        uwriteln!(self.src, "impl AsRef<str> for {name} {{");
+        self.push_str("fn as_ref(&self) -> &str {\n");
+        self.push_str("match self {\n");
+        for case in enum_.cases.iter() {
+            let case_name = case.name.to_upper_camel_case();
+            uwriteln!(self.src, "{name}::{case_name} => \"{}\",", case.name);
+        }
+        self.push_str("}\n");
+        self.push_str("}\n");
+        self.push_str("}\n");
+
+        uwriteln!(self.src, "impl core::str::FromStr for {name} {{");
+        self.push_str("type Err = anyhow::Error;\n");
+        self.push_str("fn from_str(s: &str) -> anyhow::Result<Self> {\n");
+        self.push_str("match s {\n");
+        for case in enum_.cases.iter() {
+            let case_name = case.name.to_upper_camel_case();
+            uwriteln!(self.src, "\"{}\" => Ok({name}::{case_name}),", case.name);
+        }
+        uwriteln!(
+            self.src,
+            "_ => Err(anyhow::anyhow!(\"unknown `{name}` case `{{s}}`\")),"
+        );
+        self.push_str("}\n");
+        self.push_str("}\n");
+        self.push_str("}\n");
+    }
+
     fn type_alias(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
         let info = self.info(id);
         for (name, mode) in self.modes_of(id) {
@@ -1230,48 +3418,69 @@ impl<'a> InterfaceGenerator<'a> {
     fn special_case_trappable_error(
         &self,
         results: &Results,
-    ) -> Option<(&'a Result_, TypeId, String)> {
-        // We fillin a special trappable error type in the case when a function has just one
-        // result, which is itself a `result<a, e>`, and the `e` is *not* a primitive
+        direction: Direction,
+    ) -> Option<(usize, &'a Result_, TypeId, String)> {
+        // We fillin a special trappable error type in the case when exactly one of a
+        // function's results is a `result<a, e>`, and the `e` is *not* a primitive
         // (i.e. defined in std) type, and matches the typename given by the user.
-        let mut i = results.iter_types();
-        let id = match i.next()? {
-            Type::Id(id) => id,
-            _ => return None,
-        };
-        if i.next().is_some() {
-            return None;
+        // The index of that result is returned alongside so that callers can
+        // replace just that position when a function has multiple results.
+        let mut found = None;
+        for (i, ty) in results.iter_types().enumerate() {
+            let id = match ty {
+                Type::Id(id) => id,
+                _ => continue,
+            };
+            let result = match &self.resolve.types[*id].kind {
+                TypeDefKind::Result(r) => r,
+                _ => continue,
+            };
+            let error_typeid = match result.err {
+                Some(Type::Id(id)) => resolve_type_definition_id(&self.resolve, id),
+                _ => continue,
+            };
+            let rust_type = match self.gen.trappable_errors.get(&(error_typeid, direction)) {
+                Some(rust_type) => rust_type,
+                None => continue,
+            };
+            if found.is_some() {
+                return None;
+            }
+            found = Some((i, result, error_typeid, rust_type.clone()));
         }
-        let result = match &self.resolve.types[*id].kind {
-            TypeDefKind::Result(r) => r,
-            _ => return None,
-        };
-        let error_typeid = match result.err? {
-            Type::Id(id) => resolve_type_definition_id(&self.resolve, id),
-            _ => return None,
-        };
-
-        let rust_type = self.trappable_errors.get(&error_typeid)?;
-
-        Some((result, error_typeid, rust_type.clone()))
+        found
     }
 
     fn generate_add_to_linker(&mut self, id: InterfaceId, name: &str) {
         let iface = &self.resolve.interfaces[id];
         let owner = TypeOwner::Interface(id);
 
-        if self.gen.opts.async_ {
-            uwriteln!(self.src, "#[wasmtime::component::__internal::async_trait]")
+        if self.imports_async() {
+            uwriteln!(self.src, "#[{}::async_trait]", self.gen.async_trait_crate())
         }
+        // Host-implemented resources defined in this interface, each of
+        // which has a `Host{Resource}` trait generated alongside its type.
+        let resources = iface
+            .types
+            .iter()
+            .filter(|(_, id)| matches!(self.resolve.types[**id].kind, TypeDefKind::Resource))
+            .filter_map(|(name, id)| Some((name.as_str(), self.resource_impl(*id)?)))
+            .collect::<Vec<_>>();
+
         // Generate the `pub trait` which represents the host functionality for
         // this import.
-        uwriteln!(self.src, "pub trait Host {{");
+        uwrite!(self.src, "pub trait Host");
+        for (i, (name, _)) in resources.iter().enumerate() {
+            let sep = if i == 0 { ":" } else { " +" };
+            uwrite!(self.src, "{sep} Host{}", to_rust_upper_camel_case(name));
+        }
+        uwriteln!(self.src, " {{");
         for (_, func) in iface.functions.iter() {
             self.generate_function_trait_sig(func);
         }
         uwriteln!(self.src, "}}");
 
-        let where_clause = if self.gen.opts.async_ {
+        let where_clause = if self.imports_async() {
             "T: Send, U: Host + Send".to_string()
         } else {
             "U: Host".to_string()
@@ -1285,36 +3494,192 @@ impl<'a> InterfaceGenerator<'a> {
                 ) -> wasmtime::Result<()>
                     where {where_clause},
                 {{
+                    add_to_linker_get_result(linker, move |t| Ok(get(t)))
+                }}
+
+                /// Same as [`add_to_linker`], except that `get` may fail, in
+                /// which case the error traps the calling guest.
+                pub fn add_to_linker_get_result<T, U>(
+                    linker: &mut wasmtime::component::Linker<T>,
+                    get: impl Fn(&mut T) -> wasmtime::Result<&mut U> + Send + Sync + Copy + 'static,
+                ) -> wasmtime::Result<()>
+                    where {where_clause},
+                {{
             "
         );
-        uwriteln!(self.src, "let mut inst = linker.instance(\"{name}\")?;");
+        let body_start = self.src.len();
+        if resources.is_empty() && iface.functions.is_empty() {
+            // The instance is still defined for components importing it, but
+            // neither it nor `get` is used otherwise, so don't bind them to
+            // avoid `unused_mut` and `unused_variables` for stricter users.
+            uwriteln!(self.src, "let _ = get;");
+            uwriteln!(self.src, "linker.instance(\"{name}\")?;");
+        } else {
+            uwriteln!(self.src, "let mut inst = linker.instance(\"{name}\")?;");
+        }
+        for (name, rust_type) in resources.iter() {
+            let camel = to_rust_upper_camel_case(name);
+            uwriteln!(
+                self.src,
+                "
+                    inst.resource::<{rust_type}>(
+                        \"{name}\",
+                        move |mut store, rep| {{
+                            Host{camel}::drop(
+                                get(store.data_mut())?,
+                                wasmtime::component::Resource::new_own(rep),
+                            )
+                        }},
+                    )?;
+                "
+            );
+        }
         for (_, func) in iface.functions.iter() {
             self.generate_add_function_to_linker(owner, func, "inst");
         }
         uwriteln!(self.src, "Ok(())");
         uwriteln!(self.src, "}}");
+
+        // The sync `Host` trait, along with its `Host{Resource}` supertraits,
+        // is object-safe, so it can also be added to a linker as a trait
+        // object. The body is the same, only `get` returns `dyn Host`.
+        if !self.imports_async() {
+            let body = self.src[body_start..].to_string();
+            uwriteln!(
+                self.src,
+                "
+                    /// Same as [`add_to_linker`], except that the host state
+                    /// is reached as a `dyn Host` trait object, for hosts which
+                    /// pick their implementation at runtime.
+                    pub fn add_to_linker_dyn<T>(
+                        linker: &mut wasmtime::component::Linker<T>,
+                        get: impl Fn(&mut T) -> &mut (dyn Host + '_) + Send + Sync + Copy + 'static,
+                    ) -> wasmtime::Result<()> {{
+                        add_to_linker_dyn_get_result(linker, move |t| Ok(get(t)))
+                    }}
+
+                    /// Same as [`add_to_linker_dyn`], except that `get` may
+                    /// fail, in which case the error traps the calling guest.
+                    pub fn add_to_linker_dyn_get_result<T>(
+                        linker: &mut wasmtime::component::Linker<T>,
+                        get: impl Fn(&mut T) -> wasmtime::Result<&mut (dyn Host + '_)> + Send + Sync + Copy + 'static,
+                    ) -> wasmtime::Result<()> {{
+                "
+            );
+            self.src.push_str(&body);
+        }
+
+        if self.gen.opts.import_vtable {
+            self.generate_import_vtable(id);
+        }
     }
 
     fn generate_add_function_to_linker(&mut self, owner: TypeOwner, func: &Function, linker: &str) {
         uwrite!(
             self.src,
             "{linker}.{}(\"{}\", ",
-            if self.gen.opts.async_ {
+            if self.imports_async() {
                 "func_wrap_async"
             } else {
                 "func_wrap"
             },
             func.name
         );
-        self.generate_guest_import_closure(owner, func);
+        self.generate_guest_import_closure(owner, func, true);
         uwriteln!(self.src, ")?;")
     }
 
-    fn generate_guest_import_closure(&mut self, owner: TypeOwner, func: &Function) {
-        // Generate the closure that's passed to a `Linker`, the final piece of
-        // codegen here.
-        self.src
-            .push_str("move |mut caller: wasmtime::StoreContextMut<'_, T>, (");
+    /// Generates the `HostVtable` of [`Opts::import_vtable`] for the functions
+    /// of the interface `id`.
+    fn generate_import_vtable(&mut self, id: InterfaceId) {
+        let iface = &self.resolve.interfaces[id];
+        let owner = TypeOwner::Interface(id);
+
+        uwriteln!(
+            self.src,
+            "
+                /// The host functions of this interface as boxed closures, for
+                /// embeddings which call them without a `Linker`.
+                ///
+                /// Each closure takes the host state and the function's
+                /// parameters, and returns its results as a tuple.
+                pub struct HostVtable<T> {{
+            "
+        );
+        for (_, func) in iface.functions.iter() {
+            uwrite!(
+                self.src,
+                "pub {}: Box<dyn Fn(&mut T, (",
+                self.rust_ident(&func.name)
+            );
+            for (_, ty) in func.params.iter() {
+                self.print_ty(ty, TypeMode::Owned);
+                self.push_str(", ");
+            }
+            self.push_str(")) -> ");
+            self.print_import_results(func);
+            self.push_str(" + Send + Sync>,\n");
+        }
+        uwriteln!(
+            self.src,
+            "
+                }}
+
+                impl<T> HostVtable<T> {{
+                    /// Creates the closures, which use `get` to reach the
+                    /// [`Host`] implementation within the host state.
+                    pub fn new<U: Host>(
+                        get: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
+                    ) -> Self {{
+                        Self::new_get_result(move |t| Ok(get(t)))
+                    }}
+
+                    /// Same as [`HostVtable::new`], except that `get` may
+                    /// fail, in which case the closures return its error.
+                    pub fn new_get_result<U: Host>(
+                        get: impl Fn(&mut T) -> wasmtime::Result<&mut U> + Send + Sync + Copy + 'static,
+                    ) -> Self {{
+                        Self {{
+            "
+        );
+        for (_, func) in iface.functions.iter() {
+            uwrite!(self.src, "{}: Box::new(", self.rust_ident(&func.name));
+            self.generate_guest_import_closure(owner, func, false);
+            self.push_str("),\n");
+        }
+        uwriteln!(self.src, "}}");
+        uwriteln!(self.src, "}}");
+        uwriteln!(self.src, "}}");
+    }
+
+    /// Prints the results of the import `func` as returned by its closures, a
+    /// `wasmtime::Result` of a tuple.
+    fn print_import_results(&mut self, func: &Function) {
+        self.push_str("wasmtime::Result<(");
+        for ty in func.results.iter_types() {
+            self.print_ty(ty, TypeMode::Owned);
+            self.push_str(", ");
+        }
+        self.push_str(")>");
+    }
+
+    /// Generates the closure implementing the import `func` with the host
+    /// state's [`Host`] implementation, taking a `StoreContextMut` if it's
+    /// `for_linker`, or the host state itself otherwise.
+    fn generate_guest_import_closure(
+        &mut self,
+        owner: TypeOwner,
+        func: &Function,
+        for_linker: bool,
+    ) {
+        // Generate the closure that's passed to a `Linker`, or kept in a
+        // `HostVtable`, the final piece of codegen here.
+        if for_linker {
+            self.src
+                .push_str("move |mut caller: wasmtime::StoreContextMut<'_, T>, (");
+        } else {
+            self.src.push_str("move |data: &mut T, (");
+        }
         for (i, _param) in func.params.iter().enumerate() {
             uwrite!(self.src, "arg{},", i);
         }
@@ -1326,23 +3691,31 @@ impl<'a> InterfaceGenerator<'a> {
             self.src.push_str(", ");
         }
         self.src.push_str(") |");
-        if self.gen.opts.async_ {
+        if !for_linker {
+            // Nothing else determines the error type of the `?`s within.
+            self.src.push_str(" -> ");
+            self.print_import_results(func);
+        }
+        // With `Opts::tracing_instrument` the span is created outside of the
+        // future, which it's then attached to.
+        let instrument = self.instrument_spans(self.imports_async());
+        if self.imports_async() && !instrument {
             self.src.push_str(" Box::new(async move { \n");
         } else {
             self.src.push_str(" { \n");
         }
 
         if self.gen.opts.tracing {
+            let level = self.gen.tracing_level;
             uwrite!(
                 self.src,
                 "
                    let span = tracing::span!(
-                       tracing::Level::TRACE,
+                       tracing::Level::{level},
                        \"wit-bindgen import\",
                        module = \"{}\",
                        function = \"{}\",
                    );
-                   let _enter = span.enter();
                ",
                 match owner {
                     TypeOwner::Interface(id) => self.resolve.interfaces[id]
@@ -1354,60 +3727,107 @@ impl<'a> InterfaceGenerator<'a> {
                 },
                 func.name,
             );
-            let mut event_fields = func
-                .params
-                .iter()
-                .enumerate()
-                .map(|(i, (name, _ty))| {
-                    let name = to_rust_ident(&name);
-                    format!("{name} = tracing::field::debug(&arg{i})")
-                })
-                .collect::<Vec<String>>();
-            event_fields.push(format!("\"call\""));
-            uwrite!(
-                self.src,
-                "tracing::event!(tracing::Level::TRACE, {});\n",
-                event_fields.join(", ")
-            );
+            if instrument {
+                self.src.push_str("Box::new(");
+            }
+            self.enter_span(instrument);
+            self.tracing_call_event(func);
         }
 
-        self.src.push_str("let host = get(caller.data_mut());\n");
+        if for_linker {
+            self.src.push_str("let host = get(caller.data_mut())?;\n");
+        } else {
+            self.src.push_str("let host = get(data)?;\n");
+        }
 
-        uwrite!(self.src, "let r = host.{}(", func.name.to_snake_case());
-        for (i, _) in func.params.iter().enumerate() {
-            uwrite!(self.src, "arg{},", i);
+        uwrite!(self.src, "let r = host.{}(", self.rust_ident(&func.name));
+        for (i, (_, ty)) in func.params.iter().enumerate() {
+            let arg = match self.borrowed_handles(ty) {
+                Some(BorrowedHandles::Handle) => format!("&arg{i}"),
+                Some(BorrowedHandles::Option(_)) => format!("arg{i}.as_ref()"),
+                Some(BorrowedHandles::List(_)) => format!("&arg{i}[..]"),
+                Some(BorrowedHandles::Tuple(types)) => {
+                    let mut arg = String::from("(");
+                    for (j, ty) in types.iter().enumerate() {
+                        if self.is_borrow_handle(ty) {
+                            arg.push('&');
+                        }
+                        uwrite!(arg, "arg{i}.{j}, ");
+                    }
+                    arg.push(')');
+                    arg
+                }
+                None if self.is_borrowed_import_param(ty) => {
+                    self.borrowed_conversion(ty, &format!("arg{i}")).unwrap()
+                }
+                None => format!("arg{i}"),
+            };
+            uwrite!(self.src, "{arg},");
         }
-        if self.gen.opts.async_ {
+        if self.imports_async() {
             uwrite!(self.src, ").await;\n");
         } else {
             uwrite!(self.src, ");\n");
         }
 
         if self.gen.opts.tracing {
+            let level = self.gen.tracing_level;
             uwrite!(
                 self.src,
-                "tracing::event!(tracing::Level::TRACE, result = tracing::field::debug(&r), \"return\");"
+                "tracing::event!(tracing::Level::{level}, result = tracing::field::debug(&r), \"return\");"
             );
         }
 
-        if self.special_case_trappable_error(&func.results).is_some() {
-            uwrite!(
-                self.src,
-                "match r {{
-                    Ok(a) => Ok((Ok(a),)),
-                    Err(e) => match e.downcast() {{
-                        Ok(api_error) => Ok((Err(api_error),)),
-                        Err(anyhow_error) => Err(anyhow_error),
-                    }}
-                }}"
-            );
-        } else if func.results.iter_types().len() == 1 {
+        let nresults = func.results.iter_types().len();
+        if let Some((pos, ..)) = self.special_case_trappable_error(&func.results, Direction::Import)
+        {
+            if nresults == 1 {
+                uwrite!(
+                    self.src,
+                    "match r {{
+                        Ok(a) => Ok((Ok(a),)),
+                        Err(e) => match e.downcast() {{
+                            Ok(api_error) => Ok((Err(api_error),)),
+                            Err(anyhow_error) => Err(anyhow_error),
+                        }}
+                    }}"
+                );
+            } else {
+                // Only the trappable position gets unwrapped, every other
+                // result is passed through as-is.
+                self.src.push_str("let (");
+                for i in 0..nresults {
+                    uwrite!(self.src, "r{i},");
+                }
+                self.src.push_str(") = r;\n");
+                uwrite!(
+                    self.src,
+                    "let r{pos} = match r{pos} {{
+                        Ok(a) => Ok(a),
+                        Err(e) => match e.downcast() {{
+                            Ok(api_error) => Err(api_error),
+                            Err(anyhow_error) => return Err(anyhow_error),
+                        }}
+                    }};
+                    "
+                );
+                self.src.push_str("Ok((");
+                for i in 0..nresults {
+                    uwrite!(self.src, "r{i},");
+                }
+                self.src.push_str("))\n");
+            }
+        } else if nresults == 1 {
             uwrite!(self.src, "Ok((r?,))\n");
         } else {
             uwrite!(self.src, "r\n");
         }
 
-        if self.gen.opts.async_ {
+        if instrument {
+            // Need to close the async block, `instrument` and Box::new, then
+            // the closure's block
+            self.src.push_str("}, span))\n}");
+        } else if self.imports_async() {
             // Need to close Box::new and async block
             self.src.push_str("})");
         } else {
@@ -1415,46 +3835,134 @@ impl<'a> InterfaceGenerator<'a> {
         }
     }
 
+    /// The key identifying `func` in options such as
+    /// [`Opts::tracing_redact`], `interface#func` for the functions of an
+    /// interface and the bare WIT name of world-level functions.
+    fn func_key(&self, func: &Function) -> String {
+        match self.current_interface {
+            Some((_, key, _)) => format!("{}#{}", self.resolve.name_world_key(key), func.name),
+            None => func.name.clone(),
+        }
+    }
+
+    /// Whether the host functions being generated are async, which are all of
+    /// them with [`Opts::import_async`] or those of an interface listed in
+    /// [`Opts::async_interfaces`].
+    fn imports_async(&self) -> bool {
+        self.gen.opts.imports_async()
+            || match self.current_interface {
+                Some((_, key, false)) => self
+                    .gen
+                    .opts
+                    .async_interfaces
+                    .contains(&self.resolve.name_world_key(key)),
+                _ => false,
+            }
+    }
+
+    /// Returns whether the spans of [`Opts::tracing`] are attached to the
+    /// future of a function, which is `async_`, per
+    /// [`Opts::tracing_instrument`].
+    fn instrument_spans(&self, async_: bool) -> bool {
+        self.gen.opts.tracing && self.gen.opts.tracing_instrument && async_
+    }
+
+    /// Continues the body of a function after the creation of its `span`,
+    /// either entering it or, if `instrument`, opening the async block which
+    /// it's attached to, to be closed with `}, span)`.
+    fn enter_span(&mut self, instrument: bool) {
+        if instrument {
+            self.src
+                .push_str("tracing::Instrument::instrument(async move {\n");
+        } else {
+            self.src.push_str("let _enter = span.enter();\n");
+        }
+    }
+
     fn generate_function_trait_sig(&mut self, func: &Function) {
         self.rustdoc(&func.docs);
 
-        if self.gen.opts.async_ {
+        if self.imports_async() {
             self.push_str("async ");
         }
         self.push_str("fn ");
-        self.push_str(&to_rust_ident(&func.name));
-        self.push_str("(&mut self, ");
+        self.push_str(&self.rust_ident(&func.name));
+        if self
+            .gen
+            .opts
+            .read_only_imports
+            .contains(&self.func_key(func))
+        {
+            self.push_str("(&self, ");
+        } else {
+            self.push_str("(&mut self, ");
+        }
         for (name, param) in func.params.iter() {
-            let name = to_rust_ident(name);
-            self.push_str(&name);
+            let rust_name = self.rust_ident(name);
+            self.push_str(&rust_name);
             self.push_str(": ");
-            self.print_ty(param, TypeMode::Owned);
+            // Borrowed handles are only valid for the duration of the call, so
+            // they're passed by reference to make that clear to the host.
+            match self.borrowed_handles(param) {
+                Some(BorrowedHandles::Handle) => {
+                    self.push_str("&");
+                    self.print_ty(param, TypeMode::Owned);
+                }
+                Some(BorrowedHandles::Option(ty)) => {
+                    self.push_str("Option<&");
+                    self.print_ty(&ty, TypeMode::Owned);
+                    self.push_str(">");
+                }
+                Some(BorrowedHandles::List(ty)) => {
+                    self.push_str("&[");
+                    self.print_ty(&ty, TypeMode::Owned);
+                    self.push_str("]");
+                }
+                Some(BorrowedHandles::Tuple(types)) => {
+                    self.push_str("(");
+                    for ty in types.iter() {
+                        if self.is_borrow_handle(ty) {
+                            self.push_str("&");
+                        }
+                        self.print_ty(ty, TypeMode::Owned);
+                        self.push_str(", ");
+                    }
+                    self.push_str(")");
+                }
+                // Other types nesting `borrow` handles, like records, are
+                // passed owned as their handles can't be borrowed in place.
+                None if self.has_borrow_handle(param) => self.print_ty(param, TypeMode::Owned),
+                None if self.is_borrowed_import_param(param) => {
+                    self.print_ty(param, TypeMode::AllBorrowed(Lifetime::Elided));
+                }
+                None => self.print_ty(param, TypeMode::Owned),
+            }
             self.push_str(",");
         }
         self.push_str(")");
         self.push_str(" -> ");
 
-        if let Some((r, error_id, error_typename)) =
-            self.special_case_trappable_error(&func.results)
+        if let Some((pos, r, error_id, error_typename)) =
+            self.special_case_trappable_error(&func.results, Direction::Import)
         {
             // Functions which have a single result `result<ok,err>` get special
             // cased to use the host_wasmtime_rust::Error<err>, making it possible
-            // for them to trap or use `?` to propogate their errors
-            self.push_str("Result<");
-            if let Some(ok) = r.ok {
-                self.print_ty(&ok, TypeMode::Owned);
+            // for them to trap or use `?` to propogate their errors. Functions
+            // with multiple results only have the trappable position replaced.
+            if func.results.iter_types().len() == 1 {
+                self.print_trappable_result(r, error_id, &error_typename);
             } else {
-                self.push_str("()");
-            }
-            self.push_str(",");
-            if let TypeOwner::Interface(id) = self.resolve.types[error_id].owner {
-                if let Some(path) = self.path_to_interface(id) {
-                    self.push_str(&path);
-                    self.push_str("::");
+                self.push_str("(");
+                for (i, ty) in func.results.iter_types().enumerate() {
+                    if i == pos {
+                        self.print_trappable_result(r, error_id, &error_typename);
+                    } else {
+                        self.print_ty(ty, TypeMode::Owned);
+                    }
+                    self.push_str(", ");
                 }
+                self.push_str(")");
             }
-            self.push_str(&error_typename);
-            self.push_str(">");
         } else {
             // All other functions get their return values wrapped in an wasmtime::Result.
             // Returning the anyhow::Error case can be used to trap.
@@ -1466,12 +3974,66 @@ impl<'a> InterfaceGenerator<'a> {
         self.push_str(";\n");
     }
 
+    /// Whether the imported function parameter `ty` is passed to the host
+    /// borrowed with [`Opts::borrowed_import_params`]: strings and anonymous
+    /// lists of types without lists or handles, which the host receives as
+    /// `&str` and slices borrowed from the lifted arguments.
+    fn is_borrowed_import_param(&self, ty: &Type) -> bool {
+        if !self.gen.opts.borrowed_import_params
+            || matches!(self.gen.opts.ownership, Ownership::Owning)
+        {
+            return false;
+        }
+        let id = match ty {
+            Type::String => return true,
+            Type::Id(id) => *id,
+            _ => return false,
+        };
+        let ty = &self.resolve.types[id];
+        match &ty.kind {
+            TypeDefKind::List(elem) if ty.name.is_none() => match elem {
+                Type::String => false,
+                Type::Id(elem_id) => {
+                    !self.info(*elem_id).has_list
+                        && !self.has_unclonable_handle(*elem_id)
+                        && !self.has_borrow_handle(elem)
+                }
+                _ => true,
+            },
+            _ => false,
+        }
+    }
+
+    fn print_trappable_result(&mut self, r: &Result_, error_id: TypeId, error_typename: &str) {
+        self.push_str("Result<");
+        if let Some(ok) = r.ok {
+            self.print_ty(&ok, TypeMode::Owned);
+        } else {
+            self.push_str("()");
+        }
+        self.push_str(",");
+        self.print_trappable_error_name(error_id, error_typename);
+        self.push_str(">");
+    }
+
+    /// Prints the path to the Rust error type `error_typename`, defined
+    /// alongside the wit type `error_id`.
+    fn print_trappable_error_name(&mut self, error_id: TypeId, error_typename: &str) {
+        if let TypeOwner::Interface(id) = self.resolve.types[error_id].owner {
+            if let Some(path) = self.path_to_interface(id) {
+                self.push_str(&path);
+                self.push_str("::");
+            }
+        }
+        self.push_str(error_typename);
+    }
+
     fn extract_typed_function(&mut self, func: &Function) -> (String, String) {
-        let prev = mem::take(&mut self.src);
+        let prev = self.take_src();
         let snake = func.name.to_snake_case();
         uwrite!(self.src, "*__exports.typed_func::<(");
         for (_, ty) in func.params.iter() {
-            self.print_ty(ty, TypeMode::AllBorrowed("'_"));
+            self.print_ty(ty, TypeMode::AllBorrowed(Lifetime::Elided));
             self.push_str(", ");
         }
         self.src.push_str("), (");
@@ -1483,9 +4045,38 @@ impl<'a> InterfaceGenerator<'a> {
         self.src.push_str(&func.name);
         self.src.push_str("\")?.func()");
 
-        let ret = (snake, mem::take(&mut self.src).to_string());
-        self.src = prev;
-        ret
+        (snake, mem::replace(&mut self.src, prev).to_string())
+    }
+
+    /// Emits the `tracing` event logging the arguments, named `arg{i}`, that
+    /// `func` is called with.
+    fn tracing_call_event(&mut self, func: &Function) {
+        let key = self.func_key(func);
+        let mut event_fields = func
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _ty))| {
+                let redacted = self
+                    .gen
+                    .opts
+                    .tracing_redact
+                    .contains(&(key.clone(), name.clone()));
+                let name = self.rust_ident(&name);
+                if redacted {
+                    format!("{name} = \"<redacted>\"")
+                } else {
+                    format!("{name} = tracing::field::debug(&arg{i})")
+                }
+            })
+            .collect::<Vec<String>>();
+        event_fields.push(format!("\"call\""));
+        let level = self.gen.tracing_level;
+        uwrite!(
+            self.src,
+            "tracing::event!(tracing::Level::{level}, {});\n",
+            event_fields.join(", ")
+        );
     }
 
     fn define_rust_guest_export(
@@ -1494,13 +4085,18 @@ impl<'a> InterfaceGenerator<'a> {
         ns: Option<&WorldKey>,
         func: &Function,
     ) {
-        let (async_, async__, await_) = if self.gen.opts.async_ {
+        let (async_, async__, await_) = if self.gen.opts.exports_async() {
             ("async", "_async", ".await")
         } else {
             ("", "", "")
         };
 
         self.rustdoc(&func.docs);
+        if self.gen.opts.must_use_results {
+            // `Result` is already `#[must_use]`, so a bare attribute would only
+            // be redundant; the message is what this adds.
+            self.push_str("#[must_use = \"dropping this result ignores a trap in the guest\"]\n");
+        }
         uwrite!(
             self.src,
             "pub {async_} fn call_{}<S: wasmtime::AsContextMut>(&self, mut store: S, ",
@@ -1508,52 +4104,67 @@ impl<'a> InterfaceGenerator<'a> {
         );
         for (i, param) in func.params.iter().enumerate() {
             uwrite!(self.src, "arg{}: ", i);
-            self.print_ty(&param.1, TypeMode::AllBorrowed("'_"));
+            self.print_ty(&param.1, TypeMode::AllBorrowed(Lifetime::Elided));
             self.push_str(",");
         }
         self.src.push_str(") -> wasmtime::Result<");
-        self.print_result_ty(&func.results, TypeMode::Owned);
+        let trappable = self.special_case_trappable_error(&func.results, Direction::Export);
+        match &trappable {
+            Some((_, r, error_id, error_typename)) if func.results.iter_types().len() == 1 => {
+                self.print_trappable_result(r, *error_id, error_typename);
+            }
+            Some((pos, r, error_id, error_typename)) => {
+                self.push_str("(");
+                for (i, ty) in func.results.iter_types().enumerate() {
+                    if i > 0 {
+                        self.push_str(", ");
+                    }
+                    if i == *pos {
+                        self.print_trappable_result(r, *error_id, error_typename);
+                    } else {
+                        self.print_ty(ty, TypeMode::Owned);
+                    }
+                }
+                self.push_str(")");
+            }
+            None => self.print_result_ty(&func.results, TypeMode::Owned),
+        }
 
-        if self.gen.opts.async_ {
+        if self.gen.opts.exports_async() {
             self.src
                 .push_str("> where <S as wasmtime::AsContext>::Data: Send {\n");
         } else {
             self.src.push_str("> {\n");
         }
 
+        let instrument = self.instrument_spans(self.gen.opts.exports_async());
         if self.gen.opts.tracing {
             let ns = match ns {
                 Some(key) => resolve.name_world_key(key),
                 None => "default".to_string(),
             };
+            let level = self.gen.tracing_level;
             self.src.push_str(&format!(
                 "
                    let span = tracing::span!(
-                       tracing::Level::TRACE,
+                       tracing::Level::{level},
                        \"wit-bindgen export\",
                        module = \"{ns}\",
                        function = \"{}\",
                    );
-                   let _enter = span.enter();
                ",
                 func.name,
             ));
+            self.enter_span(instrument);
+            self.tracing_call_event(func);
         }
 
         self.src.push_str("let callee = unsafe {\n");
-        self.src.push_str("wasmtime::component::TypedFunc::<(");
-        for (_, ty) in func.params.iter() {
-            self.print_ty(ty, TypeMode::AllBorrowed("'_"));
-            self.push_str(", ");
-        }
-        self.src.push_str("), (");
-        for ty in func.results.iter_types() {
-            self.print_ty(ty, TypeMode::Owned);
-            self.push_str(", ");
-        }
+        self.src.push_str("wasmtime::component::TypedFunc::<");
+        self.print_typed_func_params(func, Lifetime::Elided);
         uwriteln!(
             self.src,
-            ")>::new_unchecked(self.{})",
+            ">::new_unchecked(self.{})",
             func.name.to_snake_case()
         );
         self.src.push_str("};\n");
@@ -1575,7 +4186,13 @@ impl<'a> InterfaceGenerator<'a> {
             "callee.post_return{async__}(store.as_context_mut()){await_}?;"
         );
 
-        self.src.push_str("Ok(");
+        if let Some((pos, _, error_id, error_typename)) = &trappable {
+            uwrite!(self.src, "let ret{pos} = ret{pos}.map_err(");
+            self.print_trappable_error_name(*error_id, error_typename);
+            self.push_str("::from);\n");
+        }
+
+        self.src.push_str("let r = ");
         if func.results.iter_types().len() == 1 {
             self.src.push_str("ret0");
         } else {
@@ -1585,10 +4202,71 @@ impl<'a> InterfaceGenerator<'a> {
             }
             self.src.push_str(")");
         }
-        self.src.push_str(")\n");
+        self.src.push_str(";\n");
+
+        if self.gen.opts.tracing {
+            let level = self.gen.tracing_level;
+            uwrite!(
+                self.src,
+                "tracing::event!(tracing::Level::{level}, result = tracing::field::debug(&r), \"return\");"
+            );
+        }
+
+        self.src.push_str("Ok(r)\n");
+
+        if instrument {
+            self.src.push_str("}, span).await\n");
+        }
 
         // End function body
         self.src.push_str("}\n");
+
+        if self.gen.opts.typed_func_getters {
+            self.define_typed_func_getter(func);
+        }
+    }
+
+    /// Prints the `Params, Results` type arguments of the `TypedFunc` of the
+    /// exported `func`, with borrowed parameters using the lifetime `lt`.
+    fn print_typed_func_params(&mut self, func: &Function, lt: Lifetime) {
+        self.src.push_str("(");
+        for (_, ty) in func.params.iter() {
+            self.print_ty(ty, TypeMode::AllBorrowed(lt));
+            self.push_str(", ");
+        }
+        self.src.push_str("), (");
+        for ty in func.results.iter_types() {
+            self.print_ty(ty, TypeMode::Owned);
+            self.push_str(", ");
+        }
+        self.src.push_str(")");
+    }
+
+    /// Generates the `{function}_typed` method of [`Opts::typed_func_getters`]
+    /// for the exported `func`.
+    fn define_typed_func_getter(&mut self, func: &Function) {
+        let snake = func.name.to_snake_case();
+        let prev = self.take_src();
+        self.print_typed_func_params(func, Lifetime::Generated);
+        let params = mem::replace(&mut self.src, prev).to_string();
+        // Only functions with borrowed parameters need the lifetime.
+        let lifetime = self.lifetime();
+        let generics = if params.contains(lifetime) {
+            format!("<{lifetime}>")
+        } else {
+            String::new()
+        };
+        uwriteln!(
+            self.src,
+            "
+                /// Returns the `TypedFunc` called by [`Self::call_{snake}`],
+                /// for callers which hold onto it, such as to call it
+                /// repeatedly.
+                pub fn {snake}_typed{generics}(&self) -> wasmtime::component::TypedFunc<{params}> {{
+                    unsafe {{ wasmtime::component::TypedFunc::new_unchecked(self.{snake}) }}
+                }}
+            "
+        );
     }
 
     fn define_trappable_error_type(&mut self, id: TypeId, rust_name: String) {
@@ -1605,17 +4283,41 @@ impl<'a> InterfaceGenerator<'a> {
                 pub struct {rust_name} {{
                     inner: anyhow::Error,
                 }}
-                impl std::fmt::Display for {rust_name} {{
-                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{
+                impl core::fmt::Display for {rust_name} {{
+                    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {{
                         write!(f, \"{{}}\", self.inner)
                     }}
                 }}
-                impl std::error::Error for {rust_name} {{
-                    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {{
-                        self.inner.source()
+           "
+        );
+        // Without `std` neither this type nor `abi_type` implement
+        // `std::error::Error`, so the latter is wrapped as a message instead.
+        // With it the wit error, if that's what's wrapped, is the first link
+        // of the `source` chain so that it's reported as such.
+        let (string, from_abi) = if self.gen.opts.no_std {
+            ("alloc::string::String", "anyhow::Error::msg(abi)")
+        } else {
+            uwriteln!(
+                self.src,
+                "
+                    impl std::error::Error for {rust_name} {{
+                        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {{
+                            match self.inner.downcast_ref::<{abi_type}>() {{
+                                Some(abi) => Some(abi),
+                                None => self.inner.source(),
+                            }}
+                        }}
                     }}
-                }}
+               "
+            );
+            ("String", "anyhow::Error::from(abi)")
+        };
+        uwriteln!(
+            self.src,
+            "
                 impl {rust_name} {{
+                    #[cold]
+                    #[inline(never)]
                     pub fn trap(inner: anyhow::Error) -> Self {{
                         Self {{ inner }}
                     }}
@@ -1625,13 +4327,15 @@ impl<'a> InterfaceGenerator<'a> {
                     pub fn downcast_ref(&self) -> Option<&{abi_type}> {{
                         self.inner.downcast_ref()
                     }}
-                    pub fn context(self, s: impl Into<String>) -> Self {{
+                    pub fn context(self, s: impl Into<{string}>) -> Self {{
                         Self {{ inner: self.inner.context(s.into()) }}
                     }}
                 }}
                 impl From<{abi_type}> for {rust_name} {{
+                    #[cold]
+                    #[inline(never)]
                     fn from(abi: {abi_type}) -> {rust_name} {{
-                        {rust_name} {{ inner: anyhow::Error::from(abi) }}
+                        {rust_name} {{ inner: {from_abi} }}
                     }}
                 }}
            "
@@ -1660,19 +4364,58 @@ impl<'a> RustGenerator<'a> for InterfaceGenerator<'a> {
         self.gen.opts.ownership
     }
 
+    fn no_std(&self) -> bool {
+        self.gen.opts.no_std
+    }
+
+    fn list_representation(&self) -> ListRepresentation {
+        self.gen.opts.list_representation
+    }
+
+    fn bytes_type(&self) -> Option<&'static str> {
+        if !self.gen.opts.bytes_for_u8_lists {
+            None
+        } else if self.gen.opts.use_external_deps {
+            Some("bytes::Bytes")
+        } else {
+            Some("wasmtime::component::__internal::bytes::Bytes")
+        }
+    }
+
+    fn lifetime(&self) -> &str {
+        &self.gen.opts.lifetime
+    }
+
+    fn resource_impl(&self, resource: TypeId) -> Option<String> {
+        let resource = resolve_type_definition_id(self.resolve, resource);
+        let ty = &self.resolve.types[resource];
+        let interface = match ty.owner {
+            TypeOwner::Interface(id) => id,
+            _ => return None,
+        };
+        // Resources defined by an exported interface are implemented by the
+        // guest, so they're never typed.
+        if let Some((cur, _, true)) = self.current_interface {
+            if cur == interface {
+                return None;
+            }
+        }
+        self.gen.opts.resources.get(ty.name.as_ref()?).cloned()
+    }
+
     fn path_to_interface(&self, interface: InterfaceId) -> Option<String> {
         let mut path_to_root = String::new();
         if let Some((cur, key, is_export)) = self.current_interface {
             if cur == interface {
                 return None;
             }
-            match key {
-                WorldKey::Name(_) => {
+            path_to_root.push_str("super::");
+            if let WorldKey::Interface(id) = key {
+                let pkg = self.resolve.interfaces[*id].package.unwrap();
+                let pkgname = &self.resolve.packages[pkg].name;
+                for _ in self.gen.package_module_path(pkgname) {
                     path_to_root.push_str("super::");
                 }
-                WorldKey::Interface(_) => {
-                    path_to_root.push_str("super::super::super::");
-                }
             }
             if is_export {
                 path_to_root.push_str("super::");
@@ -1692,6 +4435,19 @@ impl<'a> RustGenerator<'a> for InterfaceGenerator<'a> {
     }
 }
 
+/// Returns the name serde's `rename_all = "kebab-case"` gives to an enum
+/// variant named `name`.
+fn serde_kebab_variant(name: &str) -> String {
+    let mut ret = String::new();
+    for (i, ch) in name.char_indices() {
+        if i > 0 && ch.is_uppercase() {
+            ret.push('-');
+        }
+        ret.push(ch.to_ascii_lowercase());
+    }
+    ret
+}
+
 /// When an interface `use`s a type from another interface, it creates a new TypeId
 /// referring to the definition TypeId. Chase this chain of references down to
 /// a TypeId for type's definition.
@@ -1703,3 +4459,461 @@ fn resolve_type_definition_id(resolve: &Resolve, mut id: TypeId) -> TypeId {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "prettyplease")]
+    use super::Formatter;
+    use super::{ModulePathStyle, Opts, Ownership, TrappableError};
+    use wit_parser::{Resolve, UnresolvedPackage};
+
+    fn generate(opts: &Opts, wit: &str) -> String {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), wit).unwrap();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        opts.generate(&resolve, world)
+    }
+
+    fn build(opts: &Opts, wit: &str) -> anyhow::Result<String> {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), wit).unwrap();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        opts.build(&resolve, world)
+    }
+
+    #[test]
+    fn builder() {
+        let opts = Opts::builder()
+            .async_(true)
+            .tracing(true)
+            .with("foo:foo/bar", "crate::bar")
+            .resource("file", "crate::File")
+            .trappable_error(TrappableError::new(
+                "foo:foo/bar",
+                "error",
+                "TrappableError",
+            ))
+            .build();
+        assert!(opts.async_);
+        assert!(opts.tracing);
+        assert!(!opts.rustfmt);
+        assert!(opts.emit_type_asserts);
+        assert_eq!(opts.with["foo:foo/bar"], "crate::bar");
+        assert_eq!(opts.resources["file"], "crate::File");
+        assert_eq!(opts.trappable_error_type.len(), 1);
+    }
+
+    const MODULE_PATHS: &str = "
+        package my:pkg
+
+        interface types {
+            record r { a: u32 }
+        }
+
+        interface api {
+            use types.{r}
+            f: func(x: r)
+        }
+
+        world foo {
+            import api
+        }
+    ";
+
+    #[test]
+    fn module_path_collision() {
+        let wit = "
+            package my:pkg
+
+            interface types {
+                f: func()
+            }
+
+            world foo {
+                import types
+                import %types: interface {
+                    g: func()
+                }
+            }
+        ";
+        let opts = Opts::builder()
+            .module_path_style(ModulePathStyle::Flat)
+            .build();
+        let err = build(&opts, wit).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "multiple interfaces are generated at the module path `types`"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "prettyplease")]
+    fn prettyplease() {
+        let wit = "
+            package foo:foo
+
+            world foo {
+                import f: func()
+            }
+        ";
+        let opts = Opts::builder().formatter(Formatter::Prettyplease).build();
+        let src = generate(&opts, wit);
+        assert!(
+            src.contains("pub trait FooImports {\n    fn f(&mut self) -> wasmtime::Result<()>;\n}")
+        );
+    }
+
+    #[test]
+    fn ownership_from_str() {
+        for s in ["owning", "borrowing", "borrowing-duplicate-if-necessary"] {
+            let ownership = s.parse::<Ownership>().unwrap();
+            assert_eq!(ownership.to_string(), s);
+        }
+        assert!(matches!(
+            "borrowing".parse::<Ownership>().unwrap(),
+            Ownership::Borrowing {
+                duplicate_if_necessary: false
+            }
+        ));
+        let err = "borrowed".parse::<Ownership>().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unrecognized ownership: `borrowed`"));
+    }
+
+    #[test]
+    fn no_std_async() {
+        let wit = "
+            package foo:foo
+
+            world foo {
+                import f: func()
+            }
+        ";
+        let err = build(&Opts::builder().no_std(true).async_(true).build(), wit).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`no_std` bindings can't be generated with `async` enabled"
+        );
+    }
+
+    #[test]
+    fn allowed_lints() {
+        let wit = "
+            package foo:foo
+
+            interface bar {
+                f: func()
+            }
+
+            world foo {
+                import bar
+            }
+        ";
+        let src = generate(&Opts::default(), wit);
+        assert!(!src.contains("clippy::all"));
+        assert!(src.contains("#[allow(dead_code, unused_imports, clippy::borrow_deref_ref,"));
+
+        let opts = Opts::builder()
+            .allowed_lints(vec!["clippy::type_complexity".to_string()])
+            .build();
+        let src = generate(&opts, wit);
+        assert!(src.contains("#[allow(clippy::type_complexity)]"));
+
+        let opts = Opts::builder().allowed_lints(Vec::new()).build();
+        let src = generate(&opts, wit);
+        assert!(!src.contains("#[allow(clippy"));
+    }
+
+    #[test]
+    fn invalid_lifetime() {
+        let wit = "
+            package foo:foo
+
+            world foo {
+                import f: func()
+            }
+        ";
+        let opts = Opts::builder().lifetime("'static".to_string()).build();
+        let err = build(&opts, wit).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid lifetime `'static` for generated types"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "resolving \"foo:foo/bar/missing\"")]
+    fn type_with_unknown_type() {
+        let wit = "
+            package foo:foo
+
+            interface bar {
+                type t = u32
+            }
+
+            world foo {
+                import bar
+            }
+        ";
+        let opts = Opts::builder()
+            .type_with("foo:foo/bar/missing", "crate::Missing")
+            .build();
+        generate(&opts, wit);
+    }
+
+    #[test]
+    fn type_with_unknown_type_error() {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse(
+            "test.wit".as_ref(),
+            "package foo:foo
+             interface bar { type t = u32 }
+             world foo { import bar }",
+        )
+        .unwrap();
+        resolve.push(pkg).unwrap();
+        let opts = Opts::builder()
+            .type_with("foo:foo/bar/missing", "crate::Missing")
+            .build();
+        let err = opts.generate_by_name(&resolve, "foo").unwrap_err();
+        assert!(format!("{err:?}").contains("resolving \"foo:foo/bar/missing\""));
+    }
+
+    #[test]
+    fn invalid_tracing_level() {
+        let wit = "
+            package foo:foo
+
+            world foo {
+                import f: func()
+            }
+        ";
+        let err = build(&Opts::builder().tracing_level("loud").build(), wit).unwrap_err();
+        assert_eq!(err.to_string(), "invalid tracing level `loud`");
+    }
+
+    #[test]
+    fn import_vtable_async() {
+        let wit = "
+            package foo:foo
+
+            world foo {
+                import f: func()
+            }
+        ";
+        let opts = Opts::builder().import_vtable(true).async_(true).build();
+        let err = build(&opts, wit).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`import_vtable` can't be generated with `async` enabled"
+        );
+    }
+
+    #[test]
+    fn import_async_without_export_async() {
+        let wit = "
+            package foo:foo
+
+            world foo {
+                import get: func() -> u32
+            }
+        ";
+        let err = build(&Opts::builder().import_async(true).build(), wit).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("`import_async` requires `export_async`"));
+    }
+
+    #[test]
+    #[cfg(feature = "prettyplease")]
+    fn header_and_footer() {
+        let wit = "
+            package foo:foo
+
+            world foo {
+                import f: func()
+            }
+        ";
+        let header = "#![allow(missing_docs)]\n//! Generated bindings.\n";
+        let footer = "impl Foo {}\n";
+        let opts = Opts::builder()
+            .formatter(Formatter::Prettyplease)
+            .header(header)
+            .footer(footer)
+            .build();
+        let src = generate(&opts, wit);
+        assert_eq!(src.find(header), Some(0));
+        assert!(src.ends_with(footer));
+
+        let src = generate(&Opts::default(), wit);
+        assert!(!src.contains("Generated bindings."));
+    }
+
+    #[test]
+    fn generate_by_name() {
+        let mut resolve = Resolve::default();
+        let mut pkgs = Vec::new();
+        for wit in [
+            "package foo:a
+             world one { import f: func() }
+             world two { import g: func() }",
+            "package foo:b
+             world two { import h: func() }",
+        ] {
+            let pkg = UnresolvedPackage::parse("test.wit".as_ref(), wit).unwrap();
+            pkgs.push(resolve.push(pkg).unwrap());
+        }
+        let opts = Opts::default();
+        let expected = |pkg, name| {
+            let world = resolve.select_world(pkg, Some(name)).unwrap();
+            opts.generate(&resolve, world)
+        };
+
+        let src = opts.generate_by_name(&resolve, "one").unwrap();
+        assert_eq!(src, expected(pkgs[0], "one"));
+        let src = opts.generate_by_name(&resolve, "foo:b/two").unwrap();
+        assert_eq!(src, expected(pkgs[1], "two"));
+
+        let err = opts.generate_by_name(&resolve, "two").unwrap_err();
+        assert!(err.to_string().contains("foo:a/two, foo:b/two"));
+        assert!(opts.generate_by_name(&resolve, "three").is_err());
+        assert!(opts.generate_by_name(&resolve, "foo:a/three").is_err());
+        assert!(opts.generate_by_name(&resolve, "foo:c/one").is_err());
+        assert!(opts.generate_by_name(&resolve, "").is_err());
+
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse(
+            "test.wit".as_ref(),
+            "package foo:a
+             world one { import f: func() }",
+        )
+        .unwrap();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        let src = opts.generate_by_name(&resolve, "").unwrap();
+        assert_eq!(src, opts.generate(&resolve, world));
+    }
+
+    #[test]
+    fn ffi_records_unlisted_field() {
+        let wit = "
+            package foo:foo
+
+            interface bar {
+                record point {
+                    x: s32,
+                    y: s32,
+                }
+
+                record segment {
+                    start: point,
+                    end: point,
+                }
+            }
+
+            world foo {
+                import bar
+            }
+        ";
+        let opts = Opts::builder().ffi_record("foo:foo/bar/segment").build();
+        let err = build(&opts, wit).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("field `start` of the FFI record \"foo:foo/bar/segment\""));
+    }
+
+    #[test]
+    fn async_interfaces_without_export_async() {
+        let wit = "
+            package foo:foo
+
+            interface fs {
+                read: func() -> list<u8>
+            }
+
+            world foo {
+                import fs
+            }
+        ";
+        let opts = Opts::builder().async_interface("foo:foo/fs").build();
+        let err = build(&opts, wit).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("`async_interfaces` requires `export_async`"));
+
+        let opts = Opts::builder()
+            .async_interface("foo:foo/fs")
+            .export_async(true)
+            .import_vtable(true)
+            .build();
+        let err = build(&opts, wit).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`import_vtable` can't be generated with `async_interfaces`"
+        );
+    }
+
+    #[test]
+    fn try_generate() {
+        let wit = "
+            package foo:foo
+
+            interface fs {
+                resource file
+                resource dir
+                open: func(d: borrow<dir>) -> file
+            }
+
+            interface net {
+                resource socket
+            }
+
+            world foo {
+                import fs
+                import net
+            }
+        ";
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), wit).unwrap();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+
+        let opts = Opts::builder()
+            .resource("file", "crate::File")
+            .resource("directory", "crate::Dir")
+            .build();
+        let err = opts.try_generate(&resolve, world).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no entry in `resources` for `dir` of `foo:foo/fs`, `socket` of `foo:foo/net`; \
+             no imported resource for the `resources` entries `directory`"
+        );
+
+        // The same check runs from `build` with `require_resources`.
+        let opts = Opts::builder()
+            .resource("file", "crate::File")
+            .resource("directory", "crate::Dir")
+            .require_resources(true)
+            .build();
+        let err = opts.build(&resolve, world).unwrap_err();
+        assert!(err.to_string().starts_with("no entry in `resources`"));
+
+        let opts = Opts::builder()
+            .resource("file", "crate::File")
+            .resource("dir", "crate::Dir")
+            .resource("socket", "crate::Socket")
+            .build();
+        let src = opts.try_generate(&resolve, world).unwrap();
+        assert_eq!(src, opts.generate(&resolve, world));
+
+        let opts = Opts::builder()
+            .resource("file", "crate::File")
+            .resource("dir", "crate::Dir")
+            .with("foo:foo/net", "crate::net")
+            .build();
+        assert!(opts.try_generate(&resolve, world).is_ok());
+    }
+}
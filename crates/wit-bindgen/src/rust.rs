@@ -1,4 +1,4 @@
-use crate::{types::TypeInfo, Ownership};
+use crate::{types::TypeInfo, ListRepresentation, Ownership};
 use heck::*;
 use std::collections::HashMap;
 use std::fmt::Write;
@@ -7,7 +7,16 @@ use wit_parser::*;
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum TypeMode {
     Owned,
-    AllBorrowed(&'static str),
+    AllBorrowed(Lifetime),
+}
+
+/// The lifetime of borrowed types, see [`TypeMode::AllBorrowed`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Lifetime {
+    /// The anonymous lifetime `'_`, which is left out of references.
+    Elided,
+    /// The lifetime of generated types, [`RustGenerator::lifetime`].
+    Generated,
 }
 
 pub trait RustGenerator<'a> {
@@ -28,6 +37,27 @@ pub trait RustGenerator<'a> {
     /// inside function signatures.
     fn ownership(&self) -> Ownership;
 
+    /// Whether `alloc` types must be named by their full paths as the
+    /// generated code may not have the `std` prelude in scope.
+    fn no_std(&self) -> bool;
+
+    /// The Rust type used for owned lists.
+    fn list_representation(&self) -> ListRepresentation;
+
+    /// The path to `bytes::Bytes` if it's used for owned `list<u8>`s.
+    fn bytes_type(&self) -> Option<&'static str>;
+
+    /// The lifetime of generated types which borrow their contents.
+    fn lifetime(&self) -> &str;
+
+    /// Returns the Rust type, if any, which the host uses to represent the
+    /// resource `resource`.
+    ///
+    /// Handles to such resources are printed as a typed
+    /// `wasmtime::component::Resource` while all others are erased to
+    /// `wasmtime::component::ResourceAny`.
+    fn resource_impl(&self, resource: TypeId) -> Option<String>;
+
     fn print_ty(&mut self, ty: &Type, mode: TypeMode) {
         match ty {
             Type::Id(t) => self.print_tyid(*t, mode),
@@ -46,13 +76,16 @@ pub trait RustGenerator<'a> {
             Type::String => match mode {
                 TypeMode::AllBorrowed(lt) => {
                     self.push_str("&");
-                    if lt != "'_" {
-                        self.push_str(lt);
-                        self.push_str(" ");
-                    }
+                    self.print_ref_lifetime(lt);
                     self.push_str("str");
                 }
-                TypeMode::Owned => self.push_str("String"),
+                TypeMode::Owned => {
+                    if self.no_std() {
+                        self.push_str("alloc::string::String");
+                    } else {
+                        self.push_str("String");
+                    }
+                }
             },
         }
     }
@@ -77,10 +110,7 @@ pub trait RustGenerator<'a> {
             if info.has_list && lt.is_none() {
                 if let TypeMode::AllBorrowed(lt) = mode {
                     self.push_str("&");
-                    if lt != "'_" {
-                        self.push_str(lt);
-                        self.push_str(" ");
-                    }
+                    self.print_ref_lifetime(lt);
                 }
             }
             let name = if lt.is_some() {
@@ -185,14 +215,31 @@ pub trait RustGenerator<'a> {
                 self.push_str(">");
             }
 
-            TypeDefKind::Handle(_) => todo!("#6722"),
-            TypeDefKind::Resource => todo!("#6722"),
+            TypeDefKind::Handle(handle) => self.print_handle(handle),
+            TypeDefKind::Resource => panic!("unsupported anonymous type reference: resource"),
 
             TypeDefKind::Type(t) => self.print_ty(t, mode),
             TypeDefKind::Unknown => unreachable!(),
         }
     }
 
+    fn print_handle(&mut self, handle: &Handle) {
+        let resource = match handle {
+            Handle::Own(id) | Handle::Borrow(id) => *id,
+        };
+        match self.resource_impl(resource) {
+            Some(rust_type) => {
+                self.push_str("wasmtime::component::Resource<");
+                self.push_str(&rust_type);
+                self.push_str(">");
+            }
+            // Handles to resources without a host representation are erased
+            // to `ResourceAny` regardless of whether they're owned or
+            // borrowed, so the resource identity is checked dynamically.
+            None => self.push_str("wasmtime::component::ResourceAny"),
+        }
+    }
+
     fn print_list(&mut self, ty: &Type, mode: TypeMode) {
         let next_mode = if matches!(self.ownership(), Ownership::Owning) {
             TypeMode::Owned
@@ -202,31 +249,47 @@ pub trait RustGenerator<'a> {
         match mode {
             TypeMode::AllBorrowed(lt) => {
                 self.push_str("&");
-                if lt != "'_" {
-                    self.push_str(lt);
-                    self.push_str(" ");
-                }
+                self.print_ref_lifetime(lt);
                 self.push_str("[");
                 self.print_ty(ty, next_mode);
                 self.push_str("]");
             }
+            TypeMode::Owned if matches!(ty, Type::U8) && self.bytes_type().is_some() => {
+                self.push_str(self.bytes_type().unwrap());
+            }
             TypeMode::Owned => {
-                self.push_str("Vec<");
+                let (open, close) = match (self.list_representation(), self.no_std()) {
+                    (ListRepresentation::Vec, false) => ("Vec<", ">"),
+                    (ListRepresentation::Vec, true) => ("alloc::vec::Vec<", ">"),
+                    (ListRepresentation::BoxedSlice, false) => ("Box<[", "]>"),
+                    (ListRepresentation::BoxedSlice, true) => ("alloc::boxed::Box<[", "]>"),
+                };
+                self.push_str(open);
                 self.print_ty(ty, next_mode);
-                self.push_str(">");
+                self.push_str(close);
             }
         }
     }
 
-    fn print_generics(&mut self, lifetime: Option<&str>) {
-        if lifetime.is_none() {
-            return;
+    /// Prints the lifetime `lt` of a reference followed by a space, or nothing
+    /// when it's elided.
+    fn print_ref_lifetime(&mut self, lt: Lifetime) {
+        if let Lifetime::Generated = lt {
+            let lt = self.lifetime().to_string();
+            self.push_str(&lt);
+            self.push_str(" ");
         }
+    }
+
+    fn print_generics(&mut self, lifetime: Option<Lifetime>) {
+        let lt = match lifetime {
+            Some(Lifetime::Elided) => "'_".to_string(),
+            Some(Lifetime::Generated) => self.lifetime().to_string(),
+            None => return,
+        };
         self.push_str("<");
-        if let Some(lt) = lifetime {
-            self.push_str(lt);
-            self.push_str(",");
-        }
+        self.push_str(&lt);
+        self.push_str(",");
         self.push_str(">");
     }
 
@@ -241,11 +304,14 @@ pub trait RustGenerator<'a> {
                 TypeMode::Owned
             } else {
                 assert!(!self.uses_two_names(&info));
-                TypeMode::AllBorrowed("'a")
+                TypeMode::AllBorrowed(Lifetime::Generated)
             };
         result.push((self.result_name(ty), first_mode));
         if self.uses_two_names(&info) {
-            result.push((self.param_name(ty), TypeMode::AllBorrowed("'a")));
+            result.push((
+                self.param_name(ty),
+                TypeMode::AllBorrowed(Lifetime::Generated),
+            ));
         }
         result
     }
@@ -297,8 +363,11 @@ pub trait RustGenerator<'a> {
                         TypeDefKind::Variant(_) => out.push_str("Variant"),
                         TypeDefKind::Enum(_) => out.push_str("Enum"),
                         TypeDefKind::Union(_) => out.push_str("Union"),
-                        TypeDefKind::Handle(_) => todo!("#6722"),
-                        TypeDefKind::Resource => todo!("#6722"),
+                        TypeDefKind::Handle(Handle::Own(id))
+                        | TypeDefKind::Handle(Handle::Borrow(id)) => {
+                            self.write_name(&Type::Id(*id), out)
+                        }
+                        TypeDefKind::Resource => out.push_str("Resource"),
                         TypeDefKind::Unknown => unreachable!(),
                     },
                 }
@@ -403,7 +472,7 @@ pub trait RustGenerator<'a> {
             )
     }
 
-    fn lifetime_for(&self, info: &TypeInfo, mode: TypeMode) -> Option<&'static str> {
+    fn lifetime_for(&self, info: &TypeInfo, mode: TypeMode) -> Option<Lifetime> {
         if matches!(self.ownership(), Ownership::Owning) {
             return None;
         }
@@ -487,6 +556,18 @@ pub fn to_rust_ident(name: &str) -> String {
     }
 }
 
+/// Same as [`to_rust_ident`] except that keywords are escaped as raw
+/// identifiers, such as `r#type`, where Rust allows it.
+pub fn to_rust_raw_ident(name: &str) -> String {
+    let ident = to_rust_ident(name);
+    match name {
+        // These keywords can't be raw identifiers.
+        "crate" | "self" | "super" => ident,
+        _ if ident != name.to_snake_case() => format!("r#{name}"),
+        _ => ident,
+    }
+}
+
 /// Translate `name` to a Rust `UpperCamelCase` identifier.
 pub fn to_rust_upper_camel_case(name: &str) -> String {
     match name {
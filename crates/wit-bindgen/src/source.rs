@@ -2,20 +2,42 @@ use std::fmt::{self, Write};
 use std::ops::Deref;
 
 /// Helper structure to maintain indentation automatically when printing.
-#[derive(Default)]
 pub struct Source {
     s: String,
     indent: usize,
+    indent_width: usize,
+}
+
+impl Default for Source {
+    fn default() -> Source {
+        Source {
+            s: String::new(),
+            indent: 0,
+            indent_width: 2,
+        }
+    }
 }
 
 impl Source {
+    /// Creates an empty source indented by `width` spaces per level.
+    pub fn with_indent_width(width: usize) -> Source {
+        Source {
+            indent_width: width,
+            ..Source::default()
+        }
+    }
+
+    /// Sets the number of spaces emitted per level of indentation.
+    pub fn set_indent_width(&mut self, width: usize) {
+        self.indent_width = width;
+    }
+
     pub fn push_str(&mut self, src: &str) {
         let lines = src.lines().collect::<Vec<_>>();
         for (i, line) in lines.iter().enumerate() {
             let trimmed = line.trim();
-            if trimmed.starts_with('}') && self.s.ends_with("  ") {
-                self.s.pop();
-                self.s.pop();
+            if trimmed.starts_with('}') && self.s.ends_with(&" ".repeat(self.indent_width)) {
+                self.s.truncate(self.s.len() - self.indent_width);
             }
             self.s.push_str(if lines.len() == 1 {
                 line
@@ -48,8 +70,8 @@ impl Source {
 
     fn newline(&mut self) {
         self.s.push('\n');
-        for _ in 0..self.indent {
-            self.s.push_str("  ");
+        for _ in 0..self.indent * self.indent_width {
+            self.s.push(' ');
         }
     }
 
@@ -127,4 +149,16 @@ mod tests {
         );
         assert_eq!(s.s, "function() {\n  x\n}");
     }
+
+    #[test]
+    fn indent_width() {
+        let mut s = Source::default();
+        s.set_indent_width(4);
+        s.push_str("if() {\n");
+        s.push_str("y\n");
+        s.push_str("} else {\n");
+        s.push_str("z\n");
+        s.push_str("}\n");
+        assert_eq!(s.s, "if() {\n    y\n} else {\n    z\n}\n");
+    }
 }
@@ -7,8 +7,11 @@ use wasmtime::{
     Store,
 };
 
+mod exports;
+mod imports;
 mod ownership;
 mod results;
+mod types;
 
 mod no_imports {
     use super::*;
@@ -119,4 +122,462 @@ mod one_import {
         assert!(store.data().hit);
         Ok(())
     }
+
+    fn component(engine: &wasmtime::Engine) -> Result<Component> {
+        Component::new(
+            engine,
+            r#"
+                (component
+                    (import "foo" (instance $i
+                        (export "foo" (func))
+                    ))
+                    (core module $m
+                        (import "" "" (func))
+                        (export "" (func 0))
+                    )
+                    (core func $f (canon lower (func $i "foo")))
+                    (core instance $i (instantiate $m
+                        (with "" (instance (export "" (func $f))))
+                    ))
+
+                    (func $f (export "bar") (canon lift (core func $i "")))
+                )
+            "#,
+        )
+    }
+
+    #[derive(Default)]
+    struct MyImports {
+        hit: bool,
+    }
+
+    impl foo::Host for MyImports {
+        fn foo(&mut self) -> Result<()> {
+            self.hit = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn get_result() -> Result<()> {
+        let engine = engine();
+        let component = component(&engine)?;
+
+        let mut linker = Linker::new(&engine);
+        foo::add_to_linker_get_result(&mut linker, |f: &mut Option<MyImports>| {
+            f.as_mut()
+                .ok_or_else(|| anyhow::anyhow!("imports not initialized"))
+        })?;
+
+        let mut store = Store::new(&engine, None);
+        let (one_import, _) = OneImport::instantiate(&mut store, &component, &linker)?;
+        let err = one_import.call_bar(&mut store).unwrap_err();
+        assert!(format!("{err:?}").contains("imports not initialized"));
+
+        let mut store = Store::new(&engine, Some(MyImports::default()));
+        let (one_import, _) = OneImport::instantiate(&mut store, &component, &linker)?;
+        one_import.call_bar(&mut store)?;
+        assert!(store.data().as_ref().unwrap().hit);
+        Ok(())
+    }
+
+    #[test]
+    fn imports() -> Result<()> {
+        let engine = engine();
+        let component = component(&engine)?;
+
+        let mut linker = Linker::new(&engine);
+        OneImport::add_to_linker(&mut linker, |f: &mut (u32, MyImports)| &mut f.1)?;
+        let mut store = Store::new(&engine, (0, MyImports::default()));
+        let (one_import, _) = OneImport::instantiate(&mut store, &component, &linker)?;
+        assert!(!one_import.imports(&store, |f: &(u32, MyImports)| &f.1).hit);
+        one_import.call_bar(&mut store)?;
+        assert!(one_import.imports(&store, |f: &(u32, MyImports)| &f.1).hit);
+        Ok(())
+    }
+
+    #[test]
+    fn dyn_host() -> Result<()> {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        struct SharedImports(Arc<AtomicBool>);
+
+        impl foo::Host for SharedImports {
+            fn foo(&mut self) -> Result<()> {
+                self.0.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        let engine = engine();
+        let component = component(&engine)?;
+
+        let mut linker = Linker::new(&engine);
+        foo::add_to_linker_dyn(&mut linker, |f: &mut Box<dyn foo::Host>| &mut **f)?;
+
+        let hit = Arc::new(AtomicBool::new(false));
+        let hosts: Vec<Box<dyn foo::Host>> = vec![
+            Box::new(MyImports::default()),
+            Box::new(SharedImports(hit.clone())),
+        ];
+        for host in hosts {
+            let mut store = Store::new(&engine, host);
+            let (one_import, _) = OneImport::instantiate(&mut store, &component, &linker)?;
+            one_import.call_bar(&mut store)?;
+        }
+        assert!(hit.load(Ordering::SeqCst));
+        Ok(())
+    }
+
+    #[test]
+    fn add_to_linker_table() -> Result<()> {
+        let engine = engine();
+        let component = component(&engine)?;
+
+        let table = OneImport::add_to_linker_table::<MyImports, MyImports>();
+        let names = table.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, ["foo"]);
+
+        // Without the import the component can't be instantiated.
+        let mut store = Store::new(&engine, MyImports::default());
+        let linker = Linker::new(&engine);
+        assert!(OneImport::instantiate(&mut store, &component, &linker).is_err());
+
+        let mut linker = Linker::new(&engine);
+        for (_, add) in table {
+            add(&mut linker, |f| Ok(f))?;
+        }
+        let (one_import, _) = OneImport::instantiate(&mut store, &component, &linker)?;
+        one_import.call_bar(&mut store)?;
+        assert!(store.data().hit);
+        Ok(())
+    }
+}
+
+mod serde_names {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world serde-names {
+                import foo: interface {
+                    enum color { light-red, dark-blue }
+                    record point { x-coord: u32, %type: u32, color: color }
+                    variant shape { dot(point), empty-set }
+
+                    foo: func(p: point, s: shape)
+                }
+            }
+        ",
+        serde: true,
+    });
+
+    #[test]
+    fn run() -> Result<()> {
+        let p = foo::Point {
+            x_coord: 1,
+            type_: 2,
+            color: foo::Color::DarkBlue,
+        };
+        let json = serde_json::to_string(&p)?;
+        assert_eq!(json, r#"{"x-coord":1,"type":2,"color":"dark-blue"}"#);
+
+        let p: foo::Point = serde_json::from_str(&json)?;
+        assert_eq!(p.x_coord, 1);
+        assert_eq!(p.type_, 2);
+        assert!(p.color == foo::Color::DarkBlue);
+
+        let s = foo::Shape::Dot(p);
+        let json = serde_json::to_string(&s)?;
+        assert_eq!(
+            json,
+            r#"{"dot":{"x-coord":1,"type":2,"color":"dark-blue"}}"#
+        );
+        let json = serde_json::to_string(&foo::Shape::EmptySet)?;
+        assert_eq!(json, r#""empty-set""#);
+        let s: foo::Shape = serde_json::from_str(&json)?;
+        assert!(matches!(s, foo::Shape::EmptySet));
+        Ok(())
+    }
+}
+
+mod additional_derives {
+    use super::*;
+    use std::collections::HashSet;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world additional-derives {
+                import foo: interface {
+                    enum color { red, green }
+                    flags perms { read, write }
+                    record point { x: u32, color: color, perms: perms }
+                    variant shape { dot(point), none }
+
+                    foo: func(s: shape)
+                }
+            }
+        ",
+        additional_derives: [Hash, PartialEq, Eq],
+    });
+
+    #[test]
+    fn run() {
+        let p = foo::Point {
+            x: 1,
+            color: foo::Color::Green,
+            perms: foo::Perms::READ | foo::Perms::WRITE,
+        };
+        let mut set = HashSet::new();
+        assert!(set.insert(foo::Shape::Dot(p)));
+        assert!(set.insert(foo::Shape::None));
+        assert!(!set.insert(foo::Shape::Dot(p)));
+    }
+}
+
+mod hashable_keys {
+    use super::*;
+    use std::collections::HashMap;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world hashable-keys {
+                import foo: interface {
+                    enum color { red, green }
+                    flags perms { read, write }
+
+                    foo: func(c: color, p: perms)
+                }
+            }
+        ",
+    });
+
+    #[test]
+    fn run() {
+        let mut colors = HashMap::new();
+        colors.insert(foo::Color::Red, "red");
+        colors.insert(foo::Color::Green, "green");
+        assert_eq!(colors[&foo::Color::Green], "green");
+
+        let mut perms = HashMap::new();
+        perms.insert(foo::Perms::READ | foo::Perms::WRITE, "rw");
+        assert_eq!(perms[&foo::Perms::all()], "rw");
+        assert_eq!(perms.get(&foo::Perms::READ), None);
+    }
+}
+
+mod enum_discriminants {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world enum-discriminants {
+                import foo: interface {
+                    enum color { red, green, blue }
+
+                    foo: func(c: color)
+                }
+            }
+        ",
+    });
+
+    #[test]
+    fn run() {
+        for color in [foo::Color::Red, foo::Color::Green, foo::Color::Blue] {
+            assert_eq!(foo::Color::try_from(color as u32).unwrap(), color);
+        }
+        assert_eq!(foo::Color::try_from(2).unwrap(), foo::Color::Blue);
+        let err = foo::Color::try_from(3).unwrap_err();
+        assert_eq!(err.to_string(), "invalid `Color` discriminant `3`");
+        assert!(foo::Color::try_from(u32::MAX).is_err());
+    }
+}
+
+mod typed_resources {
+    use super::*;
+    use wasmtime::component::Resource;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world typed-resources {
+                import fs: interface {
+                    resource file
+                    record entry { name: string, file: own<file> }
+
+                    close-all: func(files: list<own<file>>)
+                    reopen: func(file: option<borrow<file>>) -> result<entry>
+                }
+            }
+        ",
+        resources: {
+            "file": super::MyFile,
+        },
+    });
+
+    pub struct MyFile;
+
+    #[derive(Default)]
+    struct MyImports {
+        dropped: Vec<u32>,
+    }
+
+    impl fs::HostFile for MyImports {
+        fn drop(&mut self, rep: Resource<MyFile>) -> Result<()> {
+            self.dropped.push(rep.rep());
+            Ok(())
+        }
+    }
+
+    impl fs::Host for MyImports {
+        fn close_all(&mut self, files: Vec<Resource<MyFile>>) -> Result<()> {
+            self.dropped.extend(files.iter().map(|f| f.rep()));
+            Ok(())
+        }
+
+        fn reopen(&mut self, file: Option<&Resource<MyFile>>) -> Result<Result<fs::Entry, ()>> {
+            Ok(match file {
+                Some(file) => Ok(fs::Entry {
+                    name: "reopened".to_string(),
+                    file: Resource::new_own(file.rep() + 1),
+                }),
+                None => Err(()),
+            })
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import "fs" (instance $fs
+                        (export "file" (type (sub resource)))
+                    ))
+                    (alias export $fs "file" (type $file))
+
+                    (core func $dtor (canon resource.drop $file))
+                    (func (export "dtor") (param "x" (own $file))
+                        (canon lift (core func $dtor)))
+                )
+            "#,
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        fs::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports::default());
+        let instance = linker.instantiate(&mut store, &component)?;
+        let dtor = instance.get_typed_func::<(Resource<MyFile>,), ()>(&mut store, "dtor")?;
+        dtor.call(&mut store, (Resource::new_own(7),))?;
+        dtor.post_return(&mut store)?;
+        assert_eq!(store.data().dropped, [7]);
+        Ok(())
+    }
+}
+
+mod external_deps {
+    use super::*;
+    use wasmtime::Engine;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world external-deps {
+                import foo: interface {
+                    foo: func() -> u32
+                }
+                import bar: func()
+            }
+        ",
+        async: true,
+        use_external_deps: true,
+    });
+
+    struct MyImports;
+
+    #[async_trait::async_trait]
+    impl foo::Host for MyImports {
+        async fn foo(&mut self) -> anyhow::Result<u32> {
+            Ok(1)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ExternalDepsImports for MyImports {
+        async fn bar(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let mut config = component_test_util::config();
+        config.async_support(true);
+        let engine = Engine::new(&config)?;
+        let mut linker = Linker::<MyImports>::new(&engine);
+        ExternalDeps::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        Ok(())
+    }
+}
+
+mod optional_exports {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world optional-exports {
+                export foo: interface {
+                    foo: func()
+                }
+                export bar: interface {
+                    bar: func()
+                }
+            }
+        ",
+        optional_exports: true,
+    });
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (core module $m
+                        (func (export ""))
+                    )
+                    (core instance $i (instantiate $m))
+
+                    (func $f (canon lift (core func $i "")))
+
+                    (instance $i (export "foo" (func $f)))
+                    (export "foo" (instance $i))
+                )
+            "#,
+        )?;
+
+        let linker = Linker::new(&engine);
+        let mut store = Store::new(&engine, ());
+        let (exports, _) = OptionalExports::instantiate(&mut store, &component, &linker)?;
+        exports.foo().unwrap().call_foo(&mut store)?;
+        assert!(exports.bar().is_none());
+        Ok(())
+    }
 }
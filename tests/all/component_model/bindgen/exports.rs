@@ -0,0 +1,354 @@
+use super::{super::async_engine, engine};
+use anyhow::Result;
+use wasmtime::{
+    component::{Component, Func, Linker},
+    Store,
+};
+
+mod export_lookup {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                g: func()
+            }
+
+            world export-lookup {
+                export bar
+                export f: func()
+            }
+        ",
+        export_lookup: true,
+        optional_exports: true,
+    });
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (core module $m
+                        (func (export ""))
+                    )
+                    (core instance $i (instantiate $m))
+
+                    (func $f (export "f") (canon lift (core func $i "")))
+                )
+            "#,
+        )?;
+
+        let mut names = ExportLookup::EXPORT_NAMES.to_vec();
+        names.sort();
+        assert_eq!(names, ["f", "foo:foo/bar"]);
+
+        let linker = Linker::new(&engine);
+        let mut store = Store::new(&engine, ());
+        let (exports, _) = ExportLookup::instantiate(&mut store, &component, &linker)?;
+        let f = exports.export_by_name("f").unwrap();
+        let f = f.downcast_ref::<Func>().unwrap().typed::<(), ()>(&store)?;
+        f.call(&mut store, ())?;
+        f.post_return(&mut store)?;
+
+        // The optional interface is missing from this instance.
+        assert!(exports.export_by_name("foo:foo/bar").is_none());
+        assert!(exports.export_by_name("g").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn interface() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (core module $m
+                        (func (export ""))
+                    )
+                    (core instance $i (instantiate $m))
+
+                    (func $f (export "f") (canon lift (core func $i "")))
+
+                    (instance $bar (export "g" (func $f)))
+                    (export "foo:foo/bar" (instance $bar))
+                )
+            "#,
+        )?;
+
+        let linker = Linker::new(&engine);
+        let mut store = Store::new(&engine, ());
+        let (exports, _) = ExportLookup::instantiate(&mut store, &component, &linker)?;
+        let bar = exports.export_by_name("foo:foo/bar").unwrap();
+        bar.downcast_ref::<exports::foo::foo::bar::Bar>()
+            .unwrap()
+            .call_g(&mut store)?;
+        Ok(())
+    }
+}
+
+mod instantiate_pre_async_future {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world instantiate-pre-async-future {
+                export f: func()
+            }
+        ",
+        async: true,
+    });
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[tokio::test]
+    async fn run() -> Result<()> {
+        let engine = async_engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (core module $m
+                        (func (export ""))
+                    )
+                    (core instance $i (instantiate $m))
+
+                    (func (export "f") (canon lift (core func $i "")))
+                )
+            "#,
+        )?;
+
+        let linker = Linker::new(&engine);
+        let instance_pre = linker.instantiate_pre(&component)?;
+        let mut store = Store::new(&engine, ());
+
+        let future =
+            InstantiatePreAsyncFuture::instantiate_pre_async_future(&mut store, &instance_pre);
+        assert_send(&future);
+        let instance = future.await?;
+
+        // Setup which needs the instance can run before wrapping its exports.
+        assert!(instance.get_func(&mut store, "f").is_some());
+        let exports = InstantiatePreAsyncFuture::new(&mut store, &instance)?;
+        exports.call_f(&mut store).await?;
+        Ok(())
+    }
+}
+
+mod export_async_only {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                get: func() -> u32
+            }
+
+            world export-async-only {
+                import bar
+                export run: func()
+            }
+        ",
+        export_async: true,
+    });
+
+    #[derive(Default)]
+    struct MyImports {
+        calls: u32,
+    }
+
+    // The imports stay synchronous.
+    impl foo::foo::bar::Host for MyImports {
+        fn get(&mut self) -> Result<u32> {
+            self.calls += 1;
+            Ok(self.calls)
+        }
+    }
+
+    #[tokio::test]
+    async fn run() -> Result<()> {
+        let engine = async_engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import "foo:foo/bar" (instance $bar
+                        (export "get" (func (result u32)))
+                    ))
+                    (core func $get (canon lower (func $bar "get")))
+                    (core module $m
+                        (import "" "get" (func $get (result i32)))
+                        (func (export "run")
+                            (drop (call $get)))
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance (export "get" (func $get))))
+                    ))
+
+                    (func (export "run") (canon lift (core func $i "run")))
+                )
+            "#,
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        ExportAsyncOnly::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports::default());
+        let (exports, _) =
+            ExportAsyncOnly::instantiate_async(&mut store, &component, &linker).await?;
+        exports.call_run(&mut store).await?;
+        exports.call_run(&mut store).await?;
+        assert_eq!(store.data().calls, 2);
+        Ok(())
+    }
+}
+
+mod stable_export_fields {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface zeta {
+                z: func()
+            }
+
+            interface alpha {
+                a: func()
+            }
+
+            world stable-export-fields {
+                export zeta
+                export alpha
+            }
+        ",
+    });
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (core module $m
+                        (func (export ""))
+                    )
+                    (core instance $i (instantiate $m))
+
+                    (func $f (canon lift (core func $i "")))
+
+                    (instance $zeta (export "z" (func $f)))
+                    (instance $alpha (export "a" (func $f)))
+                    (export "foo:foo/zeta" (instance $zeta))
+                    (export "foo:foo/alpha" (instance $alpha))
+                )
+            "#,
+        )?;
+
+        let linker = Linker::new(&engine);
+        let mut store = Store::new(&engine, ());
+        let (exports, _) = StableExportFields::instantiate(&mut store, &component, &linker)?;
+
+        // The fields are named after the interfaces rather than their order.
+        assert!(std::ptr::eq(
+            exports.foo_foo_alpha(),
+            &exports.interface_foo_foo_alpha
+        ));
+        assert!(std::ptr::eq(
+            exports.foo_foo_zeta(),
+            &exports.interface_foo_foo_zeta
+        ));
+        exports.interface_foo_foo_alpha.call_a(&mut store)?;
+        exports.interface_foo_foo_zeta.call_z(&mut store)?;
+        Ok(())
+    }
+}
+
+mod typed_func_getters {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                greet: func(name: string) -> string
+                count: func() -> u32
+            }
+
+            world typed-func-getters {
+                export bar
+                export run: func()
+            }
+        ",
+        typed_func_getters: true,
+    });
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (core module $m
+                        (memory (export "memory") 1)
+                        (func (export "run"))
+                        (func (export "count") (result i32)
+                            i32.const 3)
+                        (func (export "greet") (param i32 i32) (result i32)
+                            unreachable)
+                        (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                            unreachable)
+                    )
+                    (core instance $i (instantiate $m))
+
+                    (func (export "run") (canon lift (core func $i "run")))
+
+                    (func $count (result u32) (canon lift (core func $i "count")))
+                    (func $greet (param "name" string) (result string)
+                        (canon lift (core func $i "greet") (memory $i "memory")
+                            (realloc (func $i "realloc"))))
+                    (instance $bar
+                        (export "greet" (func $greet))
+                        (export "count" (func $count))
+                    )
+                    (export "foo:foo/bar" (instance $bar))
+                )
+            "#,
+        )?;
+
+        let linker = Linker::new(&engine);
+        let mut store = Store::new(&engine, ());
+        let (exports, _) = TypedFuncGetters::instantiate(&mut store, &component, &linker)?;
+
+        // The typed functions can be held onto and called repeatedly.
+        let run = exports.run_typed();
+        for _ in 0..2 {
+            run.call(&mut store, ())?;
+            run.post_return(&mut store)?;
+        }
+        let count = exports.foo_foo_bar().count_typed();
+        assert_eq!(count.call(&mut store, ())?, (3,));
+        count.post_return(&mut store)?;
+
+        let _greet: wasmtime::component::TypedFunc<(&str,), (String,)> =
+            exports.foo_foo_bar().greet_typed();
+        Ok(())
+    }
+}
@@ -0,0 +1,822 @@
+use super::{super::async_engine, engine};
+use anyhow::Result;
+use wasmtime::{
+    component::{Component, Linker},
+    Store,
+};
+
+mod borrow_handles {
+    use super::*;
+    use foo::foo::i;
+    use wasmtime::component::ResourceAny;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface i {
+                resource r
+                type b = borrow<r>
+                take: func(x: own<r>)
+                peek: func(x: borrow<r>, y: b)
+                peek-all: func(xs: list<borrow<r>>, y: option<b>, z: tuple<b, u32>)
+
+                record pair { a: borrow<r>, b: u32 }
+                peek-pair: func(p: pair)
+            }
+
+            world borrow-handles {
+                import i
+            }
+        ",
+    });
+
+    struct MyImports;
+
+    // Borrowed handles are passed by reference when nested in an `option`,
+    // `list` or `tuple`, and other types holding them are passed owned.
+    impl i::Host for MyImports {
+        fn take(&mut self, _x: ResourceAny) -> Result<()> {
+            Ok(())
+        }
+
+        fn peek(&mut self, _x: &ResourceAny, _y: &i::B) -> Result<()> {
+            Ok(())
+        }
+
+        fn peek_all(
+            &mut self,
+            _xs: &[ResourceAny],
+            _y: Option<&i::B>,
+            _z: (&i::B, u32),
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn peek_pair(&mut self, _p: i::Pair) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let mut linker = Linker::new(&engine());
+        i::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        Ok(())
+    }
+}
+
+mod with_aliases_are_shared {
+    use super::*;
+    use foo::foo::api;
+    use interfaces::foo::shared::types::{Point, Size};
+
+    mod interfaces {
+        wasmtime::component::bindgen!({
+            inline: "
+                package foo:shared
+
+                interface types {
+                    record point { x: u32 }
+                    record size { w: u32 }
+                }
+
+                world shared {
+                    import types
+                }
+            ",
+        });
+    }
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface a {
+                record point { x: u32 }
+            }
+
+            interface b {
+                record size { w: u32 }
+            }
+
+            interface api {
+                use a.{point}
+                use b.{size}
+                area: func(p: point, s: size) -> u32
+            }
+
+            world with-aliases-are-shared {
+                import api
+            }
+        ",
+        // Both interfaces are remapped to the same module, which is only
+        // imported once.
+        with: {
+            "foo:foo/a": interfaces::foo::shared::types,
+            "foo:foo/b": interfaces::foo::shared::types,
+        },
+    });
+
+    struct MyImports;
+
+    impl api::Host for MyImports {
+        fn area(&mut self, p: Point, s: Size) -> Result<u32> {
+            Ok(p.x * s.w)
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let mut linker = Linker::new(&engine());
+        api::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+
+        let area = api::Host::area(&mut MyImports, Point { x: 2 }, api::Size { w: 3 })?;
+        assert_eq!(area, 6);
+        Ok(())
+    }
+}
+
+mod module_path_style {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package my:pkg
+
+            interface types {
+                record r { a: u32 }
+            }
+
+            interface api {
+                use types.{r}
+                f: func(x: r)
+            }
+
+            world module-path-style {
+                import api
+            }
+        ",
+        module_path_style: Flat,
+    });
+
+    struct MyImports;
+
+    impl types::Host for MyImports {}
+
+    // Interfaces are generated at the root, without their package's modules.
+    impl api::Host for MyImports {
+        fn f(&mut self, _x: types::R) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let mut linker = Linker::new(&engine());
+        ModulePathStyle::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let _: api::R = types::R { a: 1 };
+        Ok(())
+    }
+}
+
+mod tracing_redact {
+    use super::*;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface auth {
+                login: func(user: string, password: string)
+            }
+
+            world tracing-redact {
+                import auth
+                import login: func(user: string, password: string)
+                export run: func()
+            }
+        ",
+        tracing: true,
+        // Only the world's function is redacted, not the interface's one of
+        // the same name.
+        tracing_redact: [("login", "password")],
+    });
+
+    /// Records the fields of all events as `name=value`.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Capture {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            let entry = format!("{}={value:?}", field.name());
+            self.0.lock().unwrap().push(entry);
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut self.clone());
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    struct MyImports;
+
+    impl TracingRedactImports for MyImports {
+        fn login(&mut self, _user: String, _password: String) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl foo::foo::auth::Host for MyImports {
+        fn login(&mut self, _user: String, _password: String) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import "foo:foo/auth" (instance $auth
+                        (export "login" (func (param "user" string) (param "password" string)))
+                    ))
+                    (import "login" (func $login
+                        (param "user" string)
+                        (param "password" string)
+                    ))
+                    (core module $libc
+                        (memory (export "memory") 1)
+                        (data (i32.const 0) "alicehunter2bobswordfish")
+                    )
+                    (core instance $libc (instantiate $libc))
+                    (core func $login (canon lower (func $login) (memory $libc "memory")))
+                    (core func $auth_login
+                        (canon lower (func $auth "login") (memory $libc "memory")))
+                    (core module $m
+                        (import "" "login" (func $login (param i32 i32 i32 i32)))
+                        (import "" "auth-login" (func $auth_login (param i32 i32 i32 i32)))
+                        (func (export "run")
+                            (call $login (i32.const 0) (i32.const 5) (i32.const 5) (i32.const 7))
+                            (call $auth_login
+                                (i32.const 12) (i32.const 3) (i32.const 15) (i32.const 9)))
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance
+                            (export "login" (func $login))
+                            (export "auth-login" (func $auth_login))
+                        ))
+                    ))
+
+                    (func (export "run") (canon lift (core func $i "run")))
+                )
+            "#,
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        TracingRedact::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports);
+        let (exports, _) = TracingRedact::instantiate(&mut store, &component, &linker)?;
+
+        let capture = Capture::default();
+        tracing::subscriber::with_default(capture.clone(), || exports.call_run(&mut store))?;
+
+        let fields = capture.0.lock().unwrap();
+        assert!(fields.contains(&"user=\"alice\"".to_string()));
+        assert!(fields.contains(&"password=\"<redacted>\"".to_string()));
+        assert!(!fields.iter().any(|field| field.contains("hunter2")));
+        assert!(fields.contains(&"password=\"swordfish\"".to_string()));
+        Ok(())
+    }
+}
+
+mod read_only_imports {
+    use super::*;
+    use foo::foo::i;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface i {
+                get-size: func() -> u64
+                set-size: func(size: u64)
+            }
+
+            world read-only-imports {
+                import i
+                export run: func()
+            }
+        ",
+        read_only_imports: ["foo:foo/i#get-size"],
+    });
+
+    struct MyImports {
+        size: u64,
+    }
+
+    impl i::Host for MyImports {
+        fn get_size(&self) -> Result<u64> {
+            Ok(self.size)
+        }
+
+        fn set_size(&mut self, size: u64) -> Result<()> {
+            self.size = size;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import "foo:foo/i" (instance $i
+                        (export "get-size" (func (result u64)))
+                        (export "set-size" (func (param "size" u64)))
+                    ))
+                    (core func $get (canon lower (func $i "get-size")))
+                    (core func $set (canon lower (func $i "set-size")))
+                    (core module $m
+                        (import "" "get" (func $get (result i64)))
+                        (import "" "set" (func $set (param i64)))
+                        (func (export "run")
+                            (call $set (i64.add (call $get) (i64.const 1))))
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance
+                            (export "get" (func $get))
+                            (export "set" (func $set))
+                        ))
+                    ))
+
+                    (func (export "run") (canon lift (core func $i "run")))
+                )
+            "#,
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        ReadOnlyImports::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports { size: 1 });
+        let (exports, _) = ReadOnlyImports::instantiate(&mut store, &component, &linker)?;
+        exports.call_run(&mut store)?;
+        assert_eq!(store.data().size, 2);
+        Ok(())
+    }
+}
+
+mod import_vtable {
+    use super::*;
+    use foo::foo::i::{self, HostVtable};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface i {
+                f: func(x: u32, y: string) -> u64
+                g: func()
+            }
+
+            world import-vtable {
+                import i
+            }
+        ",
+        import_vtable: true,
+    });
+
+    #[derive(Default)]
+    struct MyImports {
+        calls: u32,
+    }
+
+    impl i::Host for MyImports {
+        fn f(&mut self, x: u32, y: String) -> Result<u64> {
+            self.calls += 1;
+            Ok(u64::from(x) + y.len() as u64)
+        }
+
+        fn g(&mut self) -> Result<()> {
+            self.calls += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let vtable = HostVtable::new(|f: &mut MyImports| f);
+        let mut imports = MyImports::default();
+        assert_eq!((vtable.f)(&mut imports, (2, "abc".to_string()))?, (5,));
+        (vtable.g)(&mut imports, ())?;
+        assert_eq!(imports.calls, 2);
+
+        // Errors of `get` are returned by the closures.
+        fn no_host(_: &mut MyImports) -> Result<&mut MyImports> {
+            anyhow::bail!("no host")
+        }
+        let vtable = HostVtable::new_get_result(no_host);
+        let err = (vtable.g)(&mut imports, ()).unwrap_err();
+        assert_eq!(err.to_string(), "no host");
+        assert_eq!(imports.calls, 2);
+        Ok(())
+    }
+}
+
+mod borrowed_import_params {
+    use super::*;
+    use foo::foo::bar::{self, Point};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                record point {
+                    x: u32,
+                }
+
+                f: func(s: string, xs: list<u32>, ps: list<point>, ls: list<string>, n: u32)
+            }
+
+            world borrowed-import-params {
+                import bar
+                import g: func(s: string)
+                export run: func()
+            }
+        ",
+        ownership: Borrowing {
+            duplicate_if_necessary: false
+        },
+        borrowed_import_params: true,
+    });
+
+    #[derive(Default)]
+    struct MyImports {
+        seen: Vec<String>,
+    }
+
+    // Strings and lists of plain data are borrowed from the lifted values,
+    // other lists are still passed owned.
+    impl bar::Host for MyImports {
+        fn f(
+            &mut self,
+            s: &str,
+            _xs: &[u32],
+            _ps: &[Point],
+            _ls: Vec<String>,
+            _n: u32,
+        ) -> Result<()> {
+            self.seen.push(s.to_string());
+            Ok(())
+        }
+    }
+
+    impl BorrowedImportParamsImports for MyImports {
+        fn g(&mut self, s: &str) -> Result<()> {
+            self.seen.push(s.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import "g" (func $g (param "s" string)))
+                    (core module $libc
+                        (memory (export "memory") 1)
+                        (data (i32.const 0) "hello")
+                    )
+                    (core instance $libc (instantiate $libc))
+                    (core func $g (canon lower (func $g) (memory $libc "memory")))
+                    (core module $m
+                        (import "" "g" (func $g (param i32 i32)))
+                        (func (export "run")
+                            (call $g (i32.const 0) (i32.const 5)))
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance (export "g" (func $g))))
+                    ))
+
+                    (func (export "run") (canon lift (core func $i "run")))
+                )
+            "#,
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        BorrowedImportParams::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports::default());
+        let (exports, _) = BorrowedImportParams::instantiate(&mut store, &component, &linker)?;
+        exports.call_run(&mut store)?;
+        assert_eq!(store.data().seen, ["hello"]);
+        Ok(())
+    }
+}
+
+mod async_interfaces {
+    use super::*;
+    use foo::foo::{fs, log};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface fs {
+                read: func() -> u32
+            }
+
+            interface log {
+                write: func(n: u32)
+            }
+
+            world async-interfaces {
+                import fs
+                import log
+                export run: func()
+            }
+        ",
+        async_interfaces: ["foo:foo/fs"],
+        export_async: true,
+    });
+
+    #[derive(Default)]
+    struct MyImports {
+        written: Vec<u32>,
+    }
+
+    #[async_trait::async_trait]
+    impl fs::Host for MyImports {
+        async fn read(&mut self) -> Result<u32> {
+            tokio::task::yield_now().await;
+            Ok(3)
+        }
+    }
+
+    // Interfaces which aren't listed stay synchronous.
+    impl log::Host for MyImports {
+        fn write(&mut self, n: u32) -> Result<()> {
+            self.written.push(n);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn run() -> Result<()> {
+        let engine = async_engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import "foo:foo/fs" (instance $fs
+                        (export "read" (func (result u32)))
+                    ))
+                    (import "foo:foo/log" (instance $log
+                        (export "write" (func (param "n" u32)))
+                    ))
+                    (core func $read (canon lower (func $fs "read")))
+                    (core func $write (canon lower (func $log "write")))
+                    (core module $m
+                        (import "" "read" (func $read (result i32)))
+                        (import "" "write" (func $write (param i32)))
+                        (func (export "run")
+                            (call $write (call $read)))
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance
+                            (export "read" (func $read))
+                            (export "write" (func $write))
+                        ))
+                    ))
+
+                    (func (export "run") (canon lift (core func $i "run")))
+                )
+            "#,
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        AsyncInterfaces::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports::default());
+        let (exports, _) =
+            AsyncInterfaces::instantiate_async(&mut store, &component, &linker).await?;
+        exports.call_run(&mut store).await?;
+        assert_eq!(store.data().written, [3]);
+        Ok(())
+    }
+}
+
+mod require_resources {
+    use super::*;
+    use foo::foo::fs;
+    use wasmtime::component::Resource;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface fs {
+                resource file
+                open: func() -> file
+            }
+
+            world require-resources {
+                import fs
+            }
+        ",
+        // Every imported resource is mapped, so the bindings compile.
+        resources: {
+            "file": super::super::super::MyFile,
+        },
+        require_resources: true,
+    });
+
+    pub struct MyFile;
+
+    #[derive(Default)]
+    struct MyImports {
+        opened: u32,
+        dropped: Vec<u32>,
+    }
+
+    impl fs::HostFile for MyImports {
+        fn drop(&mut self, rep: Resource<MyFile>) -> Result<()> {
+            self.dropped.push(rep.rep());
+            Ok(())
+        }
+    }
+
+    impl fs::Host for MyImports {
+        fn open(&mut self) -> Result<Resource<MyFile>> {
+            self.opened += 1;
+            Ok(Resource::new_own(self.opened))
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let mut linker = Linker::new(&engine());
+        fs::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+
+        let mut imports = MyImports::default();
+        let file = fs::Host::open(&mut imports)?;
+        fs::HostFile::drop(&mut imports, file)?;
+        assert_eq!(imports.dropped, [1]);
+        Ok(())
+    }
+}
+
+mod add_to_linker_table {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                f: func()
+            }
+
+            world add-to-linker-table {
+                import bar
+                import baz: interface {
+                    g: func()
+                }
+                import h: func()
+            }
+        ",
+    });
+
+    struct MyImports;
+
+    impl foo::foo::bar::Host for MyImports {
+        fn f(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl baz::Host for MyImports {
+        fn g(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl AddToLinkerTableImports for MyImports {
+        fn h(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import "foo:foo/bar" (instance (export "f" (func))))
+                    (import "baz" (instance (export "g" (func))))
+                    (import "h" (func))
+                )
+            "#,
+        )?;
+
+        let table = AddToLinkerTable::add_to_linker_table::<MyImports, MyImports>();
+        let names = table.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, ["foo:foo/bar", "baz", "h"]);
+
+        // Each entry adds its import, which together satisfy the component.
+        let mut linker = Linker::new(&engine);
+        for (_, add) in table {
+            add(&mut linker, |f| Ok(f))?;
+        }
+        let mut store = Store::new(&engine, MyImports);
+        AddToLinkerTable::instantiate(&mut store, &component, &linker)?;
+        Ok(())
+    }
+}
+
+mod add_to_linker_without_functions {
+    use super::*;
+    use foo::foo::{funcs, types};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface types {
+                record point {
+                    x: u32,
+                }
+            }
+
+            interface funcs {
+                use types.{point}
+                f: func(p: point)
+            }
+
+            world add-to-linker-without-functions {
+                import types
+                import funcs
+            }
+        ",
+    });
+
+    struct MyImports;
+
+    impl types::Host for MyImports {}
+
+    impl funcs::Host for MyImports {
+        fn f(&mut self, _p: types::Point) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        // The interface without functions is still defined in the linker,
+        // so components importing it can be instantiated.
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import "foo:foo/types" (instance))
+                )
+            "#,
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        AddToLinkerWithoutFunctions::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports);
+        linker.instantiate(&mut store, &component)?;
+        Ok(())
+    }
+}
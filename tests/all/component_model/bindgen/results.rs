@@ -379,6 +379,168 @@ mod enum_error {
 
         Ok(())
     }
+
+    #[test]
+    fn into_anyhow() {
+        fn fails() -> Result<(), Error> {
+            Err(foo::E1::B)?;
+            Ok(())
+        }
+        let e = fails().unwrap_err();
+        assert_eq!(e.downcast_ref::<foo::E1>(), Some(&foo::E1::B));
+    }
+
+    #[test]
+    fn source_is_wit_error() {
+        use std::error::Error as _;
+
+        let e = imports::TrappableE1::from(imports::E1::B);
+        let source = e.source().expect("wit error is the source");
+        assert_eq!(source.downcast_ref::<imports::E1>(), Some(&imports::E1::B));
+
+        let e = e.context("calling enum-error");
+        let source = e.source().expect("wit error is the source");
+        assert_eq!(source.downcast_ref::<imports::E1>(), Some(&imports::E1::B));
+
+        let e = imports::TrappableE1::trap(anyhow!("trap"));
+        assert!(e.source().is_none());
+    }
+}
+
+mod multi_result_enum_error {
+    use super::*;
+    use exports::foo;
+    use inline::inline::imports;
+
+    wasmtime::component::bindgen!({
+        inline: "
+        package inline:inline
+        interface imports {
+            enum e1 { a, b, c }
+            enum-error: func(a: float64) -> (r: result<float64, e1>, n: u32)
+        }
+        world result-playground {
+            import imports
+            export foo: interface {
+                enum e1 { a, b, c }
+                enum-error: func(a: float64) -> (r: result<float64, e1>, n: u32)
+            }
+        }",
+        trappable_error_type: { "inline:inline/imports"::e1: TrappableE1 }
+    });
+
+    #[test]
+    fn run() -> Result<(), Error> {
+        let engine = engine();
+        let component = Component::new(
+            &engine,
+            format!(
+                r#"
+            (component
+                (type $err' (enum "a" "b" "c"))
+                (import (interface "inline:inline/imports") (instance $i
+                    (export $err "err" (type (eq $err')))
+                    (export "enum-error" (func (param "a" float64) (result "r" (result float64 (error $err))) (result "n" u32)))
+                ))
+                (core module $libc
+                    (memory (export "memory") 1)
+                    {REALLOC_AND_FREE}
+                )
+                (core instance $libc (instantiate $libc))
+                (core module $m
+                    (import "" "core_enum_error" (func $f (param f64 i32)))
+                    (import "libc" "memory" (memory 0))
+                    (import "libc" "realloc" (func $realloc (param i32 i32 i32 i32) (result i32)))
+                    (func (export "core_enum_error_export") (param f64) (result i32)
+                        (local $retptr i32)
+                        (local.set $retptr
+                            (call $realloc
+                                (i32.const 0)
+                                (i32.const 0)
+                                (i32.const 8)
+                                (i32.const 24)))
+                        (call $f (local.get 0) (local.get $retptr))
+                        (local.get $retptr)
+                    )
+                )
+                (core func $core_enum_error
+                    (canon lower (func $i "enum-error") (memory $libc "memory") (realloc (func $libc "realloc")))
+                )
+                (core instance $i (instantiate $m
+                    (with "" (instance (export "core_enum_error" (func $core_enum_error))))
+                    (with "libc" (instance $libc))
+                ))
+                (func $f_enum_error
+                    (param "a" float64)
+                    (result "r" (result float64 (error $err')))
+                    (result "n" u32)
+                    (canon lift (core func $i "core_enum_error_export") (memory $libc "memory"))
+                )
+
+                (component $nested
+                    (import "f-err" (type $err (eq $err')))
+                    (import "f" (func $f (param "a" float64) (result "r" (result float64 (error $err))) (result "n" u32)))
+                    (export $err2 "err" (type $err'))
+                    (export "enum-error" (func $f) (func (param "a" float64) (result "r" (result float64 (error $err2))) (result "n" u32)))
+                )
+
+                (instance $n (instantiate $nested
+                    (with "f-err" (type $err'))
+                    (with "f" (func $f_enum_error))
+                ))
+                (export "foo" (instance $n))
+            )
+        "#
+            ),
+        )?;
+
+        #[derive(Default)]
+        struct MyImports {}
+
+        impl imports::Host for MyImports {
+            fn enum_error(&mut self, a: f64) -> (Result<f64, imports::TrappableE1>, u32) {
+                if a == 0.0 {
+                    (Ok(a), 1)
+                } else if a == 1.0 {
+                    (Err(imports::E1::A.into()), 2)
+                } else {
+                    (Err(imports::TrappableE1::trap(anyhow!("enum_error: trap"))), 3)
+                }
+            }
+        }
+
+        let mut linker = Linker::new(&engine);
+        imports::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+
+        let mut store = Store::new(&engine, MyImports::default());
+        let (results, _) = ResultPlayground::instantiate(&mut store, &component, &linker)?;
+
+        let (r, n) = results
+            .foo()
+            .call_enum_error(&mut store, 0.0)
+            .expect("no trap");
+        assert_eq!(r.expect("no error returned"), 0.0);
+        assert_eq!(n, 1);
+
+        let (r, n) = results
+            .foo()
+            .call_enum_error(&mut store, 1.0)
+            .expect("no trap");
+        assert_eq!(r.err().expect("error returned"), foo::E1::A);
+        assert_eq!(n, 2);
+
+        let e = results
+            .foo()
+            .call_enum_error(&mut store, 2.0)
+            .err()
+            .expect("trap");
+        assert_eq!(
+            format!("{}", e.source().expect("trap message is stored in source")),
+            "enum_error: trap"
+        );
+
+        Ok(())
+    }
 }
 
 mod record_error {
@@ -1004,3 +1166,142 @@ mod with_remapping {
         Ok(())
     }
 }
+
+mod world_error {
+    use super::*;
+
+    wasmtime::component::bindgen!({
+        inline: "
+        package inline:inline
+        world result-playground {
+            enum e1 { a, b, c }
+            import enum-error: func(a: float64) -> result<float64, e1>
+            export run: func(a: float64) -> result<float64, e1>
+        }",
+        trappable_error_type: { "inline:inline/result-playground"::e1: TrappableE1 }
+    });
+
+    #[test]
+    fn run() -> Result<(), Error> {
+        let engine = engine();
+        let component = Component::new(
+            &engine,
+            format!(
+                r#"
+            (component
+                (type $err' (enum "a" "b" "c"))
+                (import "e1" (type $err (eq $err')))
+                (import "enum-error" (func $i_enum_error (param "a" float64) (result (result float64 (error $err)))))
+                (core module $libc
+                    (memory (export "memory") 1)
+                    {REALLOC_AND_FREE}
+                )
+                (core instance $libc (instantiate $libc))
+                (core module $m
+                    (import "" "core_enum_error" (func $f (param f64 i32)))
+                    (import "libc" "memory" (memory 0))
+                    (import "libc" "realloc" (func $realloc (param i32 i32 i32 i32) (result i32)))
+                    (func (export "core_enum_error_export") (param f64) (result i32)
+                        (local $retptr i32)
+                        (local.set $retptr
+                            (call $realloc
+                                (i32.const 0)
+                                (i32.const 0)
+                                (i32.const 4)
+                                (i32.const 16)))
+                        (call $f (local.get 0) (local.get $retptr))
+                        (local.get $retptr)
+                    )
+                )
+                (core func $core_enum_error
+                    (canon lower (func $i_enum_error) (memory $libc "memory") (realloc (func $libc "realloc")))
+                )
+                (core instance $i (instantiate $m
+                    (with "" (instance (export "core_enum_error" (func $core_enum_error))))
+                    (with "libc" (instance $libc))
+                ))
+                (func (export "run")
+                    (param "a" float64)
+                    (result (result float64 (error $err)))
+                    (canon lift (core func $i "core_enum_error_export") (memory $libc "memory"))
+                )
+            )
+        "#
+            ),
+        )?;
+
+        #[derive(Default)]
+        struct MyImports {}
+
+        impl ResultPlaygroundImports for MyImports {
+            fn enum_error(&mut self, a: f64) -> Result<f64, TrappableE1> {
+                if a == 0.0 {
+                    Ok(a)
+                } else if a == 1.0 {
+                    Err(E1::A)?
+                } else {
+                    Err(TrappableE1::trap(anyhow!("enum_error: trap")))
+                }
+            }
+        }
+
+        let mut linker = Linker::new(&engine);
+        ResultPlayground::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+
+        let mut store = Store::new(&engine, MyImports::default());
+        let (results, _) = ResultPlayground::instantiate(&mut store, &component, &linker)?;
+
+        assert_eq!(
+            results
+                .call_run(&mut store, 0.0)
+                .expect("no trap")
+                .expect("no error returned"),
+            0.0
+        );
+
+        let e = results
+            .call_run(&mut store, 1.0)
+            .expect("no trap")
+            .err()
+            .expect("error returned");
+        assert_eq!(e, E1::A);
+
+        let e = results.call_run(&mut store, 2.0).err().expect("trap");
+        assert_eq!(
+            format!("{}", e.source().expect("trap message is stored in source")),
+            "enum_error: trap"
+        );
+
+        Ok(())
+    }
+}
+
+mod record_error_message {
+    use inline::inline::imports;
+
+    wasmtime::component::bindgen!({
+        inline: "
+        package inline:inline
+        interface imports {
+            record e1 { code: u32, message: string }
+            record e2 { line: u32, col: u32 }
+            f: func() -> result<u32, e1>
+            g: func() -> result<u32, e2>
+        }
+        world result-playground {
+            import imports
+        }",
+    });
+
+    #[test]
+    fn run() {
+        let e1 = imports::E1 {
+            code: 2,
+            message: "file not found".to_string(),
+        };
+        assert_eq!(e1.to_string(), "file not found");
+
+        let e2 = imports::E2 { line: 1, col: 2 };
+        assert_eq!(e2.to_string(), "E2 { line: 1, col: 2 }");
+    }
+}
@@ -0,0 +1,813 @@
+use super::{super::REALLOC_AND_FREE, engine};
+use anyhow::Result;
+use wasmtime::{
+    component::{Component, Linker},
+    Store,
+};
+
+mod derive_default {
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world derive-default {
+                import foo: interface {
+                    flags perms { read, write }
+                    record inner { a: u32, b: option<string> }
+                    record outer { inner: inner, list: list<u8>, perms: perms }
+
+                    foo: func(o: outer)
+                }
+            }
+        ",
+        derive_default: true,
+        // `Default` isn't derived twice when also requested explicitly.
+        additional_derives: [Default],
+    });
+
+    #[test]
+    fn run() {
+        let outer = foo::Outer::default();
+        assert_eq!(outer.inner.a, 0);
+        assert_eq!(outer.inner.b, None);
+        assert!(outer.list.is_empty());
+        assert_eq!(outer.perms, foo::Perms::empty());
+    }
+}
+
+mod two_names {
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world two-names {
+                record thing { name: string, data: list<u8>, tag: option<string> }
+                variant shape { named(thing), empty }
+                record names { names: list<string>, things: list<thing> }
+
+                export f: func(t: thing, s: shape, n: names) -> tuple<thing, shape, names>
+            }
+        ",
+        ownership: Borrowing {
+            duplicate_if_necessary: true
+        },
+    });
+
+    #[test]
+    fn run() {
+        let owned = ThingResult {
+            name: "name".to_string(),
+            data: vec![1, 2],
+            tag: Some("tag".to_string()),
+        };
+        let borrowed = ThingParam::from(&owned);
+        assert_eq!(borrowed.name, "name");
+        assert_eq!(borrowed.data, [1, 2]);
+        assert_eq!(borrowed.tag, Some("tag"));
+
+        let thing = borrowed.into_owned();
+        assert_eq!(thing.name, owned.name);
+        assert_eq!(thing.data, owned.data);
+        assert_eq!(thing.tag, owned.tag);
+
+        let shape = ShapeResult::Named(owned);
+        match ShapeParam::from(&shape) {
+            ShapeParam::Named(thing) => assert_eq!(thing.name, "name"),
+            ShapeParam::Empty => unreachable!(),
+        }
+        assert!(matches!(ShapeParam::Empty.into_owned(), ShapeResult::Empty));
+
+        // Lists of strings can only be converted to owned lists.
+        let names = NamesParam {
+            names: &["a", "b"],
+            things: &[ThingParam::from(&thing)],
+        }
+        .into_owned();
+        assert_eq!(names.names, ["a", "b"]);
+        assert_eq!(names.things[0].data, [1, 2]);
+    }
+}
+
+mod boxed_slices {
+    use super::*;
+    use foo::foo::lists::{self, Thing};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface lists {
+                record thing { data: list<u8> }
+
+                lens: func(t: thing, names: list<string>) -> list<u32>
+            }
+
+            world boxed-slices {
+                import lists
+                export run: func(data: list<u8>, names: list<string>) -> list<u32>
+            }
+        ",
+        list_representation: BoxedSlice,
+    });
+
+    struct MyImports;
+
+    impl lists::Host for MyImports {
+        fn lens(&mut self, t: Thing, names: Box<[String]>) -> Result<Box<[u32]>> {
+            let lens = names.iter().map(|name| name.len() as u32);
+            Ok(std::iter::once(t.data.len() as u32).chain(lens).collect())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        // `run` passes its arguments on to `lens` as the `thing` record,
+        // which has the same flat representation.
+        let component = Component::new(
+            &engine,
+            format!(
+                r#"
+                (component
+                    (type $thing' (record (field "data" (list u8))))
+                    (import "foo:foo/lists" (instance $lists
+                        (export $thing "thing" (type (eq $thing')))
+                        (export "lens" (func
+                            (param "t" $thing)
+                            (param "names" (list string))
+                            (result (list u32))
+                        ))
+                    ))
+                    (core module $libc
+                        (memory (export "memory") 1)
+                        {REALLOC_AND_FREE}
+                    )
+                    (core instance $libc (instantiate $libc))
+                    (core func $lens
+                        (canon lower (func $lists "lens") (memory $libc "memory")
+                            (realloc (func $libc "realloc")))
+                    )
+                    (core module $m
+                        (import "" "lens" (func $lens (param i32 i32 i32 i32 i32)))
+                        (import "libc" "realloc" (func $realloc (param i32 i32 i32 i32) (result i32)))
+                        (func (export "run") (param i32 i32 i32 i32) (result i32)
+                            (local $retptr i32)
+                            (local.set $retptr
+                                (call $realloc
+                                    (i32.const 0)
+                                    (i32.const 0)
+                                    (i32.const 4)
+                                    (i32.const 8)))
+                            (call $lens
+                                (local.get 0)
+                                (local.get 1)
+                                (local.get 2)
+                                (local.get 3)
+                                (local.get $retptr))
+                            (local.get $retptr)
+                        )
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance (export "lens" (func $lens))))
+                        (with "libc" (instance $libc))
+                    ))
+
+                    (func (export "run")
+                        (param "data" (list u8))
+                        (param "names" (list string))
+                        (result (list u32))
+                        (canon lift (core func $i "run") (memory $libc "memory")
+                            (realloc (func $libc "realloc")))
+                    )
+                )
+            "#
+            ),
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        BoxedSlices::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports);
+        let (exports, _) = BoxedSlices::instantiate(&mut store, &component, &linker)?;
+
+        let names = ["a".to_string(), "bc".to_string()];
+        let lens: Box<[u32]> = exports.call_run(&mut store, &[1, 2, 3], &names)?;
+        assert_eq!(&lens[..], [3, 1, 2]);
+        Ok(())
+    }
+}
+
+mod raw_identifiers {
+    use super::*;
+    use foo::foo::bar;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                record r {
+                    %type: u32,
+                }
+
+                %type: func(%self: r) -> u32
+            }
+
+            world raw-identifiers {
+                import bar
+                export run: func(x: u32) -> u32
+            }
+        ",
+        raw_identifiers: true,
+    });
+
+    struct MyImports;
+
+    impl bar::Host for MyImports {
+        fn r#type(&mut self, self_: bar::R) -> Result<u32> {
+            Ok(self_.r#type * 2)
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        // `run` passes its argument on to `type` as the `r` record.
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (type $r' (record (field "type" u32)))
+                    (import "foo:foo/bar" (instance $bar
+                        (export $r "r" (type (eq $r')))
+                        (export "type" (func (param "self" $r) (result u32)))
+                    ))
+                    (core func $type (canon lower (func $bar "type")))
+                    (core module $m
+                        (import "" "type" (func $type (param i32) (result i32)))
+                        (func (export "run") (param i32) (result i32)
+                            (call $type (local.get 0)))
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance (export "type" (func $type))))
+                    ))
+
+                    (func (export "run") (param "x" u32) (result u32)
+                        (canon lift (core func $i "run")))
+                )
+            "#,
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        RawIdentifiers::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports);
+        let (exports, _) = RawIdentifiers::instantiate(&mut store, &component, &linker)?;
+        assert_eq!(exports.call_run(&mut store, 3)?, 6);
+        Ok(())
+    }
+}
+
+mod enum_conversions {
+    use super::*;
+    use foo::foo::bar::Color;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                enum color { light-blue, red }
+
+                paint: func(c: color)
+            }
+
+            world enum-conversions {
+                import bar
+            }
+        ",
+        enum_string_conversions: true,
+        enum_discriminant_consts: true,
+    });
+
+    #[test]
+    fn strings() -> Result<()> {
+        assert_eq!(Color::LightBlue.as_ref(), "light-blue");
+        assert_eq!(Color::Red.as_ref(), "red");
+        assert_eq!("light-blue".parse::<Color>()?, Color::LightBlue);
+
+        let err = "blue".parse::<Color>().unwrap_err();
+        assert_eq!(err.to_string(), "unknown `Color` case `blue`");
+        Ok(())
+    }
+
+    #[test]
+    fn discriminants() -> Result<()> {
+        assert_eq!(Color::LIGHT_BLUE_VALUE, 0);
+        assert_eq!(Color::RED_VALUE, 1);
+        assert_eq!(Color::try_from(Color::RED_VALUE)?, Color::Red);
+        assert_eq!(Color::LightBlue as u32, Color::LIGHT_BLUE_VALUE);
+        Ok(())
+    }
+}
+
+mod type_with {
+    use super::*;
+    use foo::foo::bar;
+    use wasmtime::component::{ComponentType, Lift, Lower};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                record timestamp {
+                    seconds: u64,
+                }
+
+                record event {
+                    at: timestamp,
+                }
+
+                emit: func(e: event) -> timestamp
+            }
+
+            world type-with {
+                import bar
+                export run: func(seconds: u64) -> u64
+            }
+        ",
+        type_with: {
+            "foo:foo/bar/timestamp": super::super::super::Timestamp,
+        },
+    });
+
+    #[derive(ComponentType, Lift, Lower, Clone, Copy, Debug, PartialEq)]
+    #[component(record)]
+    pub struct Timestamp {
+        seconds: u64,
+    }
+
+    #[derive(Default)]
+    struct MyImports {
+        events: Vec<Timestamp>,
+    }
+
+    // The WIT type is the Rust type it's mapped to, wherever it's used.
+    impl bar::Host for MyImports {
+        fn emit(&mut self, e: bar::Event) -> Result<Timestamp> {
+            self.events.push(e.at);
+            Ok(Timestamp {
+                seconds: e.at.seconds + 1,
+            })
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        // `run` passes its argument on to `emit` as the `event` record, and
+        // returns the `timestamp` it gets back.
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (type $timestamp' (record (field "seconds" u64)))
+                    (import "foo:foo/bar" (instance $bar
+                        (export $timestamp "timestamp" (type (eq $timestamp')))
+                        (type $event' (record (field "at" $timestamp)))
+                        (export $event "event" (type (eq $event')))
+                        (export "emit" (func (param "e" $event) (result $timestamp)))
+                    ))
+                    (core func $emit (canon lower (func $bar "emit")))
+                    (core module $m
+                        (import "" "emit" (func $emit (param i64) (result i64)))
+                        (func (export "run") (param i64) (result i64)
+                            (call $emit (local.get 0)))
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance (export "emit" (func $emit))))
+                    ))
+
+                    (func (export "run") (param "seconds" u64) (result u64)
+                        (canon lift (core func $i "run")))
+                )
+            "#,
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        TypeWith::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports::default());
+        let (exports, _) = TypeWith::instantiate(&mut store, &component, &linker)?;
+        assert_eq!(exports.call_run(&mut store, 5)?, 6);
+        assert_eq!(store.data().events, [Timestamp { seconds: 5 }]);
+        Ok(())
+    }
+}
+
+mod trappable_error_direction {
+    use super::*;
+    use exports::foo::foo::guest::Guest;
+    use foo::foo::types::{Error, GuestError, HostError};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface types {
+                variant error {
+                    bad,
+                }
+            }
+
+            interface host {
+                use types.{error}
+                f: func() -> result<u32, error>
+            }
+
+            interface guest {
+                use types.{error}
+                g: func() -> result<u32, error>
+            }
+
+            world trappable-error-direction {
+                import host
+                export guest
+            }
+        ",
+        trappable_error_type: {
+            "foo:foo/types"::error: HostError,
+            export "foo:foo/types"::error: GuestError,
+        },
+    });
+
+    struct MyImports;
+
+    impl foo::foo::types::Host for MyImports {}
+
+    impl foo::foo::host::Host for MyImports {
+        fn f(&mut self) -> Result<u32, HostError> {
+            Err(Error::Bad)?
+        }
+    }
+
+    // Errors of exports are converted to their own trappable type.
+    #[allow(dead_code)]
+    fn call_g(guest: &Guest, store: &mut Store<MyImports>) -> Result<Result<u32, GuestError>> {
+        guest.call_g(store)
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let mut linker = Linker::new(&engine());
+        TrappableErrorDirection::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+
+        let err = foo::foo::host::Host::f(&mut MyImports).unwrap_err();
+        assert!(matches!(err.downcast()?, Error::Bad));
+
+        let err = GuestError::from(Error::Bad);
+        assert!(matches!(err.downcast_ref(), Some(Error::Bad)));
+        Ok(())
+    }
+}
+
+mod no_clone_with_owned_handles {
+    use super::*;
+    use foo::foo::i;
+    use wasmtime::component::Resource;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface i {
+                resource r
+                record holder {
+                    x: own<r>,
+                }
+                variant maybe {
+                    some(holder),
+                    none,
+                }
+                f: func(x: holder, y: maybe)
+            }
+
+            world no-clone-with-owned-handles {
+                import i
+            }
+        ",
+        // `Resource<MyR>` isn't `Clone`, so the types holding it must not
+        // derive it for the bindings to compile.
+        resources: {
+            "r": super::super::super::MyR,
+        },
+    });
+
+    pub struct MyR;
+
+    #[derive(Default)]
+    struct MyImports {
+        dropped: Vec<u32>,
+    }
+
+    impl i::HostR for MyImports {
+        fn drop(&mut self, rep: Resource<MyR>) -> Result<()> {
+            self.dropped.push(rep.rep());
+            Ok(())
+        }
+    }
+
+    impl i::Host for MyImports {
+        fn f(&mut self, x: i::Holder, y: i::Maybe) -> Result<()> {
+            self.dropped.push(x.x.rep());
+            if let i::Maybe::Some(y) = y {
+                self.dropped.push(y.x.rep());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let mut linker = Linker::new(&engine());
+        i::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+
+        let mut imports = MyImports::default();
+        let holder = |rep| i::Holder {
+            x: Resource::new_own(rep),
+        };
+        i::Host::f(&mut imports, holder(1), i::Maybe::Some(holder(2)))?;
+        assert_eq!(imports.dropped, [1, 2]);
+        Ok(())
+    }
+}
+
+mod derive_eq {
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world derive-eq {
+                import foo: interface {
+                    record ints { a: u32, b: list<s64> }
+                    record floats { a: u32, b: option<float64> }
+                    variant v { a(ints), b(floats) }
+
+                    foo: func(a: ints, b: floats, c: v)
+                }
+            }
+        ",
+        derive_eq: true,
+    });
+
+    fn assert_eq_trait<T: Eq>() {}
+
+    #[test]
+    fn run() {
+        assert_eq_trait::<foo::Ints>();
+
+        let ints = foo::Ints { a: 1, b: vec![2] };
+        assert_eq!(ints, ints.clone());
+        assert_ne!(ints, foo::Ints { a: 1, b: vec![] });
+
+        let floats = foo::Floats { a: 1, b: Some(0.5) };
+        assert_eq!(foo::V::B(floats.clone()), foo::V::B(floats.clone()));
+        assert_ne!(foo::V::A(ints), foo::V::B(floats));
+    }
+}
+
+mod list_field_iters {
+    use foo::foo::bar;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                type names = list<string>
+
+                record shelf {
+                    items: names,
+                    ids: list<u32>,
+                    label: string,
+                }
+
+                stock: func(s: shelf)
+            }
+
+            world list-field-iters {
+                import bar
+            }
+        ",
+        list_field_iters: ["foo:foo/bar/shelf"],
+    });
+
+    #[test]
+    fn run() {
+        let shelf = bar::Shelf {
+            items: vec!["a".to_string(), "b".to_string()],
+            ids: vec![1, 2],
+            label: "label".to_string(),
+        };
+        assert_eq!(shelf.items_iter().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(shelf.ids_iter().sum::<u32>(), 3);
+    }
+}
+
+mod ffi_records {
+    use foo::foo::bar::{Point, PointFfi, Segment, SegmentFfi};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                record point {
+                    x: s32,
+                    y: s32,
+                }
+
+                type anchor = point
+
+                record segment {
+                    start: anchor,
+                    end: point,
+                    closed: bool,
+                }
+
+                draw: func(s: segment)
+            }
+
+            world ffi-records {
+                import bar
+            }
+        ",
+        ffi_records: ["foo:foo/bar/point", "foo:foo/bar/segment"],
+    });
+
+    #[test]
+    fn run() {
+        let segment = Segment {
+            start: Point { x: 1, y: 2 },
+            end: Point { x: 3, y: 4 },
+            closed: true,
+        };
+        let ffi = SegmentFfi::from(segment);
+        let _: PointFfi = ffi.start;
+        assert_eq!((ffi.start.x, ffi.start.y), (1, 2));
+        assert_eq!((ffi.end.x, ffi.end.y), (3, 4));
+        assert!(ffi.closed);
+        assert_eq!(std::mem::size_of::<SegmentFfi>(), 20);
+
+        let segment = Segment::from(ffi);
+        assert_eq!((segment.end.x, segment.end.y), (3, 4));
+        assert!(segment.closed);
+    }
+}
+
+mod bytes_for_u8_lists {
+    use super::*;
+    use bytes::Bytes;
+    use foo::foo::bar::{self, Packet};
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            interface bar {
+                record packet { payload: list<u8>, lengths: list<u32> }
+
+                send: func(p: packet) -> packet
+            }
+
+            world bytes-for-u8-lists {
+                import bar
+                export run: func(payload: list<u8>, lengths: list<u32>) -> tuple<list<u8>, list<u32>>
+            }
+        ",
+        bytes_for_u8_lists: true,
+    });
+
+    struct MyImports;
+
+    impl bar::Host for MyImports {
+        fn send(&mut self, p: Packet) -> Result<Packet> {
+            Ok(Packet {
+                payload: p.payload.slice(1..),
+                lengths: p.lengths,
+            })
+        }
+    }
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        // `run` passes its arguments on to `send` as the `packet` record, and
+        // its result back, which have the same flat representation.
+        let component = Component::new(
+            &engine,
+            format!(
+                r#"
+                (component
+                    (type $packet' (record
+                        (field "payload" (list u8))
+                        (field "lengths" (list u32))
+                    ))
+                    (import "foo:foo/bar" (instance $bar
+                        (export $packet "packet" (type (eq $packet')))
+                        (export "send" (func (param "p" $packet) (result $packet)))
+                    ))
+                    (core module $libc
+                        (memory (export "memory") 1)
+                        {REALLOC_AND_FREE}
+                    )
+                    (core instance $libc (instantiate $libc))
+                    (core func $send
+                        (canon lower (func $bar "send") (memory $libc "memory")
+                            (realloc (func $libc "realloc")))
+                    )
+                    (core module $m
+                        (import "" "send" (func $send (param i32 i32 i32 i32 i32)))
+                        (import "libc" "realloc" (func $realloc (param i32 i32 i32 i32) (result i32)))
+                        (func (export "run") (param i32 i32 i32 i32) (result i32)
+                            (local $retptr i32)
+                            (local.set $retptr
+                                (call $realloc
+                                    (i32.const 0)
+                                    (i32.const 0)
+                                    (i32.const 4)
+                                    (i32.const 16)))
+                            (call $send
+                                (local.get 0)
+                                (local.get 1)
+                                (local.get 2)
+                                (local.get 3)
+                                (local.get $retptr))
+                            (local.get $retptr)
+                        )
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance (export "send" (func $send))))
+                        (with "libc" (instance $libc))
+                    ))
+
+                    (func (export "run")
+                        (param "payload" (list u8))
+                        (param "lengths" (list u32))
+                        (result (tuple (list u8) (list u32)))
+                        (canon lift (core func $i "run") (memory $libc "memory")
+                            (realloc (func $libc "realloc")))
+                    )
+                )
+            "#
+            ),
+        )?;
+
+        let mut linker = Linker::new(&engine);
+        BytesForU8Lists::add_to_linker(&mut linker, |f: &mut MyImports| f)?;
+        let mut store = Store::new(&engine, MyImports);
+        let (exports, _) = BytesForU8Lists::instantiate(&mut store, &component, &linker)?;
+
+        let (payload, lengths) = exports.call_run(&mut store, b"hello", &[5])?;
+        assert_eq!(payload, Bytes::from_static(b"ello"));
+        assert_eq!(lengths, [5]);
+        Ok(())
+    }
+}
+
+mod bytes_for_u8_lists_borrowing {
+    use bytes::Bytes;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo
+
+            world bytes-for-u8-lists-borrowing {
+                record packet { payload: list<u8>, lengths: list<u32> }
+
+                export send: func(p: packet) -> packet
+            }
+        ",
+        bytes_for_u8_lists: true,
+        ownership: Borrowing {
+            duplicate_if_necessary: true
+        },
+    });
+
+    #[test]
+    fn run() {
+        let owned = PacketResult {
+            payload: Bytes::from_static(b"abc"),
+            lengths: vec![3],
+        };
+        let borrowed = PacketParam::from(&owned);
+        assert_eq!(borrowed.payload, b"abc");
+        assert_eq!(borrowed.lengths, [3]);
+
+        let packet = PacketResult::from(borrowed);
+        assert_eq!(packet.payload, owned.payload);
+        assert_eq!(packet.lengths, owned.lengths);
+    }
+}
@@ -819,6 +819,73 @@ fn strings() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn bytes_lists() -> Result<()> {
+    let component = format!(
+        r#"(component
+            (core module $m
+                (memory (export "memory") 1)
+                (func (export "roundtrip") (param i32 i32) (result i32)
+                    (local $base i32)
+                    (local.set $base
+                        (call $realloc
+                            (i32.const 0)
+                            (i32.const 0)
+                            (i32.const 4)
+                            (i32.const 8)))
+                    (i32.store offset=0
+                        (local.get $base)
+                        (local.get 0))
+                    (i32.store offset=4
+                        (local.get $base)
+                        (local.get 1))
+                    (local.get $base)
+                )
+
+                {REALLOC_AND_FREE}
+            )
+            (core instance $i (instantiate $m))
+
+            (func (export "roundtrip") (param "a" (list u8)) (result (list u8))
+                (canon lift
+                    (core func $i "roundtrip")
+                    (memory $i "memory")
+                    (realloc (func $i "realloc"))
+                )
+            )
+            (func (export "list16") (param "a" (list u16)) (result (list u16))
+                (canon lift
+                    (core func $i "roundtrip")
+                    (memory $i "memory")
+                    (realloc (func $i "realloc"))
+                )
+            )
+        )"#
+    );
+
+    let engine = super::engine();
+    let component = Component::new(&engine, component)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Linker::new(&engine).instantiate(&mut store, &component)?;
+    let roundtrip =
+        instance.get_typed_func::<(bytes::Bytes,), (bytes::Bytes,)>(&mut store, "roundtrip")?;
+
+    for data in [&b""[..], b"foo", b"hello \xff there"] {
+        let ret = roundtrip
+            .call(&mut store, (bytes::Bytes::copy_from_slice(data),))?
+            .0;
+        assert_eq!(ret, data);
+        roundtrip.post_return(&mut store)?;
+    }
+
+    // Only `list<u8>` is represented as `Bytes`.
+    assert!(instance
+        .get_typed_func::<(bytes::Bytes,), (bytes::Bytes,)>(&mut store, "list16")
+        .is_err());
+
+    Ok(())
+}
+
 #[test]
 fn many_parameters() -> Result<()> {
     let component = format!(
@@ -433,6 +433,7 @@ fn flags() -> Result<()> {
         Foo {
             #[component(name = "foo-bar-baz")]
             const A;
+            /// Flags can be documented.
             const B;
             const C;
         }
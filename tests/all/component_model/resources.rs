@@ -37,8 +37,8 @@ fn host_resource_types() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<T>("t", |_, _| {})?;
-    linker.root().resource::<U>("u", |_, _| {})?;
+    linker.root().resource::<T>("t", |_, _| Ok(()))?;
+    linker.root().resource::<U>("u", |_, _| Ok(()))?;
     let i = linker.instantiate(&mut store, &c)?;
     let t1 = i.get_resource(&mut store, "t1").unwrap();
     let t2 = i.get_resource(&mut store, "t2").unwrap();
@@ -367,7 +367,7 @@ fn drop_host_twice() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t", |_, _| {})?;
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
     let i = linker.instantiate(&mut store, &c)?;
     let dtor = i.get_typed_func::<(&Resource<MyType>,), ()>(&mut store, "dtor")?;
 
@@ -383,6 +383,43 @@ fn drop_host_twice() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn host_dtor_error_traps() -> Result<()> {
+    let engine = super::engine();
+    let c = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "t" (type $t (sub resource)))
+
+                (core func $dtor (canon resource.drop $t))
+                (func (export "dtor") (param "x" (own $t))
+                    (canon lift (core func $dtor)))
+            )
+        "#,
+    )?;
+
+    struct MyType;
+
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    linker
+        .root()
+        .resource::<MyType>("t", |_, rep| anyhow::bail!("dtor failed for {rep}"))?;
+    let i = linker.instantiate(&mut store, &c)?;
+    let dtor = i.get_typed_func::<(Resource<MyType>,), ()>(&mut store, "dtor")?;
+
+    let err = dtor
+        .call(&mut store, (Resource::new_own(100),))
+        .unwrap_err();
+    assert!(
+        format!("{err:?}").contains("dtor failed for 100"),
+        "{err:?}"
+    );
+
+    Ok(())
+}
+
 #[test]
 fn manually_destroy() -> Result<()> {
     let engine = super::engine();
@@ -435,6 +472,7 @@ fn manually_destroy() -> Result<()> {
         let data: &mut Data = cx.data_mut();
         data.drops += 1;
         data.last_drop = Some(rep);
+        Ok(())
     })?;
     let i = linker.instantiate(&mut store, &c)?;
     let t2_ctor = i.get_typed_func::<(u32,), (ResourceAny,)>(&mut store, "[constructor]t2")?;
@@ -496,7 +534,7 @@ fn dynamic_type() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t1", |_, _| {})?;
+    linker.root().resource::<MyType>("t1", |_, _| Ok(()))?;
     let i = linker.instantiate(&mut store, &c)?;
 
     let a = i.get_func(&mut store, "a").unwrap();
@@ -547,7 +585,7 @@ fn dynamic_val() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t1", |_, _| {})?;
+    linker.root().resource::<MyType>("t1", |_, _| Ok(()))?;
     let i = linker.instantiate(&mut store, &c)?;
 
     let a = i.get_func(&mut store, "a").unwrap();
@@ -664,7 +702,7 @@ fn active_borrows_at_end_of_call() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t", |_, _| {})?;
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
     let i = linker.instantiate(&mut store, &c)?;
 
     let f = i.get_typed_func::<(&Resource<MyType>,), ()>(&mut store, "f")?;
@@ -719,7 +757,7 @@ fn thread_through_borrow() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t", |_, _| {})?;
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
     linker
         .root()
         .func_wrap("f", |_cx, (r,): (Resource<MyType>,)| {
@@ -763,7 +801,7 @@ fn cannot_use_borrow_for_own() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t", |_, _| {})?;
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
     let i = linker.instantiate(&mut store, &c)?;
 
     let f = i.get_typed_func::<(&Resource<MyType>,), (Resource<MyType>,)>(&mut store, "f")?;
@@ -808,7 +846,7 @@ fn passthrough_wrong_type() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t", |_, _| {})?;
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
     linker
         .root()
         .func_wrap("f", |_cx, (r,): (Resource<MyType>,)| Ok((r,)))?;
@@ -848,7 +886,7 @@ fn pass_moved_resource() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t", |_, _| {})?;
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
     let i = linker.instantiate(&mut store, &c)?;
 
     let f = i.get_typed_func::<(&Resource<MyType>, &Resource<MyType>), ()>(&mut store, "f")?;
@@ -979,7 +1017,7 @@ fn host_borrow_as_resource_any() -> Result<()> {
     // First test the above component where the host properly drops the argument
     {
         let mut linker = Linker::new(&engine);
-        linker.root().resource::<MyType>("t", |_, _| {})?;
+        linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
         linker
             .root()
             .func_wrap("f", |mut cx, (r,): (ResourceAny,)| {
@@ -997,7 +1035,7 @@ fn host_borrow_as_resource_any() -> Result<()> {
     // Then also test the case where the host forgets a drop
     {
         let mut linker = Linker::new(&engine);
-        linker.root().resource::<MyType>("t", |_, _| {})?;
+        linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
         linker.root().func_wrap("f", |_cx, (_r,): (ResourceAny,)| {
             // ... no drop here
             Ok(())
@@ -1106,7 +1144,7 @@ fn pass_host_borrow_to_guest() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t", |_, _| {})?;
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
     let i = linker.instantiate(&mut store, &c)?;
     let take = i.get_typed_func::<(&Resource<MyType>,), ()>(&mut store, "take")?;
 
@@ -1179,7 +1217,7 @@ fn drop_on_owned_resource() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t", |_, _| {})?;
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
     linker.root().func_wrap("[constructor]t", |_cx, ()| {
         Ok((Resource::<MyType>::new_own(300),))
     })?;
@@ -1256,8 +1294,8 @@ fn guest_different_host_same() -> Result<()> {
 
     let mut store = Store::new(&engine, ());
     let mut linker = Linker::new(&engine);
-    linker.root().resource::<MyType>("t1", |_, _| {})?;
-    linker.root().resource::<MyType>("t2", |_, _| {})?;
+    linker.root().resource::<MyType>("t1", |_, _| Ok(()))?;
+    linker.root().resource::<MyType>("t2", |_, _| Ok(()))?;
     linker.root().func_wrap(
         "f",
         |_cx, (r1, r2): (Resource<MyType>, Resource<MyType>)| {
@@ -1282,3 +1320,54 @@ fn guest_different_host_same() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn convert_resource_any() -> Result<()> {
+    let engine = super::engine();
+    let c = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "t" (type $t (sub resource)))
+
+                (core module $m
+                    (func (export "f") (param i32) (result i32)
+                        local.get 0)
+                )
+                (core instance $i (instantiate $m))
+                (func (export "f") (param "x" (own $t)) (result (own $t))
+                    (canon lift (core func $i "f")))
+            )
+        "#,
+    )?;
+
+    struct MyType;
+    struct OtherType;
+
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
+    let i = linker.instantiate(&mut store, &c)?;
+    let f = i.get_typed_func::<(ResourceAny,), (ResourceAny,)>(&mut store, "f")?;
+
+    let any = Resource::<MyType>::new_own(100).try_into_resource_any(&mut store)?;
+    assert!(any.owned());
+    assert_eq!(any.ty(), ResourceType::host::<MyType>());
+    let (any,) = f.call(&mut store, (any,))?;
+    f.post_return(&mut store)?;
+
+    let err = Resource::<OtherType>::try_from_resource_any(any, &mut store).unwrap_err();
+    assert_eq!(err.to_string(), "resource type mismatch");
+
+    let resource: Resource<MyType> = any.try_into_resource(&mut store)?;
+    assert!(resource.owned());
+    assert_eq!(resource.rep(), 100);
+
+    // The handle has moved out of the store's table with the conversion.
+    assert!(Resource::<MyType>::try_from_resource_any(any, &mut store).is_err());
+
+    let borrow = Resource::<MyType>::new_borrow(200);
+    assert!(borrow.try_into_resource_any(&mut store).is_err());
+
+    Ok(())
+}